@@ -0,0 +1,89 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rayon::prelude::*;
+
+use odyssey_randomizer::kingdom::{Kingdoms, KingdomName};
+use odyssey_randomizer::moon::{MoonPool, Moons};
+use odyssey_randomizer::route;
+use odyssey_randomizer::state::State;
+
+fn setup(c: &mut Criterion) {
+    let kingdoms = Kingdoms::new();
+
+    // building the lookups for the moon data, done once per run
+    c.bench_function("Moons::new", |b| b.iter(|| Moons::new(&kingdoms)));
+}
+
+fn hot_paths(c: &mut Criterion) {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    // a route that has just arrived in Cascade, the first kingdom with moons
+    let mut pool = MoonPool::new(&moons);
+    let mut state = State::new();
+    state.add_kingdom_to_schedule(KingdomName::Cascade);
+    state.schedule_kingdom(&kingdoms, &mut rng).unwrap();
+    pool.kingdom_visited(&moons, &state);
+
+    let mut group = c.benchmark_group("return_available");
+    group.bench_function("incremental", |b| {
+        b.iter_batched(|| (pool.clone(), state.clone()),
+                       |(mut pool, mut state)| pool.return_available(&moons, &mut state),
+                       BatchSize::SmallInput)
+    });
+    // the baseline the pool replaced, checking every moon in the kingdom
+    group.bench_function("rescan", |b| {
+        b.iter(|| {
+            moons.iter()
+                .filter(|(id, m)| {
+                    m.kingdom() == state.current_kingdom() && !state.moon_scheduled(*id)
+                        && m.available(&state)
+                })
+                .map(|(id, _)| id)
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+
+    // every moon in the kingdom queued up, so the picks come from a long list
+    let mut queued = state.clone();
+    for (id, moon) in moons.iter() {
+        if moon.kingdom() == KingdomName::Cascade {
+            queued.add_moon_to_schedule(id);
+        }
+    }
+    c.bench_function("schedule_moon", |b| {
+        b.iter_batched(|| queued.clone(),
+                       |mut state| {
+                           while state.schedule_moon(&moons, &mut rng).is_ok() {}
+                           state
+                       },
+                       BatchSize::SmallInput)
+    });
+}
+
+fn generate(c: &mut Criterion) {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let pool = MoonPool::new(&moons);
+    let state = State::new();
+
+    c.bench_function("generate 1 route", |b| {
+        let mut rng = StdRng::seed_from_u64(0);
+        b.iter(|| route::generate(&kingdoms, &moons, pool.clone(), state.clone(),
+                                  &mut rng))
+    });
+
+    // a batch of routes, as generated by stats and --best-of
+    c.bench_function("generate 100 routes", |b| {
+        b.iter(|| {
+            route::generate_many(&kingdoms, &moons, &pool, &state, 0, 100)
+                .count()
+        })
+    });
+}
+
+criterion_group!(benches, setup, hot_paths, generate);
+criterion_main!(benches);
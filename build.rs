@@ -0,0 +1,134 @@
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+// the data file parser is shared with the loader for custom data
+#[allow(dead_code)]
+#[path = "src/data.rs"]
+mod data;
+#[allow(dead_code)]
+#[path = "src/error.rs"]
+mod error;
+
+use data::{Data, RequirementData};
+
+const DATA: &str = "data/odyssey.txt";
+
+fn main() {
+    println!("cargo:rerun-if-changed={}", DATA);
+    println!("cargo:rerun-if-changed=src/data.rs");
+    println!("cargo:rerun-if-changed=src/error.rs");
+    let data = match data::read(DATA) {
+        Ok(data) => data,
+        Err(e) => panic!("{}", e),
+    };
+
+    let out = env::var("OUT_DIR").unwrap();
+    fs::write(Path::new(&out).join("kingdoms.rs"), kingdoms(&data)).unwrap();
+    fs::write(Path::new(&out).join("moons.rs"), moons(&data)).unwrap();
+}
+
+fn kingdoms(data: &Data) -> String {
+    let mut s = String::new();
+    writeln!(s, "static KINGDOMS: [Kingdom; {}] = [", data.kingdoms.len()).unwrap();
+    for k in &data.kingdoms {
+        writeln!(s, "    Kingdom {{").unwrap();
+        writeln!(s, "        name: Cow::Borrowed({:?}),", k.name).unwrap();
+        writeln!(s, "        moons_to_leave: {},", k.moons_to_leave).unwrap();
+        writeln!(s, "        moons_to_unlock: {},", k.moons_to_unlock).unwrap();
+        writeln!(s, "        coin_income: {},", k.coin_income).unwrap();
+        writeln!(s, "        regional_coins: {},", k.regional_coins).unwrap();
+        writeln!(s, "        prerequisite_kingdoms: Cow::Borrowed(&[{}]),",
+                 list(&k.prerequisites, kingdom)).unwrap();
+        writeln!(s, "        next_kingdoms: Cow::Borrowed(&[{}]),",
+                 list(&k.next, kingdom)).unwrap();
+        writeln!(s, "        exit_moon: {:?},", k.exit_moon).unwrap();
+        writeln!(s, "        phase_moons: Cow::Borrowed(&{:?}),", k.phase_moons).unwrap();
+        writeln!(s, "    }},").unwrap();
+    }
+    writeln!(s, "];").unwrap();
+
+    // the table is indexed by kingdom, so it has to follow KingdomName
+    for (i, k) in data.kingdoms.iter().enumerate() {
+        writeln!(s, "const _: () = assert!(KingdomName::{} as usize == {}, {:?});",
+                 k.key, i, format!("{}:{}: kingdom {} is out of order",
+                                   DATA, k.line, k.key)).unwrap();
+    }
+    s
+}
+
+fn moons(data: &Data) -> String {
+    let mut s = String::new();
+    writeln!(s, "static MOONS: [Moon; {}] = [", data.moons.len()).unwrap();
+    for m in &data.moons {
+        writeln!(s, "    Moon {{").unwrap();
+        writeln!(s, "        name: Cow::Borrowed({:?}),", m.name).unwrap();
+        writeln!(s, "        count: {},", m.count).unwrap();
+        writeln!(s, "        kingdom: {},", kingdom(&m.kingdom)).unwrap();
+        writeln!(s, "        prerequisites: Cow::Borrowed(&[{}]),",
+                 list(&m.prerequisites, requirement)).unwrap();
+        writeln!(s, "        achievement: {},", m.achievement).unwrap();
+        writeln!(s, "        advances_phase: {},", m.advances_phase).unwrap();
+        writeln!(s, "        captures: Cow::Borrowed(&[{}]),",
+                 list(&m.captures, |c| format!("Capture::{}", c))).unwrap();
+        writeln!(s, "        coin_cost: {},", m.coin_cost).unwrap();
+        writeln!(s, "        breaks_moon_rock: {},", m.breaks_moon_rock).unwrap();
+        match &m.hint_art {
+            Some((k, location)) => {
+                writeln!(s, "        hint_art: Some(HintArt::new({}, Cow::Borrowed({:?}))),",
+                         kingdom(k), location).unwrap();
+            }
+            None => writeln!(s, "        hint_art: None,").unwrap(),
+        }
+        writeln!(s, "        tags: Cow::Borrowed(&[{}]),",
+                 list(&m.tags, |t| format!("Tag::{}", t))).unwrap();
+        writeln!(s, "        difficulty: {},", m.difficulty).unwrap();
+        writeln!(s, "        time: {},", m.time).unwrap();
+        writeln!(s, "    }},").unwrap();
+    }
+    writeln!(s, "];").unwrap();
+    writeln!(s, "static OFFSET: [(usize, usize); {}] = {:?};",
+             data.offset.len(), data.offset).unwrap();
+    s
+}
+
+fn kingdom(key: &String) -> String {
+    format!("KingdomName::{}", key)
+}
+
+fn requirement(r: &RequirementData) -> String {
+    match r {
+        RequirementData::Moon(m) => format!("Requirement::Moon({})", m),
+        RequirementData::Kingdom(k, c) => {
+            format!("Requirement::Kingdom({}, {})", kingdom(k), c)
+        }
+        RequirementData::MoonCount(c) => format!("Requirement::MoonCount({})", c),
+        RequirementData::CompletedMainGame => {
+            String::from("Requirement::Flag(Flag::CompletedMainGame)")
+        }
+        RequirementData::MoonRockBroken(k) => {
+            format!("Requirement::Flag(Flag::MoonRockBroken({}))", kingdom(k))
+        }
+        RequirementData::PhaseAtLeast(k, p) => {
+            format!("Requirement::PhaseAtLeast({}, {})", kingdom(k), p)
+        }
+        RequirementData::PhaseOnly(k, p) => {
+            format!("Requirement::PhaseOnly({}, {})", kingdom(k), p)
+        }
+        RequirementData::Capture(c) => format!("Requirement::Capture(Capture::{})", c),
+        RequirementData::Outfit(o) => format!("Requirement::Outfit(Outfit::{})", o),
+        RequirementData::HintArt(k) => format!("Requirement::HintArt({})", kingdom(k)),
+        RequirementData::Tagged(t, c) => format!("Requirement::Tagged(Tag::{}, {})", t, c),
+        RequirementData::All(r) => {
+            format!("Requirement::All(Cow::Borrowed(&[{}]))", list(r, requirement))
+        }
+        RequirementData::Any(r) => {
+            format!("Requirement::Any(Cow::Borrowed(&[{}]))", list(r, requirement))
+        }
+    }
+}
+
+fn list<T, F: Fn(&T) -> String>(items: &[T], f: F) -> String {
+    items.iter().map(f).collect::<Vec<String>>().join(", ")
+}
@@ -0,0 +1,565 @@
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::error::RandomizerError;
+use crate::estimate::{format_duration, Estimates};
+use crate::kingdom::{Kingdoms, KingdomName};
+use crate::moon::{MoonID, Moons};
+use crate::requirement::{Flag, Requirement};
+
+// boards that fail a line are thrown away and drawn again this many times
+const ATTEMPTS: usize = 2000;
+// the widest a cell gets in the printed grid
+const CELL_WIDTH: usize = 18;
+
+// the kind of challenge a moon is, going by its name, so a board doesn't end
+// up as five timer challenges
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Kind {
+    Shop,
+    Timer,
+    Notes,
+    Shards,
+    Peach,
+    CaptainToad,
+    Treasure,
+    Gardening,
+    Race,
+    Hopping,
+    SecretPath,
+    HatAndSeek,
+    Minigame,
+    Other,
+}
+
+impl Kind {
+    pub fn of(name: &str) -> Kind {
+        let patterns = [
+            ("Shopping in", Kind::Shop),
+            ("Timer Challenge", Kind::Timer),
+            ("Taking Notes", Kind::Notes),
+            ("Moon Shards", Kind::Shards),
+            ("Peach in", Kind::Peach),
+            ("Captain Toad", Kind::CaptainToad),
+            ("Treasure", Kind::Treasure),
+            ("Gardening", Kind::Gardening),
+            ("Regular Cup", Kind::Race),
+            ("Master Cup", Kind::Race),
+            ("Caught Hopping", Kind::Hopping),
+            ("Rabbit", Kind::Hopping),
+            ("Secret Path", Kind::SecretPath),
+            ("Hat-and-Seek", Kind::HatAndSeek),
+            ("Picture Match", Kind::Minigame),
+            ("Slots", Kind::Minigame),
+            ("Quiz", Kind::Minigame),
+            ("Volleyball", Kind::Minigame),
+            ("Jump-Rope", Kind::Minigame),
+        ];
+        patterns.iter()
+            .find(|(p, _)| name.contains(p))
+            .map(|(_, k)| *k)
+            .unwrap_or(Kind::Other)
+    }
+}
+
+// a rough time to get a set of moons from a new file, following the story
+// only as far as the moons need it
+pub struct Timings {
+    travel: u32,
+    times: Vec<u32>,
+    kingdom: Vec<KingdomName>,
+    // the earliest each kingdom can be reached
+    arrival: HashMap<KingdomName, u32>,
+    // each moon and every moon it needs before it
+    closure: Vec<Vec<MoonID>>,
+    // the earliest each moon can be done for reasons other than moons, such
+    // as finishing the story or a total moon count
+    gate: Vec<u32>,
+}
+
+impl Timings {
+    pub fn new(kingdoms: &Kingdoms, moons: &Moons,
+               estimates: &Estimates) -> Result<Self, RandomizerError> {
+        let mut timings = Timings {
+            travel: estimates.travel(),
+            times: moons.iter()
+                .map(|(id, _)| estimates.moon(id))
+                .collect::<Result<_, _>>()?,
+            kingdom: moons.iter().map(|(_, m)| m.kingdom()).collect(),
+            arrival: HashMap::new(),
+            closure: Vec::new(),
+            gate: Vec::new(),
+        };
+        for (id, _) in moons.iter() {
+            let mut closure = Vec::new();
+            timings.collect(kingdoms, moons, id, &mut closure)?;
+            closure.sort_unstable();
+            closure.dedup();
+            timings.closure.push(closure);
+        }
+
+        // kingdoms come in story order, so every kingdom leading to one has
+        // its time by the time it is reached
+        let mut done = HashMap::new();
+        for k in KingdomName::ALL.iter() {
+            let kingdom = kingdoms.kingdom(*k)?;
+            let mut arrival = 0;
+            for p in kingdom.prerequisites() {
+                arrival = arrival.max(timings.arrival(*p)?);
+            }
+            let from: Option<u32> = kingdoms.iter()
+                .filter(|(_, p)| p.next().contains(k))
+                .filter_map(|(p, _)| done.get(&p).copied())
+                .min();
+            arrival = match from {
+                Some(from) => arrival.max(from),
+                // kingdoms nothing leads to open up after the story
+                None if *k != KingdomName::Cap => {
+                    arrival.max(timings.arrival(KingdomName::Mushroom)?)
+                }
+                None => arrival,
+            };
+            arrival = arrival.max(timings.collect_any(moons, kingdom.moons_to_unlock()));
+            if *k != KingdomName::Cap {
+                arrival += timings.travel;
+            }
+            timings.arrival.insert(*k, arrival);
+            done.insert(*k, arrival + timings.leave(kingdoms, moons, *k)?);
+        }
+
+        for (_, moon) in moons.iter() {
+            let mut gate = timings.coin_gate(kingdoms, moon.coin_cost())?;
+            for r in moon.prerequisites() {
+                gate = gate.max(timings.requirement_gate(kingdoms, moons, r, &done)?);
+            }
+            timings.gate.push(gate);
+        }
+        Ok(timings)
+    }
+
+    // how long it takes to get every moon in a line, from a new file
+    pub fn line(&self, line: &[MoonID]) -> u32 {
+        let mut needed: Vec<MoonID> = line.iter()
+            .flat_map(|m| self.closure[*m].iter().copied())
+            .collect();
+        needed.sort_unstable();
+        needed.dedup();
+        let mut kingdoms: Vec<KingdomName> = needed.iter()
+            .map(|m| self.kingdom[*m])
+            .collect();
+        kingdoms.sort_by_key(|k| *k as usize);
+        kingdoms.dedup();
+
+        // be in the latest kingdom, then pick up everything else on the way,
+        // new gives every kingdom an arrival so these are always there
+        let start = kingdoms.iter()
+            .map(|k| self.arrival[k])
+            .chain(line.iter().map(|m| self.gate[*m]))
+            .max()
+            .unwrap_or(0);
+        let collecting: u32 = needed.iter().map(|m| self.times[*m]).sum();
+        start + collecting + self.travel * kingdoms.len().saturating_sub(1) as u32
+    }
+
+    fn collect(&self, kingdoms: &Kingdoms, moons: &Moons, id: MoonID,
+               closure: &mut Vec<MoonID>) -> Result<(), RandomizerError> {
+        if closure.contains(&id) {
+            return Ok(());
+        }
+        closure.push(id);
+        for r in moons.moon(id)?.prerequisites() {
+            self.require(kingdoms, moons, r, closure)?;
+        }
+        Ok(())
+    }
+
+    fn require(&self, kingdoms: &Kingdoms, moons: &Moons, r: &Requirement,
+               closure: &mut Vec<MoonID>) -> Result<(), RandomizerError> {
+        // the moons a requirement needs, through its phase, captures and
+        // moon rock
+        let needed: Vec<MoonID> = match r {
+            Requirement::Moon(m) => vec![*m],
+            Requirement::PhaseAtLeast(k, p) => {
+                let phases = kingdoms.kingdom(*k)?.phase_moons();
+                phases[..std::cmp::min(*p as usize, phases.len())].to_vec()
+            }
+            Requirement::Capture(c) => moons.iter()
+                .find(|(_, m)| m.captures().contains(c))
+                .map(|(m, _)| m)
+                .into_iter()
+                .collect(),
+            Requirement::Flag(Flag::MoonRockBroken(k)) => moons.iter()
+                .find(|(_, m)| m.kingdom() == *k && m.breaks_moon_rock())
+                .map(|(m, _)| m)
+                .into_iter()
+                .collect(),
+            Requirement::All(r) => {
+                for r in r.iter() {
+                    self.require(kingdoms, moons, r, closure)?;
+                }
+                return Ok(());
+            }
+            // the branch whose moons take the least time, as a runner would
+            Requirement::Any(r) => {
+                let added = |c: &[MoonID]| -> u32 {
+                    c[closure.len()..].iter().map(|m| self.times[*m]).sum()
+                };
+                let mut quickest: Option<Vec<MoonID>> = None;
+                for r in r.iter() {
+                    let mut branch = closure.clone();
+                    self.require(kingdoms, moons, r, &mut branch)?;
+                    if quickest.as_ref().is_none_or(|q| added(&branch) < added(q)) {
+                        quickest = Some(branch);
+                    }
+                }
+                if let Some(q) = quickest {
+                    *closure = q;
+                }
+                return Ok(());
+            }
+            Requirement::Kingdom(..) | Requirement::MoonCount(_)
+            | Requirement::Flag(Flag::CompletedMainGame) | Requirement::PhaseOnly(..)
+            | Requirement::Outfit(_) | Requirement::HintArt(_)
+            | Requirement::Tagged(..) => Vec::new(),
+        };
+        for m in needed {
+            self.collect(kingdoms, moons, m, closure)?;
+        }
+        Ok(())
+    }
+
+    fn arrival(&self, k: KingdomName) -> Result<u32, RandomizerError> {
+        // kingdoms are timed in story order, so one needing a later kingdom
+        // has nothing to go on
+        self.arrival.get(&k).copied().ok_or_else(|| RandomizerError::UnsatisfiableData(
+            format!("{:?} is needed before it can be reached", k)))
+    }
+
+    fn leave(&self, kingdoms: &Kingdoms, moons: &Moons,
+             k: KingdomName) -> Result<u32, RandomizerError> {
+        // the exit moon and what it needs, then the quickest moons in the
+        // kingdom that need nothing else up to the count to leave
+        let kingdom = kingdoms.kingdom(k)?;
+        let needed: Vec<MoonID> = match kingdom.exit_moon() {
+            Some(exit) => self.closure[exit].clone(),
+            None => Vec::new(),
+        };
+        let mut count: u16 = 0;
+        for m in &needed {
+            count += moons.moon(*m)?.count();
+        }
+        let mut time: u32 = needed.iter().map(|m| self.times[*m]).sum();
+        let mut spare: Vec<(MoonID, u16)> = moons.iter()
+            .filter(|(id, m)| {
+                m.kingdom() == k && self.closure[*id].len() == 1 && !needed.contains(id)
+                    && m.prerequisites().iter().all(|r| {
+                        r.flags().next().is_none() && r.moon_counts().next().is_none()
+                            && r.tags().next().is_none()
+                    })
+            })
+            .map(|(id, m)| (id, m.count()))
+            .collect();
+        spare.sort_by_key(|(m, _)| self.times[*m]);
+        for (m, c) in spare {
+            if count >= kingdom.moons_to_leave() {
+                break;
+            }
+            count += c;
+            time += self.times[m];
+        }
+        Ok(time)
+    }
+
+    fn collect_any(&self, moons: &Moons, count: u16) -> u32 {
+        // the quickest moons anywhere, for requirements on a moon total
+        let mut quickest: Vec<(MoonID, u16)> = moons.iter()
+            .filter(|(_, m)| m.count() > 0 && !m.achievement())
+            .map(|(id, m)| (id, m.count()))
+            .collect();
+        quickest.sort_by_key(|(m, _)| self.times[*m]);
+        let mut collected = 0;
+        let mut time = 0;
+        for (m, c) in quickest {
+            if collected >= count {
+                break;
+            }
+            collected += c;
+            time += self.times[m];
+        }
+        time
+    }
+
+    fn coin_gate(&self, kingdoms: &Kingdoms, cost: u16) -> Result<u32, RandomizerError> {
+        // the first arrival by which the kingdoms reached so far have paid
+        // for a shop moon
+        if cost == 0 {
+            return Ok(0);
+        }
+        let mut reached: Vec<(u32, u16)> = Vec::new();
+        for (k, kingdom) in kingdoms.iter() {
+            reached.push((self.arrival(k)?, kingdom.coin_income()));
+        }
+        reached.sort_unstable();
+        let mut coins = 0;
+        for (arrival, income) in reached {
+            coins += u32::from(income);
+            if coins >= u32::from(cost) {
+                return Ok(arrival);
+            }
+        }
+        Err(RandomizerError::UnsatisfiableData(
+            format!("no route earns the {} coins a moon costs", cost)))
+    }
+
+    fn visit_gate(&self, k: KingdomName, visits: u32,
+                  done: &HashMap<KingdomName, u32>) -> Result<u32, RandomizerError> {
+        if visits <= 1 {
+            return self.arrival(k);
+        }
+        // coming back means leaving first
+        let done = done.get(&k).copied().ok_or_else(|| RandomizerError::UnsatisfiableData(
+            format!("{:?} is needed before it can be left", k)))?;
+        Ok(done + 2 * self.travel * (visits - 1))
+    }
+
+    fn requirement_gate(&self, kingdoms: &Kingdoms, moons: &Moons, r: &Requirement,
+                        done: &HashMap<KingdomName, u32>) -> Result<u32, RandomizerError> {
+        let mushroom = self.arrival(KingdomName::Mushroom)?;
+        let gate = match r {
+            // the moon itself is in the closure, with its time
+            Requirement::Moon(_) => 0,
+            Requirement::Kingdom(k, c) => self.visit_gate(*k, u32::from(*c), done)?,
+            Requirement::MoonCount(c) => self.collect_any(moons, *c),
+            Requirement::Flag(Flag::CompletedMainGame) => mushroom,
+            // the moon breaking the rock is in the closure, but the rock only
+            // breaks on a visit after the story
+            Requirement::Flag(Flag::MoonRockBroken(k)) | Requirement::HintArt(k) => {
+                mushroom.max(self.arrival(*k)?) + self.travel
+            }
+            // the phase moons are in the closure, and a moon for a single
+            // phase is done on the way through it
+            Requirement::PhaseAtLeast(k, _) | Requirement::PhaseOnly(k, _) => {
+                self.arrival(*k)?
+            }
+            // the kingdom the capture is first met in
+            Requirement::Capture(c) => {
+                let mut first = None;
+                for (_, m) in moons.iter().filter(|(_, m)| m.captures().contains(c)) {
+                    let arrival = self.arrival(m.kingdom())?;
+                    first = Some(first.map_or(arrival, |f: u32| f.min(arrival)));
+                }
+                first.ok_or_else(|| RandomizerError::UnsatisfiableData(
+                    format!("nothing teaches the {:?} capture", c)))?
+            }
+            // enough visits to the kingdom for its regional coins to pay
+            Requirement::Outfit(o) => {
+                let per_visit = kingdoms.kingdom(o.kingdom())?.regional_coins_per_visit();
+                if per_visit == 0 {
+                    return Err(RandomizerError::UnsatisfiableData(
+                        format!("{} can't be bought with regional coins", o.name())));
+                }
+                let visits = o.cost().div_ceil(per_visit);
+                self.visit_gate(o.kingdom(), u32::from(visits), done)?
+            }
+            Requirement::Tagged(t, c) => {
+                let mut tagged: Vec<u32> = moons.iter()
+                    .filter(|(_, m)| m.tags().contains(t))
+                    .map(|(id, _)| self.times[id])
+                    .collect();
+                tagged.sort_unstable();
+                tagged.iter().take(*c as usize).sum()
+            }
+            Requirement::All(r) => {
+                let mut gate = 0;
+                for r in r.iter() {
+                    gate = gate.max(self.requirement_gate(kingdoms, moons, r, done)?);
+                }
+                gate
+            }
+            Requirement::Any(r) => {
+                let mut gate = None;
+                for r in r.iter() {
+                    let g = self.requirement_gate(kingdoms, moons, r, done)?;
+                    gate = Some(gate.map_or(g, |f: u32| f.min(g)));
+                }
+                gate.unwrap_or(0)
+            }
+        };
+        Ok(gate)
+    }
+}
+
+pub struct Board {
+    size: usize,
+    target: u32,
+    moons: Vec<MoonID>,
+}
+
+impl Board {
+    pub fn generate<R: Rng + ?Sized>(kingdoms: &Kingdoms, moons: &Moons,
+                                     estimates: &Estimates, size: usize, target: u32,
+                                     rng: &mut R) -> Result<Self, RandomizerError> {
+        let timings = Timings::new(kingdoms, moons, estimates)?;
+        // moons that can be done on their own in time, leaving out the
+        // achievements and route steps that aren't moons
+        let candidates: Vec<(MoonID, KingdomName, Kind)> = moons.iter()
+            .filter(|(id, m)| {
+                m.count() > 0 && !m.achievement() && timings.line(&[*id]) <= target
+            })
+            .map(|(id, m)| (id, m.kingdom(), Kind::of(m.name())))
+            .collect();
+
+        // spread the board over the kingdoms and kinds there are, with no more
+        // than half of it left to moons with no kind
+        let cells = size * size;
+        let mut kingdom_count: Vec<KingdomName> = candidates.iter()
+            .map(|(_, k, _)| *k)
+            .collect();
+        kingdom_count.sort_by_key(|k| *k as usize);
+        kingdom_count.dedup();
+        let per_kingdom = cells.div_ceil(kingdom_count.len().max(1));
+        let per_kind = std::cmp::max(1, cells / 8);
+
+        // cells are filled in order, each with a moon that keeps the part of
+        // every line through it in time, since a line only gets slower as it
+        // fills up, and the board starts over when a cell can't be filled
+        'attempt: for _ in 0..ATTEMPTS {
+            let mut shuffled = candidates.clone();
+            shuffled.shuffle(rng);
+            let mut picked: Vec<MoonID> = Vec::new();
+            let mut in_kingdom: HashMap<KingdomName, usize> = HashMap::new();
+            let mut of_kind: HashMap<Kind, usize> = HashMap::new();
+            while picked.len() < cells {
+                let cell = picked.len();
+                let fits = |(m, kingdom, kind): (MoonID, KingdomName, Kind), picked: &[MoonID]| {
+                    let kind_limit = if kind == Kind::Other { cells / 2 } else { per_kind };
+                    in_kingdom.get(&kingdom).copied().unwrap_or(0) < per_kingdom
+                        && of_kind.get(&kind).copied().unwrap_or(0) < kind_limit
+                        && through(size, cell).iter().all(|line| {
+                            let mut line: Vec<MoonID> = line.iter()
+                                .filter(|c| **c < cell)
+                                .map(|c| picked[*c])
+                                .collect();
+                            line.push(m);
+                            timings.line(&line) <= target
+                        })
+                };
+                let position = match shuffled.iter().position(|m| fits(*m, &picked)) {
+                    Some(position) => position,
+                    None => continue 'attempt,
+                };
+                let (m, kingdom, kind) = shuffled.swap_remove(position);
+                *in_kingdom.entry(kingdom).or_insert(0) += 1;
+                *of_kind.entry(kind).or_insert(0) += 1;
+                picked.push(m);
+            }
+            return Ok(Board {
+                size,
+                target,
+                moons: picked,
+            });
+        }
+        Err(RandomizerError::UnsatisfiableData(
+            format!("no {}x{} bingo board has every line within {}", size, size,
+                    format_duration(target))))
+    }
+
+    // every row, column and both diagonals
+    pub fn lines(&self) -> Vec<Vec<MoonID>> {
+        let n = self.size;
+        let mut lines = Vec::new();
+        for x in 0..n {
+            lines.push((0..n).map(|y| self.moons[x * n + y]).collect());
+            lines.push((0..n).map(|y| self.moons[y * n + x]).collect());
+        }
+        lines.push((0..n).map(|x| self.moons[x * n + x]).collect());
+        lines.push((0..n).map(|x| self.moons[x * n + n - 1 - x]).collect());
+        lines
+    }
+
+    pub fn moons(&self) -> &[MoonID] {
+        &self.moons
+    }
+
+    // the goal list bingo sites take for a custom board, row by row
+    pub fn json(&self, moons: &Moons) -> Result<String, RandomizerError> {
+        let mut goals = Vec::new();
+        for m in &self.moons {
+            goals.push(format!("  {{\"name\": \"{}\"}}", escape(&goal(moons, *m)?)));
+        }
+        Ok(format!("[\n{}\n]\n", goals.join(",\n")))
+    }
+
+    pub fn print(&self, moons: &Moons) -> Result<(), RandomizerError> {
+        println!("Every line within {}", format_duration(self.target));
+        let border = format!("+{}", format!("{}+", "-".repeat(CELL_WIDTH + 2))
+                             .repeat(self.size));
+        println!("{}", border);
+        for row in self.moons.chunks(self.size) {
+            let mut cells: Vec<Vec<String>> = Vec::new();
+            for m in row {
+                cells.push(wrap(&goal(moons, *m)?, CELL_WIDTH));
+            }
+            let height = cells.iter().map(|c| c.len()).max().unwrap_or(0);
+            for y in 0..height {
+                let line: Vec<String> = cells.iter()
+                    .map(|c| format!(" {:<width$} ", c.get(y).map(|s| s.as_str())
+                                     .unwrap_or(""), width = CELL_WIDTH))
+                    .collect();
+                println!("|{}|", line.join("|"));
+            }
+            println!("{}", border);
+        }
+        Ok(())
+    }
+}
+
+fn through(size: usize, cell: usize) -> Vec<Vec<usize>> {
+    // the cells of every line a cell is on
+    let (row, column) = (cell / size, cell % size);
+    let mut lines = vec![
+        (0..size).map(|x| row * size + x).collect(),
+        (0..size).map(|y| y * size + column).collect(),
+    ];
+    if row == column {
+        lines.push((0..size).map(|x| x * size + x).collect());
+    }
+    if row + column == size - 1 {
+        lines.push((0..size).map(|x| x * size + size - 1 - x).collect());
+    }
+    lines
+}
+
+fn goal(moons: &Moons, id: MoonID) -> Result<String, RandomizerError> {
+    // moon names repeat between kingdoms, so the kingdom goes with them
+    let moon = moons.moon(id)?;
+    Ok(format!("{} ({:?})", moon.name(), moon.kingdom()))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    // words too long for a line are broken up
+    let mut words = Vec::new();
+    for word in text.split_whitespace() {
+        let chars: Vec<char> = word.chars().collect();
+        for chunk in chars.chunks(width) {
+            words.push(chunk.iter().collect::<String>());
+        }
+    }
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(&word);
+            }
+            _ => lines.push(word),
+        }
+    }
+    lines
+}
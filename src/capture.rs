@@ -0,0 +1,31 @@
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Capture {
+    Glydon,
+    Gushen,
+    Pokio,
+    Uproot,
+}
+
+impl Capture {
+    pub const ALL: [Capture; 4] = [
+        Capture::Glydon,
+        Capture::Gushen,
+        Capture::Pokio,
+        Capture::Uproot,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Capture> {
+        Capture::ALL.iter()
+            .find(|c| format!("{:?}", c).eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Capture::Glydon => "Glydon",
+            Capture::Gushen => "Gushen",
+            Capture::Pokio => "Pokio",
+            Capture::Uproot => "Uproot",
+        }
+    }
+}
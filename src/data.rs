@@ -0,0 +1,484 @@
+// the kingdom and moon data file, read by the build script to compile the
+// static tables and by the loader for custom data
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::error::RandomizerError;
+
+// seconds it takes to collect a moon of each difficulty
+const DIFFICULTY_TIME: [u32; 5] = [60, 180, 300, 480, 900];
+
+pub struct Data {
+    pub path: String,
+    pub kingdoms: Vec<KingdomData>,
+    pub moons: Vec<MoonData>,
+    // the moons in each kingdom, in the order the kingdoms are defined
+    pub offset: Vec<(usize, usize)>,
+}
+
+pub struct KingdomData {
+    pub line: usize,
+    pub key: String,
+    pub name: String,
+    pub moons_to_leave: u16,
+    pub moons_to_unlock: u16,
+    pub coin_income: u16,
+    pub regional_coins: u16,
+    pub prerequisites: Vec<String>,
+    pub next: Vec<String>,
+    pub exit_moon: Option<usize>,
+    pub phase_moons: Vec<usize>,
+}
+
+pub struct MoonData {
+    pub line: usize,
+    pub key: String,
+    pub name: String,
+    pub kingdom: String,
+    pub count: u16,
+    pub prerequisites: Vec<RequirementData>,
+    pub achievement: bool,
+    pub advances_phase: bool,
+    pub captures: Vec<String>,
+    pub coin_cost: u16,
+    pub breaks_moon_rock: bool,
+    // the kingdom the art hangs in and where
+    pub hint_art: Option<(String, String)>,
+    pub tags: Vec<String>,
+    pub difficulty: u8,
+    pub time: u32,
+}
+
+// a requirement with its kingdoms, captures, outfits and tags still named
+pub enum RequirementData {
+    Moon(usize),
+    Kingdom(String, u8),
+    MoonCount(u16),
+    CompletedMainGame,
+    MoonRockBroken(String),
+    PhaseAtLeast(String, u8),
+    PhaseOnly(String, u8),
+    Capture(String),
+    Outfit(String),
+    HintArt(String),
+    Tagged(String, u16),
+    All(Vec<RequirementData>),
+    Any(Vec<RequirementData>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+    Open,
+    Close,
+}
+
+struct Block {
+    line: usize,
+    header: Vec<Token>,
+    directives: Vec<(usize, Vec<Token>)>,
+}
+
+type Error = (usize, String);
+
+impl Data {
+    pub fn error(&self, line: usize, message: String) -> RandomizerError {
+        RandomizerError::Parse {
+            path: self.path.clone(),
+            line,
+            message,
+        }
+    }
+}
+
+pub fn read(path: &str) -> Result<Data, RandomizerError> {
+    let text = fs::read_to_string(path).map_err(|e| RandomizerError::Io {
+        path: String::from(path),
+        message: e.to_string(),
+    })?;
+    parse(path, &text).map_err(|(line, message)| RandomizerError::Parse {
+        path: String::from(path),
+        line,
+        message,
+    })
+}
+
+fn parse(path: &str, text: &str) -> Result<Data, Error> {
+    // split the file into blocks, each a header line and the indented
+    // directives under it
+    let mut blocks: Vec<Block> = Vec::new();
+    for (i, l) in text.lines().enumerate() {
+        let line = i + 1;
+        let tokens = tokenize(l).map_err(|e| (line, e))?;
+        if tokens.is_empty() {
+            continue;
+        }
+        if l.starts_with(char::is_whitespace) {
+            match blocks.last_mut() {
+                Some(b) => b.directives.push((line, tokens)),
+                None => return Err((line, String::from("expected a kingdom or moon"))),
+            }
+        } else {
+            blocks.push(Block { line, header: tokens, directives: Vec::new() });
+        }
+    }
+
+    // every kingdom and moon is named first, so they can be referred to
+    // before they are defined
+    let mut kingdom_keys = Vec::new();
+    let mut moon_ids = HashMap::new();
+    for b in &blocks {
+        let key = word(&b.header, 1, b.line)?;
+        if word(&b.header, 0, b.line)? == "kingdom" {
+            if kingdom_keys.contains(&key) {
+                return Err((b.line, format!("kingdom {} is defined twice", key)));
+            }
+            kingdom_keys.push(key);
+        } else if moon_ids.insert(key.clone(), moon_ids.len()).is_some() {
+            return Err((b.line, format!("moon {} is defined twice", key)));
+        }
+    }
+    let names = Names { kingdoms: &kingdom_keys, moons: &moon_ids };
+
+    let mut kingdoms = Vec::new();
+    let mut moons = Vec::new();
+    for b in &blocks {
+        if word(&b.header, 0, b.line)? == "kingdom" {
+            kingdoms.push(parse_kingdom(b, &names)?);
+        } else {
+            moons.push(parse_moon(b, &names)?);
+        }
+    }
+
+    // the moons of each kingdom are numbered together, kingdom by kingdom
+    let mut order: Vec<usize> = Vec::new();
+    for moon in &moons {
+        let k = names.kingdom(&moon.kingdom, moon.line)?;
+        if order.last().is_some_and(|last| k < *last) {
+            return Err((moon.line, format!(
+                "moon {} is not with the other moons of its kingdom", moon.key)));
+        }
+        order.push(k);
+    }
+    let offset = (0..kingdoms.len())
+        .map(|k| {
+            (order.iter().filter(|m| **m < k).count(),
+             order.iter().filter(|m| **m <= k).count())
+        })
+        .collect();
+
+    // mark the moons that move each kingdom on to its next phase, and make
+    // sure moons only available in a phase are taken before that
+    for k in &kingdoms {
+        for m in &k.phase_moons {
+            moons[*m].advances_phase = true;
+        }
+    }
+    for id in 0..moons.len() {
+        let mut only = Vec::new();
+        for r in &moons[id].prerequisites {
+            phase_only(r, &mut only);
+        }
+        for (k, p) in only {
+            let k = names.kingdom(&k, moons[id].line)?;
+            if let Some(m) = kingdoms[k].phase_moons.get(p as usize) {
+                moons[*m].prerequisites.push(RequirementData::Moon(id));
+            }
+        }
+    }
+
+    Ok(Data {
+        path: String::from(path),
+        kingdoms,
+        moons,
+        offset,
+    })
+}
+
+fn phase_only(r: &RequirementData, only: &mut Vec<(String, u8)>) {
+    // phase-only anywhere in a requirement, even in one branch of an any, as
+    // the other branches may never be met once the phase has moved on
+    match r {
+        RequirementData::PhaseOnly(k, p) => only.push((k.clone(), *p)),
+        RequirementData::All(r) | RequirementData::Any(r) => {
+            for rr in r {
+                phase_only(rr, only);
+            }
+        }
+        _ => {}
+    }
+}
+
+struct Names<'a> {
+    kingdoms: &'a [String],
+    moons: &'a HashMap<String, usize>,
+}
+
+impl<'a> Names<'a> {
+    fn kingdom(&self, key: &str, line: usize) -> Result<usize, Error> {
+        self.kingdoms.iter().position(|k| k == key)
+            .ok_or_else(|| (line, format!("unknown kingdom {}", key)))
+    }
+
+    fn moon(&self, key: &str, line: usize) -> Result<usize, Error> {
+        self.moons.get(key).copied()
+            .ok_or_else(|| (line, format!("unknown moon {}", key)))
+    }
+}
+
+fn parse_kingdom(b: &Block, names: &Names) -> Result<KingdomData, Error> {
+    let mut kingdom = KingdomData {
+        line: b.line,
+        key: word(&b.header, 1, b.line)?,
+        name: text(&b.header, 2, b.line)?,
+        moons_to_leave: 0,
+        moons_to_unlock: 0,
+        coin_income: 0,
+        regional_coins: 0,
+        prerequisites: Vec::new(),
+        next: Vec::new(),
+        exit_moon: None,
+        phase_moons: Vec::new(),
+    };
+    end(&b.header, 3, b.line)?;
+    for (line, tokens) in &b.directives {
+        let line = *line;
+        match word(tokens, 0, line)?.as_str() {
+            "leave" => kingdom.moons_to_leave = number(tokens, 1, line)?,
+            "unlock" => kingdom.moons_to_unlock = number(tokens, 1, line)?,
+            "coins" => kingdom.coin_income = number(tokens, 1, line)?,
+            "regional-coins" => kingdom.regional_coins = number(tokens, 1, line)?,
+            "requires" => kingdom.prerequisites.push(kingdom_key(tokens, 1, line, names)?),
+            "next" => kingdom.next.push(kingdom_key(tokens, 1, line, names)?),
+            "exit" => kingdom.exit_moon = Some(names.moon(&word(tokens, 1, line)?, line)?),
+            "phase" => kingdom.phase_moons.push(names.moon(&word(tokens, 1, line)?, line)?),
+            other => return Err((line, format!("unknown kingdom setting {}", other))),
+        }
+        end(tokens, 2, line)?;
+    }
+    Ok(kingdom)
+}
+
+fn parse_moon(b: &Block, names: &Names) -> Result<MoonData, Error> {
+    let kind = word(&b.header, 0, b.line)?;
+    let mut moon = MoonData {
+        line: b.line,
+        key: word(&b.header, 1, b.line)?,
+        name: String::new(),
+        kingdom: kingdom_key(&b.header, 2, b.line, names)?,
+        count: 1,
+        prerequisites: Vec::new(),
+        achievement: false,
+        advances_phase: false,
+        captures: Vec::new(),
+        coin_cost: 0,
+        breaks_moon_rock: false,
+        hint_art: None,
+        tags: Vec::new(),
+        difficulty: 1,
+        time: DIFFICULTY_TIME[0],
+    };
+    match kind.as_str() {
+        "moon" => (),
+        "multi-moon" => {
+            // multi moons are boss fights
+            moon.count = 3;
+            moon.difficulty = 2;
+            moon.time = DIFFICULTY_TIME[1];
+        }
+        "achievement" => {
+            // just a chat with Toadette
+            moon.achievement = true;
+            moon.time = 10;
+        }
+        "shop-moon" => {
+            // every shop moon costs 100 gold coins
+            moon.coin_cost = 100;
+            moon.time = 20;
+        }
+        "moon-rock" => {
+            // not a moon, but a step in the route that unlocks more moons
+            moon.name = String::from("Break the Moon Rock");
+            moon.count = 0;
+            moon.breaks_moon_rock = true;
+            moon.time = 30;
+        }
+        other => return Err((b.line, format!("unknown kind of moon {}", other))),
+    }
+    if !moon.breaks_moon_rock {
+        moon.name = text(&b.header, 3, b.line)?;
+        end(&b.header, 4, b.line)?;
+    } else {
+        end(&b.header, 3, b.line)?;
+    }
+
+    for (line, tokens) in &b.directives {
+        let line = *line;
+        let mut next = 2;
+        match word(tokens, 0, line)?.as_str() {
+            "needs" => {
+                let mut position = 1;
+                moon.prerequisites.push(requirement(tokens, &mut position, line, names)?);
+                next = position;
+            }
+            "hint-art" => {
+                // hint art only hangs after the main game, so the moon needs
+                // a visit to the art's kingdom after that
+                let kingdom = kingdom_key(tokens, 1, line, names)?;
+                moon.hint_art = Some((kingdom.clone(), text(tokens, 2, line)?));
+                moon.prerequisites.push(RequirementData::HintArt(kingdom));
+                next = 3;
+            }
+            "tag" => moon.tags.push(word(tokens, 1, line)?),
+            "learns" => moon.captures.push(word(tokens, 1, line)?),
+            "difficulty" => {
+                // 1 is a moon anyone can pick up, 5 is the hardest the game has
+                let difficulty: u8 = number(tokens, 1, line)?;
+                if !(1..=5).contains(&difficulty) {
+                    return Err((line, format!("difficulty {} is not from 1 to 5",
+                                              difficulty)));
+                }
+                moon.difficulty = difficulty;
+                moon.time = DIFFICULTY_TIME[difficulty as usize - 1];
+            }
+            other => return Err((line, format!("unknown moon setting {}", other))),
+        }
+        end(tokens, next, line)?;
+    }
+    Ok(moon)
+}
+
+fn requirement(tokens: &[Token], position: &mut usize, line: usize,
+               names: &Names) -> Result<RequirementData, Error> {
+    let p = *position;
+    let kind = word(tokens, p, line)?;
+    let (r, used) = match kind.as_str() {
+        "moon" => (RequirementData::Moon(names.moon(&word(tokens, p + 1, line)?, line)?), 2),
+        "kingdom" => {
+            let kingdom = kingdom_key(tokens, p + 1, line, names)?;
+            // the number of visits is optional, and defaults to the first
+            match tokens.get(p + 2) {
+                Some(Token::Word(_)) => match number(tokens, p + 2, line)? {
+                    0 => return Err((line, String::from("kingdom visits start at 1"))),
+                    visits => (RequirementData::Kingdom(kingdom, visits), 3),
+                },
+                _ => (RequirementData::Kingdom(kingdom, 1), 2),
+            }
+        }
+        "moons" => (RequirementData::MoonCount(number(tokens, p + 1, line)?), 2),
+        "main-game" => (RequirementData::CompletedMainGame, 1),
+        "moon-rock" => {
+            (RequirementData::MoonRockBroken(kingdom_key(tokens, p + 1, line, names)?), 2)
+        }
+        "phase" => (RequirementData::PhaseAtLeast(kingdom_key(tokens, p + 1, line, names)?,
+                                                  number(tokens, p + 2, line)?), 3),
+        "phase-only" => (RequirementData::PhaseOnly(kingdom_key(tokens, p + 1, line, names)?,
+                                                    number(tokens, p + 2, line)?), 3),
+        "capture" => (RequirementData::Capture(word(tokens, p + 1, line)?), 2),
+        "outfit" => (RequirementData::Outfit(word(tokens, p + 1, line)?), 2),
+        "hint-art" => (RequirementData::HintArt(kingdom_key(tokens, p + 1, line, names)?), 2),
+        "tagged" => (RequirementData::Tagged(word(tokens, p + 1, line)?,
+                                             number(tokens, p + 2, line)?), 3),
+        "all" | "any" => {
+            // each requirement combined is in brackets
+            let mut combined = Vec::new();
+            *position = p + 1;
+            while tokens.get(*position) == Some(&Token::Open) {
+                *position += 1;
+                combined.push(requirement(tokens, position, line, names)?);
+                if tokens.get(*position) != Some(&Token::Close) {
+                    return Err((line, String::from("expected )")));
+                }
+                *position += 1;
+            }
+            if combined.is_empty() {
+                return Err((line, format!("{} needs requirements in brackets", kind)));
+            }
+            let r = if kind == "all" {
+                RequirementData::All(combined)
+            } else {
+                RequirementData::Any(combined)
+            };
+            return Ok(r);
+        }
+        other => return Err((line, format!("unknown requirement {}", other))),
+    };
+    *position = p + used;
+    Ok(r)
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => break,
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(e) => text.push(e),
+                            None => return Err(String::from("unterminated name")),
+                        },
+                        Some(t) => text.push(t),
+                        None => return Err(String::from("unterminated name")),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_whitespace() => (),
+            c => {
+                let mut word = c.to_string();
+                while let Some(w) = chars.peek() {
+                    if w.is_whitespace() || "()\"#".contains(*w) {
+                        break;
+                    }
+                    word.push(*w);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn word(tokens: &[Token], i: usize, line: usize) -> Result<String, Error> {
+    match tokens.get(i) {
+        Some(Token::Word(w)) => Ok(w.clone()),
+        _ => Err((line, String::from("missing value"))),
+    }
+}
+
+fn text(tokens: &[Token], i: usize, line: usize) -> Result<String, Error> {
+    match tokens.get(i) {
+        Some(Token::Text(t)) => Ok(t.clone()),
+        _ => Err((line, String::from("missing quoted name"))),
+    }
+}
+
+fn number<T: std::str::FromStr>(tokens: &[Token], i: usize,
+                                line: usize) -> Result<T, Error> {
+    let w = word(tokens, i, line)?;
+    w.parse().map_err(|_| (line, format!("invalid number {}", w)))
+}
+
+fn kingdom_key(tokens: &[Token], i: usize, line: usize,
+               names: &Names) -> Result<String, Error> {
+    let key = word(tokens, i, line)?;
+    names.kingdom(&key, line)?;
+    Ok(key)
+}
+
+fn end(tokens: &[Token], i: usize, line: usize) -> Result<(), Error> {
+    if tokens.len() > i {
+        return Err((line, String::from("unexpected value at end of line")));
+    }
+    Ok(())
+}
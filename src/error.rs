@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::fmt;
+
+// everything that can stop the randomizer, shared with the build script so
+// the data file reports its errors the same way in both
+#[derive(Debug, PartialEq)]
+pub enum RandomizerError {
+    // a moon id past the end of the moon data
+    BadMoonId(usize),
+    UnknownKingdom(String),
+    // data that no route can get through, such as a moon that needs itself
+    UnsatisfiableData(String),
+    // a route that ran out of kingdoms before scheduling every moon
+    IncompleteRoute { scheduled: usize, total: usize },
+    // a kingdom or moon scheduled when none were queued up
+    NothingToSchedule(String),
+    // a shop moon bought with fewer coins than it costs
+    NotEnoughCoins { cost: u16, coins: u32 },
+    // a mistake in a data file, at a line
+    Parse { path: String, line: usize, message: String },
+    // a file that could not be read
+    Io { path: String, message: String },
+    // a permalink that can't be decoded or doesn't match the data
+    BadPermalink(String),
+}
+
+impl fmt::Display for RandomizerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RandomizerError::BadMoonId(id) => write!(f, "No moon with id {}", id),
+            RandomizerError::UnknownKingdom(k) => write!(f, "Unknown kingdom: {}", k),
+            RandomizerError::UnsatisfiableData(e) => write!(f, "Unsatisfiable data: {}", e),
+            RandomizerError::IncompleteRoute { scheduled, total } => {
+                write!(f, "Route ended after scheduling {} of {} moons", scheduled, total)
+            }
+            RandomizerError::NothingToSchedule(e) => write!(f, "Nothing to schedule: {}", e),
+            RandomizerError::NotEnoughCoins { cost, coins } => {
+                write!(f, "Can't pay {} coins with {} left", cost, coins)
+            }
+            RandomizerError::Parse { path, line, message } => {
+                write!(f, "{}:{}: {}", path, line, message)
+            }
+            RandomizerError::Io { path, message } => write!(f, "{}: {}", path, message),
+            RandomizerError::BadPermalink(e) => write!(f, "Bad permalink: {}", e),
+        }
+    }
+}
+
+impl Error for RandomizerError {}
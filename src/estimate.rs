@@ -0,0 +1,130 @@
+use std::fmt;
+use std::fs;
+
+use crate::error::RandomizerError;
+use crate::moon::{Moons, MoonID};
+
+// seconds spent travelling each time the route changes kingdom
+const TRAVEL_TIME: u32 = 60;
+
+pub struct Estimates {
+    travel: u32,
+    moons: Vec<u32>,
+}
+
+impl Estimates {
+    pub fn new(moons: &Moons) -> Self {
+        Estimates {
+            travel: TRAVEL_TIME,
+            moons: moons.iter().map(|(_, m)| m.time()).collect(),
+        }
+    }
+
+    pub fn load(&mut self, moons: &Moons, path: &str) -> Result<(), RandomizerError> {
+        // each line is a time and then a moon name, or travel for the time
+        // taken to change kingdom, for example
+        //     12:30 Jump-Rope Genius
+        //     45 travel
+        let contents = fs::read_to_string(path).map_err(|e| RandomizerError::Io {
+            path: String::from(path),
+            message: e.to_string(),
+        })?;
+        let error = |line: usize, message: String| RandomizerError::Parse {
+            path: String::from(path),
+            line,
+            message,
+        };
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let time = parts.next().and_then(parse_duration);
+            let name = parts.next().map(|n| n.trim()).unwrap_or("");
+            let time = match time {
+                Some(t) if !name.is_empty() => t,
+                _ => {
+                    return Err(error(number + 1,
+                                     String::from("expected a time and a moon name")));
+                }
+            };
+            if name.eq_ignore_ascii_case("travel") {
+                self.travel = time;
+                continue;
+            }
+            let found = moons.find(name);
+            if found.is_empty() {
+                return Err(error(number + 1, format!("no moon named \"{}\"", name)));
+            }
+            for id in found {
+                // a table made for other moon data doesn't have every id
+                *self.moons.get_mut(id).ok_or(RandomizerError::BadMoonId(id))? = time;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn travel(&self) -> u32 {
+        self.travel
+    }
+
+    pub fn moon(&self, id: MoonID) -> Result<u32, RandomizerError> {
+        self.moons.get(id).copied().ok_or(RandomizerError::BadMoonId(id))
+    }
+}
+
+// the time of the visit being printed and of the route up to it, shared by
+// every printer so the times under each visit add up the same way
+pub struct Subtotal<'a> {
+    estimates: &'a Estimates,
+    visit: u32,
+    elapsed: u32,
+}
+
+impl<'a> Subtotal<'a> {
+    pub fn new(estimates: &'a Estimates) -> Self {
+        Subtotal {
+            estimates,
+            visit: 0,
+            elapsed: 0,
+        }
+    }
+
+    // a new visit starts with the travel to it
+    pub fn arrive(&mut self) {
+        self.visit = self.estimates.travel();
+        self.elapsed += self.estimates.travel();
+    }
+
+    pub fn collect(&mut self, id: MoonID) -> Result<(), RandomizerError> {
+        let time = self.estimates.moon(id)?;
+        self.visit += time;
+        self.elapsed += time;
+        Ok(())
+    }
+
+    pub fn elapsed(&self) -> u32 {
+        self.elapsed
+    }
+}
+
+impl fmt::Display for Subtotal<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(visit {}, elapsed {})", format_duration(self.visit),
+               format_duration(self.elapsed))
+    }
+}
+
+pub fn parse_duration(text: &str) -> Option<u32> {
+    // seconds, m:ss or h:mm:ss
+    let mut total = 0;
+    for part in text.split(':') {
+        total = total * 60 + part.parse::<u32>().ok()?;
+    }
+    Some(total)
+}
+
+pub fn format_duration(seconds: u32) -> String {
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
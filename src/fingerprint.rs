@@ -0,0 +1,149 @@
+use std::fmt;
+use std::fs;
+use std::hash::Hasher;
+
+use crate::error::RandomizerError;
+use crate::kingdom::{Kingdoms, KingdomName};
+use crate::moon::{MoonID, Moons};
+use crate::permalink::Fnv;
+
+// six bits of the fingerprint to a word
+const WORDS: [&str; 64] = [
+    "Cappy", "Crescent", "Sphynx", "Jaxi", "Bonneter", "Tostarena", "Shiveria",
+    "Volbono", "Glydon", "Lakitu", "Goomba", "Chomp", "Cheep", "Uproot",
+    "Wiggler", "Pokio", "Bullet", "Banzai", "Koopa", "Toadette", "Peach",
+    "Tiara", "Hariet", "Topper", "Rango", "Spewart", "Broodal", "Cookatiel",
+    "Knucklotec", "Torkdrift", "Lunar", "Eclipse", "Orbit", "Comet", "Nebula",
+    "Meteor", "Stardust", "Shard", "Seed", "Coin", "Balloon", "Odyssey", "Frog",
+    "Fuzzy", "Snowball", "Bubblaine", "Yoshi", "Bowser", "Dorrie", "Chargin",
+    "Tadpole", "Parabones", "Boomerang", "Mummy", "Scarecrow", "Lochlady",
+    "Gushen", "Tropical", "Hammer", "Pinball", "Jumprope", "Volleyball",
+    "Gobble", "Crazycap",
+];
+
+// a short name for the order of a route, so racers can check they have the
+// same route by reading out a few words instead of every moon
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Fingerprint(u32);
+
+impl Fingerprint {
+    pub fn new(kingdoms: &Kingdoms, moons: &Moons,
+               route: &[MoonID]) -> Result<Self, RandomizerError> {
+        // moons are hashed by kingdom and name rather than id, so the same
+        // route from data with the moons in another order matches
+        let mut hasher = Fnv::new();
+        for m in route {
+            let moon = moons.moon(*m)?;
+            hasher.write(kingdoms.kingdom(moon.kingdom())?.name().as_bytes());
+            hasher.write(b": ");
+            hasher.write(moon.name().as_bytes());
+            hasher.write(b"\n");
+        }
+        let hash = hasher.finish();
+        Ok(Fingerprint((hash ^ (hash >> 32)) as u32))
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let words: Vec<&str> = (0..4)
+            .map(|x| WORDS[(self.0 >> (26 - 6 * x) & 0x3f) as usize])
+            .collect();
+        write!(f, "{} ({:08x})", words.join(" "), self.0)
+    }
+}
+
+// a route read back from a file printed in either format
+pub struct RouteFile {
+    moons: Vec<MoonID>,
+    fingerprint: Option<String>,
+}
+
+impl RouteFile {
+    pub fn read(kingdoms: &Kingdoms, moons: &Moons,
+                path: &str) -> Result<Self, RandomizerError> {
+        let text = fs::read_to_string(path).map_err(|e| RandomizerError::Io {
+            path: String::from(path),
+            message: e.to_string(),
+        })?;
+        let error = |line: usize, message: String| RandomizerError::Parse {
+            path: String::from(path),
+            line,
+            message,
+        };
+
+        let mut route = RouteFile {
+            moons: Vec::new(),
+            fingerprint: None,
+        };
+        let mut current = None;
+        for (x, line) in text.lines().enumerate() {
+            let header = line.strip_prefix("# ").unwrap_or(line);
+            if let Some(f) = header.strip_prefix("Fingerprint: ") {
+                route.fingerprint = Some(String::from(f.trim()));
+                continue;
+            }
+            if line.trim().is_empty() || line.starts_with('#')
+                || line.starts_with("Permalink: ") || line.starts_with("Route score: ")
+                || line.starts_with("Projected finish: ") || line.starts_with("\t(") {
+                continue;
+            }
+
+            // the full format heads each kingdom and numbers its moons, and
+            // the text format puts the kingdom before every moon
+            let name = if let Some(k) = line.strip_prefix("===")
+                .and_then(|l| l.strip_suffix("===")) {
+                current = Some(kingdom(kingdoms, k)
+                    .ok_or_else(|| error(x + 1, format!("unknown kingdom {}", k)))?);
+                continue;
+            } else if let Some(name) = line.strip_prefix('\t') {
+                name
+            } else if let Some((number, name)) = line.split_once(".\t") {
+                if number.parse::<u16>().is_err() {
+                    return Err(error(x + 1, format!("unexpected line {}", line)));
+                }
+                name
+            } else {
+                let (k, name) = kingdoms.iter()
+                    .find_map(|(k, kingdom)| {
+                        Some((k, line.strip_prefix(kingdom.name())?.strip_prefix(": ")?))
+                    })
+                    .ok_or_else(|| error(x + 1, format!("unexpected line {}", line)))?;
+                current = Some(k);
+                name
+            };
+            let k = current
+                .ok_or_else(|| error(x + 1, String::from("moon before any kingdom")))?;
+            let id = find(moons, k, name)
+                .ok_or_else(|| error(x + 1, format!("unknown moon {}", name)))?;
+            route.moons.push(id);
+        }
+        Ok(route)
+    }
+
+    pub fn moons(&self) -> &[MoonID] {
+        &self.moons
+    }
+
+    // the fingerprint printed with the route, if it has one
+    pub fn fingerprint(&self) -> Option<&str> {
+        self.fingerprint.as_deref()
+    }
+}
+
+fn kingdom(kingdoms: &Kingdoms, name: &str) -> Option<KingdomName> {
+    kingdoms.iter().find(|(_, k)| k.name() == name).map(|(k, _)| k)
+}
+
+fn find(moons: &Moons, kingdom: KingdomName, line: &str) -> Option<MoonID> {
+    // the full format follows names with counts and notes, so take the
+    // longest name the line starts with
+    moons.iter()
+        .filter(|(_, m)| m.kingdom() == kingdom)
+        .filter(|(_, m)| {
+            line.strip_prefix(m.name())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+        })
+        .max_by_key(|(_, m)| m.name().len())
+        .map(|(id, _)| id)
+}
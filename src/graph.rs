@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+
+use crate::error::RandomizerError;
+use crate::kingdom::{Kingdoms, KingdomName};
+use crate::moon::{Moons, MoonID};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Dot,
+    Mermaid,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "dot" => Some(Format::Dot),
+            "mermaid" => Some(Format::Mermaid),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum EdgeStyle {
+    // kingdom can be travelled to next
+    Next,
+    // kingdom or moon requires a kingdom visit first
+    Prerequisite,
+    // moon requires a moon in the same kingdom
+    Moon,
+    // moon requires a moon in a different kingdom
+    CrossKingdom,
+}
+
+struct Node {
+    id: String,
+    label: String,
+    cluster: Option<KingdomName>,
+    kingdom: bool,
+    exit: bool,
+    multi: bool,
+    external: bool,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    style: EdgeStyle,
+    label: Option<String>,
+}
+
+pub struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    node_ids: HashSet<String>,
+}
+
+fn kingdom_node(kingdom: KingdomName) -> String {
+    format!("k_{:?}", kingdom)
+}
+
+fn moon_node(moon: MoonID) -> String {
+    format!("m{}", moon)
+}
+
+impl Graph {
+    pub fn new(kingdoms: &Kingdoms, moons: &Moons,
+               filter: Option<KingdomName>,
+               show_moons: bool) -> Result<Self, RandomizerError> {
+        let mut graph = Graph {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            node_ids: HashSet::new(),
+        };
+        let wanted = |k: KingdomName| filter.is_none() || filter == Some(k);
+
+        // the kingdom chain
+        for (k, kingdom) in kingdoms.iter() {
+            if wanted(k) {
+                // kingdoms with no links, like Dark Side, still get a node
+                graph.add_kingdom(kingdoms, k)?;
+            }
+            for n in kingdom.next() {
+                if wanted(k) || wanted(*n) {
+                    graph.add_kingdom(kingdoms, k)?;
+                    graph.add_kingdom(kingdoms, *n)?;
+                    graph.add_edge(kingdom_node(k), kingdom_node(*n),
+                                   EdgeStyle::Next, None);
+                }
+            }
+            for p in kingdom.prerequisites() {
+                if wanted(k) || wanted(*p) {
+                    graph.add_kingdom(kingdoms, k)?;
+                    graph.add_kingdom(kingdoms, *p)?;
+                    graph.add_edge(kingdom_node(*p), kingdom_node(k),
+                                   EdgeStyle::Prerequisite, None);
+                }
+            }
+        }
+
+        if !show_moons {
+            return Ok(graph);
+        }
+
+        // the moon prerequisite graph
+        for (id, moon) in moons.iter() {
+            if !wanted(moon.kingdom()) {
+                continue;
+            }
+            graph.add_moon(kingdoms, moons, id, false)?;
+            for (k, visits) in moon.prerequisite_kingdoms() {
+                graph.add_kingdom(kingdoms, k)?;
+                let label = if visits > 1 {
+                    Some(format!("visit {}", visits))
+                } else {
+                    None
+                };
+                graph.add_edge(kingdom_node(k), moon_node(id),
+                               EdgeStyle::Prerequisite, label);
+            }
+            for p in moons.prerequisites(id).iter().copied() {
+                let kingdom = moons.moon(p)?.kingdom();
+                graph.add_moon(kingdoms, moons, p, !wanted(kingdom))?;
+                let style = if kingdom == moon.kingdom() {
+                    EdgeStyle::Moon
+                } else {
+                    EdgeStyle::CrossKingdom
+                };
+                graph.add_edge(moon_node(p), moon_node(id), style, None);
+            }
+            // moons outside the filter that this moon unlocks
+            for d in moons.dependents(id) {
+                // only moon prerequisites, the kingdom chain draws the rest
+                if !wanted(moons.moon(*d)?.kingdom()) && moons.prerequisites(*d).contains(&id) {
+                    graph.add_moon(kingdoms, moons, *d, true)?;
+                    graph.add_edge(moon_node(id), moon_node(*d),
+                                   EdgeStyle::CrossKingdom, None);
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    fn add_kingdom(&mut self, kingdoms: &Kingdoms,
+                   kingdom: KingdomName) -> Result<(), RandomizerError> {
+        let id = kingdom_node(kingdom);
+        if self.node_ids.insert(id.clone()) {
+            self.nodes.push(Node {
+                id,
+                label: String::from(kingdoms.kingdom(kingdom)?.name()),
+                cluster: None,
+                kingdom: true,
+                exit: false,
+                multi: false,
+                external: false,
+            });
+        }
+        Ok(())
+    }
+
+    fn add_moon(&mut self, kingdoms: &Kingdoms, moons: &Moons, moon: MoonID,
+                external: bool) -> Result<(), RandomizerError> {
+        let id = moon_node(moon);
+        if self.node_ids.insert(id.clone()) {
+            let m = moons.moon(moon)?;
+            self.nodes.push(Node {
+                id,
+                label: String::from(m.name()),
+                cluster: Some(m.kingdom()),
+                kingdom: false,
+                exit: kingdoms.exit_for(moon).is_some(),
+                multi: m.count() > 1,
+                external,
+            });
+        }
+        Ok(())
+    }
+
+    fn add_edge(&mut self, from: String, to: String, style: EdgeStyle,
+                label: Option<String>) {
+        let duplicate = self.edges.iter()
+            .any(|e| e.from == from && e.to == to && e.style == style);
+        if !duplicate {
+            self.edges.push(Edge { from, to, style, label });
+        }
+    }
+
+    pub fn print(&self, kingdoms: &Kingdoms, format: Format) {
+        match format {
+            Format::Dot => self.print_dot(kingdoms),
+            Format::Mermaid => self.print_mermaid(kingdoms),
+        }
+    }
+
+    fn print_dot(&self, kingdoms: &Kingdoms) {
+        println!("digraph odyssey {{");
+        println!("    rankdir=LR;");
+        for n in self.nodes.iter().filter(|n| n.cluster.is_none()) {
+            println!("    {} {};", n.id, dot_attributes(n));
+        }
+        for (k, kingdom) in kingdoms.iter() {
+            let members: Vec<&Node> = self.nodes.iter()
+                .filter(|n| n.cluster == Some(k))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            println!("    subgraph cluster_{:?} {{", k);
+            println!("        label=\"{}\";",
+                     escape_dot(kingdom.name()));
+            for n in members {
+                println!("        {} {};", n.id, dot_attributes(n));
+            }
+            println!("    }}");
+        }
+        for e in &self.edges {
+            let mut attributes = Vec::new();
+            match e.style {
+                EdgeStyle::Next => {}
+                EdgeStyle::Prerequisite => {
+                    attributes.push(String::from("style=dashed"));
+                }
+                EdgeStyle::Moon => {}
+                EdgeStyle::CrossKingdom => {
+                    attributes.push(String::from("color=red"));
+                    attributes.push(String::from("penwidth=2"));
+                }
+            }
+            if let Some(l) = &e.label {
+                attributes.push(format!("label=\"{}\"", escape_dot(l)));
+            }
+            if attributes.is_empty() {
+                println!("    {} -> {};", e.from, e.to);
+            } else {
+                println!("    {} -> {} [{}];", e.from, e.to,
+                         attributes.join(", "));
+            }
+        }
+        println!("}}");
+    }
+
+    fn print_mermaid(&self, kingdoms: &Kingdoms) {
+        println!("flowchart LR");
+        for n in self.nodes.iter().filter(|n| n.cluster.is_none()) {
+            println!("    {}", mermaid_node(n));
+        }
+        for (k, kingdom) in kingdoms.iter() {
+            let members: Vec<&Node> = self.nodes.iter()
+                .filter(|n| n.cluster == Some(k))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            println!("    subgraph cluster_{:?}[\"{}\"]", k,
+                     escape_mermaid(kingdom.name()));
+            for n in members {
+                println!("        {}", mermaid_node(n));
+            }
+            println!("    end");
+        }
+        for e in &self.edges {
+            let arrow = match e.style {
+                EdgeStyle::Next => "-->",
+                EdgeStyle::Prerequisite => "-.->",
+                EdgeStyle::Moon => "-->",
+                EdgeStyle::CrossKingdom => "==>",
+            };
+            match &e.label {
+                Some(l) => println!("    {} {}|\"{}\"| {}", e.from, arrow,
+                                    escape_mermaid(l), e.to),
+                None => println!("    {} {} {}", e.from, arrow, e.to),
+            }
+        }
+        println!("    classDef kingdom fill:#dde8ff,stroke:#335;");
+        println!("    classDef exit fill:#ffd700,stroke:#a67c00;");
+        println!("    classDef multi stroke-width:4px;");
+        println!("    classDef external stroke-dasharray:5 5;");
+        for n in &self.nodes {
+            if n.kingdom {
+                println!("    class {} kingdom", n.id);
+            }
+            if n.exit {
+                println!("    class {} exit", n.id);
+            }
+            if n.multi {
+                println!("    class {} multi", n.id);
+            }
+            if n.external {
+                println!("    class {} external", n.id);
+            }
+        }
+    }
+}
+
+fn dot_attributes(node: &Node) -> String {
+    let mut attributes = vec![format!("label=\"{}\"", escape_dot(&node.label))];
+    let mut styles = Vec::new();
+    if node.kingdom {
+        attributes.push(String::from("shape=box"));
+        styles.push("rounded");
+    } else if node.multi {
+        attributes.push(String::from("shape=doubleoctagon"));
+    }
+    if node.exit {
+        styles.push("filled");
+        attributes.push(String::from("fillcolor=gold"));
+    }
+    if node.external {
+        styles.push("dashed");
+    }
+    if !styles.is_empty() {
+        attributes.push(format!("style=\"{}\"", styles.join(",")));
+    }
+    format!("[{}]", attributes.join(", "))
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn mermaid_node(node: &Node) -> String {
+    let label = escape_mermaid(&node.label);
+    if node.kingdom {
+        format!("{}([\"{}\"])", node.id, label)
+    } else if node.multi {
+        format!("{}{{{{\"{}\"}}}}", node.id, label)
+    } else {
+        format!("{}[\"{}\"]", node.id, label)
+    }
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;")
+}
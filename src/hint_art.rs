@@ -0,0 +1,27 @@
+use std::borrow::Cow;
+
+use crate::kingdom::KingdomName;
+
+#[derive(Clone, Hash)]
+pub struct HintArt {
+    kingdom: KingdomName,
+    location: Cow<'static, str>,
+}
+
+impl HintArt {
+    pub const fn new(kingdom: KingdomName, location: Cow<'static, str>) -> Self {
+        HintArt {
+            kingdom,
+            location,
+        }
+    }
+
+    // the kingdom the art hangs in, not the kingdom of the moon it shows
+    pub fn kingdom(&self) -> KingdomName {
+        self.kingdom
+    }
+
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+}
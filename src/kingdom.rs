@@ -1,6 +1,13 @@
+use std::borrow::Cow;
+
+use crate::data::Data;
+use crate::error::RandomizerError;
 use crate::moon::MoonID;
 use crate::state::State;
 
+// the kingdoms compiled from data/odyssey.txt by build.rs
+include!(concat!(env!("OUT_DIR"), "/kingdoms.rs"));
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum KingdomName {
     Cap,
@@ -22,61 +29,96 @@ pub enum KingdomName {
     Darker,
 }
 
+impl KingdomName {
+    pub const ALL: [KingdomName; 17] = [
+        KingdomName::Cap,
+        KingdomName::Cascade,
+        KingdomName::Sand,
+        KingdomName::Lake,
+        KingdomName::Wooded,
+        KingdomName::Cloud,
+        KingdomName::Lost,
+        KingdomName::Metro,
+        KingdomName::Snow,
+        KingdomName::Seaside,
+        KingdomName::Luncheon,
+        KingdomName::Ruined,
+        KingdomName::Bowser,
+        KingdomName::Moon,
+        KingdomName::Mushroom,
+        KingdomName::Dark,
+        KingdomName::Darker,
+    ];
+
+    pub fn from_name(name: &str) -> Result<KingdomName, RandomizerError> {
+        KingdomName::ALL.iter()
+            .find(|k| format!("{:?}", k).eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| RandomizerError::UnknownKingdom(String::from(name)))
+    }
+}
+
+#[derive(Clone, Hash)]
 pub struct Kingdom {
-    name: String,
+    name: Cow<'static, str>,
     moons_to_leave: u16,
     moons_to_unlock: u16,
-    prerequisite_kingdoms: Vec<KingdomName>,
-    next_kingdoms: Vec<KingdomName>,
+    coin_income: u16,
+    regional_coins: u16,
+    prerequisite_kingdoms: Cow<'static, [KingdomName]>,
+    next_kingdoms: Cow<'static, [KingdomName]>,
     exit_moon: Option<MoonID>,
+    phase_moons: Cow<'static, [MoonID]>,
 }
 
 impl Kingdom {
-    fn new(name: &str, moons_to_leave: u16,
-               moons_to_unlock: u16) -> Self {
-        Kingdom {
-            name: String::from(name),
-            moons_to_leave,
-            moons_to_unlock,
-            prerequisite_kingdoms: Vec::new(),
-            next_kingdoms: Vec::new(),
-            exit_moon: None,
-        }
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    fn add_prerequisite(&mut self, id: KingdomName) {
-        self.prerequisite_kingdoms.push(id);
+    pub fn next(&self) -> &[KingdomName] {
+        &self.next_kingdoms
     }
 
-    fn link_next(&mut self, id: KingdomName) {
-        self.next_kingdoms.push(id);
+    pub fn prerequisites(&self) -> &[KingdomName] {
+        &self.prerequisite_kingdoms
     }
 
-    pub fn name(&self) -> &str {
-        self.name.as_str()
+    pub fn moons_to_leave(&self) -> u16 {
+        self.moons_to_leave
     }
 
-    pub fn next(&self) -> &Vec<KingdomName> {
-        &self.next_kingdoms
+    pub fn moons_to_unlock(&self) -> u16 {
+        self.moons_to_unlock
     }
 
-    pub fn moons_to_leave(&self) -> u16 {
-        self.moons_to_leave
+    pub fn coin_income(&self) -> u16 {
+        self.coin_income
     }
 
-    pub fn set_exit_moon(&mut self, moon: MoonID) {
-        self.exit_moon = Some(moon);
+    pub fn regional_coins(&self) -> u16 {
+        self.regional_coins
+    }
+
+    // rough estimate of the regional coins found on each visit
+    pub fn regional_coins_per_visit(&self) -> u16 {
+        self.regional_coins / 2
+    }
+
+    pub fn exit_moon(&self) -> Option<MoonID> {
+        self.exit_moon
+    }
+
+    pub fn phase_moons(&self) -> &[MoonID] {
+        &self.phase_moons
     }
 
     pub fn can_leave(&self, state: &State) -> bool {
         // can leave if the required moon ID has been scheduled
-        match self.exit_moon {
-            Some(m) => {
-                if !state.moon_scheduled(m) {
-                    return false;
-                }
+        if let Some(m) = self.exit_moon {
+            if !state.moon_scheduled(m) {
+                return false;
             }
-            None => {}
         }
         // can leave if the total kingdom moons are enough
         if state.completed_main_game() {
@@ -93,7 +135,7 @@ impl Kingdom {
 
     pub fn available(&self, state: &State) -> bool {
         // available if all prerequisites are scheduled
-        for p in &self.prerequisite_kingdoms {
+        for p in self.prerequisite_kingdoms.iter() {
             if !state.kingdom_scheduled(*p, 1) {
                 return false;
             }
@@ -110,76 +152,75 @@ impl Kingdom {
 }
 
 pub struct Kingdoms {
-   kingdoms: Vec<Kingdom>,
+   kingdoms: Cow<'static, [Kingdom]>,
 }
 
-impl Kingdoms {
-    pub fn kingdom(&self, id: KingdomName) -> &Kingdom {
-        &self.kingdoms[id as usize]
+impl Default for Kingdoms {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    pub fn kingdom_mut(&mut self, id: KingdomName) -> &mut Kingdom {
-        &mut self.kingdoms[id as usize]
+impl Kingdoms {
+    pub fn kingdom(&self, id: KingdomName) -> Result<&Kingdom, RandomizerError> {
+        self.kingdoms.get(id as usize)
+            .ok_or_else(|| RandomizerError::UnknownKingdom(format!("{:?}", id)))
     }
 
-    pub fn new() -> Self {
-        let mut kingdoms = Vec::new();
+    pub fn iter(&self) -> impl Iterator<Item = (KingdomName, &Kingdom)> {
+        KingdomName::ALL.iter().copied().zip(self.kingdoms.iter())
+    }
 
-        // main game
-        kingdoms.push(Kingdom::new("Cap Kingdom", 0, 0));
-        kingdoms.push(Kingdom::new("Cascade Kingdom", 5, 0));
-        kingdoms.push(Kingdom::new("Sand Kingdom", 16, 0));
-        kingdoms.push(Kingdom::new("Lake Kingdom", 8, 0));
-        kingdoms.push(Kingdom::new("Wooded Kingdom", 16, 0));
-        kingdoms.push(Kingdom::new("Cloud Kingdom", 0, 0));
-        kingdoms.push(Kingdom::new("Lost Kingdom", 10, 0));
-        kingdoms.push(Kingdom::new("Metro Kingdom", 20, 0));
-        kingdoms.push(Kingdom::new("Snow Kingdom", 10, 0));
-        kingdoms.push(Kingdom::new("Seaside Kingdom", 10, 0));
-        kingdoms.push(Kingdom::new("Luncheon Kingdom", 18, 0));
-        kingdoms.push(Kingdom::new("Ruined Kingdom", 3, 0));
-        kingdoms.push(Kingdom::new("Bowser's Kingdom", 8, 0));
-        kingdoms.push(Kingdom::new("Moon Kingdom", 1, 0));
-        kingdoms.push(Kingdom::new("Mushroom Kingdom", 1, 0));
-        kingdoms.push(Kingdom::new("Dark Side", 4, 250));
-        kingdoms.push(Kingdom::new("Darker Side", 3, 500));
-
-        // link up main game
-        kingdoms[KingdomName::Cap as usize].link_next(KingdomName::Cascade);
-        kingdoms[KingdomName::Cascade as usize].link_next(KingdomName::Sand);
-        kingdoms[KingdomName::Cascade as usize].add_prerequisite(KingdomName::Cap);
-        kingdoms[KingdomName::Sand as usize].link_next(KingdomName::Lake);
-        kingdoms[KingdomName::Sand as usize].link_next(KingdomName::Wooded);
-        kingdoms[KingdomName::Sand as usize].add_prerequisite(KingdomName::Cascade);
-        kingdoms[KingdomName::Lake as usize].link_next(KingdomName::Cloud);
-        kingdoms[KingdomName::Lake as usize].add_prerequisite(KingdomName::Sand);
-        kingdoms[KingdomName::Wooded as usize].link_next(KingdomName::Cloud);
-        kingdoms[KingdomName::Wooded as usize].add_prerequisite(KingdomName::Sand);
-        kingdoms[KingdomName::Cloud as usize].link_next(KingdomName::Lost);
-        kingdoms[KingdomName::Cloud as usize].add_prerequisite(KingdomName::Lake);
-        kingdoms[KingdomName::Cloud as usize].add_prerequisite(KingdomName::Wooded);
-        kingdoms[KingdomName::Lost as usize].link_next(KingdomName::Metro);
-        kingdoms[KingdomName::Lost as usize].add_prerequisite(KingdomName::Cloud);
-        kingdoms[KingdomName::Metro as usize].link_next(KingdomName::Snow);
-        kingdoms[KingdomName::Metro as usize].link_next(KingdomName::Seaside);
-        kingdoms[KingdomName::Metro as usize].add_prerequisite(KingdomName::Lost);
-        kingdoms[KingdomName::Snow as usize].link_next(KingdomName::Luncheon);
-        kingdoms[KingdomName::Snow as usize].add_prerequisite(KingdomName::Metro);
-        kingdoms[KingdomName::Seaside as usize].link_next(KingdomName::Luncheon);
-        kingdoms[KingdomName::Seaside as usize].add_prerequisite(KingdomName::Metro);
-        kingdoms[KingdomName::Luncheon as usize].link_next(KingdomName::Ruined);
-        kingdoms[KingdomName::Luncheon as usize].add_prerequisite(KingdomName::Snow);
-        kingdoms[KingdomName::Luncheon as usize].add_prerequisite(KingdomName::Seaside);
-        kingdoms[KingdomName::Ruined as usize].link_next(KingdomName::Bowser);
-        kingdoms[KingdomName::Ruined as usize].add_prerequisite(KingdomName::Metro);
-        kingdoms[KingdomName::Bowser as usize].link_next(KingdomName::Moon);
-        kingdoms[KingdomName::Bowser as usize].add_prerequisite(KingdomName::Ruined);
-        kingdoms[KingdomName::Moon as usize].link_next(KingdomName::Mushroom);
-        kingdoms[KingdomName::Moon as usize].add_prerequisite(KingdomName::Bowser);
-        kingdoms[KingdomName::Mushroom as usize].add_prerequisite(KingdomName::Moon);
+    pub fn exit_for(&self, moon: MoonID) -> Option<&Kingdom> {
+        self.kingdoms.iter().find(|k| k.exit_moon() == Some(moon))
+    }
 
+    pub fn new() -> Self {
         Kingdoms {
-            kingdoms
+            kingdoms: Cow::Borrowed(&KINGDOMS)
+        }
+    }
+
+    pub fn load(data: &Data) -> Result<Self, RandomizerError> {
+        // the kingdoms are fixed, so custom data redefines every one of them
+        // in the same order
+        if data.kingdoms.len() != KingdomName::ALL.len() {
+            let line = data.kingdoms.last().map_or(0, |k| k.line);
+            return Err(data.error(line, format!("expected {} kingdoms, found {}",
+                                                KingdomName::ALL.len(),
+                                                data.kingdoms.len())));
+        }
+        let mut kingdoms = Vec::new();
+        for (k, id) in data.kingdoms.iter().zip(KingdomName::ALL.iter()) {
+            let kingdom = |key: &str| {
+                KingdomName::from_name(key).map_err(|e| data.error(k.line, e.to_string()))
+            };
+            if kingdom(&k.key)? != *id {
+                return Err(data.error(k.line, format!("expected kingdom {:?}, found {}",
+                                                      id, k.key)));
+            }
+            let mut prerequisites = Vec::new();
+            for p in &k.prerequisites {
+                prerequisites.push(kingdom(p)?);
+            }
+            let mut next = Vec::new();
+            for n in &k.next {
+                next.push(kingdom(n)?);
+            }
+            kingdoms.push(Kingdom {
+                name: Cow::Owned(k.name.clone()),
+                moons_to_leave: k.moons_to_leave,
+                moons_to_unlock: k.moons_to_unlock,
+                coin_income: k.coin_income,
+                regional_coins: k.regional_coins,
+                prerequisite_kingdoms: Cow::Owned(prerequisites),
+                next_kingdoms: Cow::Owned(next),
+                exit_moon: k.exit_moon,
+                phase_moons: Cow::Owned(k.phase_moons.clone()),
+            });
         }
+        Ok(Kingdoms {
+            kingdoms: Cow::Owned(kingdoms)
+        })
     }
 }
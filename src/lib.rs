@@ -0,0 +1,19 @@
+pub mod bingo;
+pub mod capture;
+pub mod data;
+pub mod error;
+pub mod estimate;
+pub mod fingerprint;
+pub mod graph;
+pub mod hint_art;
+pub mod kingdom;
+pub mod moon;
+pub mod outfit;
+pub mod permalink;
+pub mod requirement;
+pub mod reveal;
+pub mod route;
+pub mod score;
+pub mod state;
+pub mod stats;
+pub mod tag;
@@ -1,72 +1,509 @@
-use rand::{thread_rng, Rng};
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
 
-use crate::state::State;
-use crate::kingdom::{KingdomName, Kingdoms};
-use crate::moon::Moons;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use rayon::prelude::*;
 
-mod kingdom;
-mod moon;
-mod state;
+use odyssey_randomizer::bingo::Board;
+use odyssey_randomizer::data;
+use odyssey_randomizer::error::RandomizerError;
+use odyssey_randomizer::estimate::{format_duration, parse_duration, Estimates};
+use odyssey_randomizer::fingerprint::{Fingerprint, RouteFile};
+use odyssey_randomizer::graph::{Format, Graph};
+use odyssey_randomizer::route;
+use odyssey_randomizer::score::{Metric, Score};
+use odyssey_randomizer::state::State;
+use odyssey_randomizer::stats::Stats;
+use odyssey_randomizer::kingdom::{KingdomName, Kingdoms};
+use odyssey_randomizer::moon::{MoonID, MoonPool, Moons};
+use odyssey_randomizer::permalink::Settings;
+use odyssey_randomizer::reveal::{Reveal, Step};
 
 fn main() {
-    let mut state = State::new();
-    let mut kingdoms = Kingdoms::new();
-    let mut moons = Moons::new(&mut kingdoms);
-    let mut leave_chance = 1;
-
-    // start up the first kingdom
-    state.add_kingdom_to_schedule(KingdomName::Cap);
-    state.schedule_kingdom();
-
-    loop {
-        // first, find all moons that can be scheduled
-        let available = moons.return_available(&mut state);
-        for a in &available {
-            state.add_moon_to_schedule(*a);
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // custom data replaces the built in kingdoms and moons for every command
+    let loaded = match option_value(&args, "--data").map(String::from) {
+        Some(path) => {
+            let position = args.iter().position(|a| a == "--data").unwrap();
+            args.drain(position..position + 2);
+            load(&path)
         }
-        // schedule a random count trying to be enough to leave
-        let exit_count;
-        if state.completed_main_game() {
-            exit_count = 1;
-        } else {
-            exit_count = kingdoms.kingdom(state.current_kingdom())
-                .moons_to_leave();
+        None => {
+            let kingdoms = Kingdoms::new();
+            Moons::new(&kingdoms).map(|moons| (kingdoms, moons))
         }
-        let scheduleable = state.moons_to_schedule();
-        let exit_count = std::cmp::min(exit_count as usize, scheduleable);
-        let scheduled;
-        if exit_count == scheduleable {
-            scheduled = exit_count;
-        } else {
-            scheduled = thread_rng().gen_range(exit_count, scheduleable);
+    };
+    let (kingdoms, moons) = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
         }
-        if scheduled == 0 {
-            state.next_kingdom(&kingdoms);
-            // schedule the next kingdom
-            if !state.schedule_kingdom() {
-                // no more moons and no more kingdoms, we are done
-                break;
-            }
-        } else {
-            // schedule the moons
-            for _ in 0..scheduled {
-               state.schedule_moon(&moons);
-            }
-            // lets only leave with a 10% chance that increases 10% each time
-            let chance = thread_rng().gen_range(0, 10);
-            if chance < leave_chance {
-                leave_chance = 1;
-                // leave for the next kingdom
-                if state.next_kingdom(&kingdoms) {
-                    state.schedule_kingdom();
-                }
+    };
+
+    let result = match args.first().map(|a| a.as_str()) {
+        Some("dependents") => dependents(&kingdoms, &moons, &args[1..]),
+        Some("graph") => graph(&kingdoms, &moons, &args[1..]),
+        Some("stats") => stats(&kingdoms, &moons, &args[1..]),
+        Some("verify") => verify(&kingdoms, &moons, &args[1..]),
+        Some("reveal") => reveal(&kingdoms, &moons, &args[1..]),
+        Some("bingo") => bingo(&kingdoms, &moons, &args[1..]),
+        Some(option) if option.starts_with("--") => {
+            route(&kingdoms, &moons, &args)
+        }
+        Some(other) => {
+            eprintln!("Unknown command: {}", other);
+            process::exit(1);
+        }
+        None => route(&kingdoms, &moons, &args),
+    };
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn load(path: &str) -> Result<(Kingdoms, Moons), RandomizerError> {
+    let data = data::read(path)?;
+    let kingdoms = Kingdoms::load(&data)?;
+    let moons = Moons::load(&kingdoms, &data)?;
+    Ok((kingdoms, moons))
+}
+
+fn dependents(kingdoms: &Kingdoms, moons: &Moons,
+              args: &[String]) -> Result<(), RandomizerError> {
+    let name = args.join(" ");
+    if name.is_empty() {
+        eprintln!("Usage: odyssey_randomizer dependents <moon name>");
+        process::exit(1);
+    }
+    let found = moons.find(&name);
+    if found.is_empty() {
+        eprintln!("No moon named \"{}\"", name);
+        process::exit(1);
+    }
+    for (i, id) in found.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        moons.print_dependents(kingdoms, *id)?;
+    }
+    Ok(())
+}
+
+fn graph(kingdoms: &Kingdoms, moons: &Moons, args: &[String]) -> Result<(), RandomizerError> {
+    let format = match option_value(args, "--format") {
+        Some(f) => match Format::from_name(f) {
+            Some(format) => format,
+            None => {
+                eprintln!("Unknown graph format: {} (expected dot or mermaid)",
+                          f);
+                process::exit(1);
+            }
+        },
+        None => Format::Dot,
+    };
+    let filter = match option_value(args, "--kingdom") {
+        Some(k) => Some(KingdomName::from_name(k)?),
+        None => None,
+    };
+    let show_moons = args.iter().any(|a| a == "--moons");
+    Graph::new(kingdoms, moons, filter, show_moons)?.print(kingdoms, format);
+    Ok(())
+}
+
+fn stats(kingdoms: &Kingdoms, moons: &Moons, args: &[String]) -> Result<(), RandomizerError> {
+    let runs = match option_value(args, "--runs") {
+        Some(n) => match n.parse::<usize>() {
+            Ok(runs) if runs > 0 => runs,
+            _ => {
+                eprintln!("Invalid number of runs: {}", n);
+                process::exit(1);
+            }
+        },
+        None => 1000,
+    };
+    let pool = MoonPool::new(moons);
+    let state = State::new();
+    let stats = route::generate_many(kingdoms, moons, &pool, &state,
+                                     thread_rng().gen(), runs)
+        .try_fold(Stats::new, |mut stats, s| {
+            // only a route that ran out of moons or kingdoms is a failure,
+            // anything else is a problem with the data
+            match s {
+                Ok(s) => stats.add(kingdoms, moons, &s)?,
+                Err(RandomizerError::IncompleteRoute { .. })
+                | Err(RandomizerError::NothingToSchedule(_)) => stats.add_failure(),
+                Err(e) => return Err(e),
+            }
+            Ok(stats)
+        })
+        .try_reduce(Stats::new, |a, b| Ok(a.merge(b)))?;
+    stats.print(kingdoms)
+}
+
+fn verify(kingdoms: &Kingdoms, moons: &Moons, args: &[String]) -> Result<(), RandomizerError> {
+    let path = match args {
+        [path] => path,
+        _ => {
+            eprintln!("Usage: odyssey_randomizer verify <route file>");
+            process::exit(1);
+        }
+    };
+    let route = RouteFile::read(kingdoms, moons, path)?;
+    let fingerprint = Fingerprint::new(kingdoms, moons, route.moons())?.to_string();
+    println!("Fingerprint: {}", fingerprint);
+    // a route that was edited no longer matches the fingerprint it came with
+    match route.fingerprint() {
+        Some(f) if f == fingerprint => println!("Matches the fingerprint in the file"),
+        Some(f) => {
+            println!("Does not match the fingerprint in the file: {}", f);
+            process::exit(1);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+fn bingo(kingdoms: &Kingdoms, moons: &Moons, args: &[String]) -> Result<(), RandomizerError> {
+    let size = match option_value(args, "--size") {
+        Some(n) => match n.parse::<usize>() {
+            Ok(size) if (1..=9).contains(&size) => size,
+            _ => {
+                eprintln!("Invalid board size: {} (expected 1 to 9)", n);
+                process::exit(1);
+            }
+        },
+        None => 5,
+    };
+    let target = match option_value(args, "--target") {
+        Some(t) => match parse_duration(t) {
+            Some(target) if target > 0 => target,
+            _ => {
+                eprintln!("Invalid target time: {} (expected seconds, m:ss or h:mm:ss)",
+                          t);
+                process::exit(1);
+            }
+        },
+        None => 60 * 60,
+    };
+    let json = match option_value(args, "--format") {
+        Some("grid") | None => false,
+        Some("json") => true,
+        Some(f) => {
+            eprintln!("Unknown board format: {} (expected grid or json)", f);
+            process::exit(1);
+        }
+    };
+    let seed = match option_value(args, "--seed") {
+        Some(s) => match s.parse::<u64>() {
+            Ok(seed) => seed,
+            _ => {
+                eprintln!("Invalid seed: {}", s);
+                process::exit(1);
+            }
+        },
+        None => thread_rng().gen(),
+    };
+    let mut estimates = Estimates::new(moons);
+    if let Some(path) = option_value(args, "--times") {
+        estimates.load(moons, path)?;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let board = Board::generate(kingdoms, moons, &estimates, size, target, &mut rng)?;
+    if json {
+        print!("{}", board.json(moons)?);
+        Ok(())
+    } else {
+        println!("Seed: {}", seed);
+        board.print(moons)
+    }
+}
+
+fn option_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    let position = args.iter().position(|a| a == name)?;
+    match args.get(position + 1) {
+        Some(value) => Some(value.as_str()),
+        None => {
+            eprintln!("Missing value for {}", name);
+            process::exit(1);
+        }
+    }
+}
+
+fn settings(kingdoms: &Kingdoms, moons: &Moons,
+            args: &[String]) -> Result<Settings, RandomizerError> {
+    // a permalink already holds every generator setting
+    if let Some(p) = option_value(args, "--permalink") {
+        for option in ["--seed", "--curve", "--max-difficulty", "--best-of",
+                       "--metric"].iter() {
+            if args.iter().any(|a| a == option) {
+                eprintln!("{} can't be used with --permalink", option);
+                process::exit(1);
+            }
+        }
+        return Settings::from_permalink(p, kingdoms, moons);
+    }
+
+    let seed = match option_value(args, "--seed") {
+        Some(s) => match s.parse::<u64>() {
+            Ok(seed) => seed,
+            _ => {
+                eprintln!("Invalid seed: {}", s);
+                process::exit(1);
+            }
+        },
+        None => thread_rng().gen(),
+    };
+    let curve = match option_value(args, "--curve") {
+        Some(c) => match c.parse::<f64>() {
+            Ok(curve) if curve >= 0.0 => curve,
+            _ => {
+                eprintln!("Invalid curve: {} (expected a number from 0)", c);
+                process::exit(1);
+            }
+        },
+        None => 0.0,
+    };
+    let max_difficulty = match option_value(args, "--max-difficulty") {
+        Some(d) => match d.parse::<u8>() {
+            Ok(max) if (1..=5).contains(&max) => Some(max),
+            _ => {
+                eprintln!("Invalid difficulty: {} (expected 1 to 5)", d);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let metric = match option_value(args, "--metric") {
+        Some(m) => match Metric::from_name(m) {
+            Some(metric) => metric,
+            None => {
+                eprintln!("Unknown metric: {} (expected visits, revisits, variance or gap)",
+                          m);
+                process::exit(1);
+            }
+        },
+        None => Metric::Visits,
+    };
+    let runs = match option_value(args, "--best-of") {
+        Some(n) => match n.parse::<u32>() {
+            Ok(runs) if runs > 0 => runs,
+            _ => {
+                eprintln!("Invalid number of routes: {}", n);
+                process::exit(1);
+            }
+        },
+        None => 1,
+    };
+    Ok(Settings::new(seed, curve, max_difficulty, runs, metric))
+}
+
+fn best_route(kingdoms: &Kingdoms, moons: &Moons,
+              settings: &Settings) -> Result<(Score, State), RandomizerError> {
+    let mut pool = MoonPool::new(moons);
+    if let Some(max) = settings.max_difficulty() {
+        pool.cap_difficulty(kingdoms, moons, max);
+    }
+
+    // generate every route from the same data, keeping the best one
+    let mut state = State::new();
+    state.set_difficulty_curve(settings.curve());
+    let metric = settings.metric();
+    let best = route::generate_many(kingdoms, moons, &pool, &state,
+                                    settings.seed(), settings.best_of() as usize)
+        .map(|s| s.and_then(|s| Ok((Score::new(moons, &s)?, s))))
+        .try_reduce_with(|a, b| {
+            // ties keep the earlier route
+            if b.0.value(metric) < a.0.value(metric) {
+                Ok(b)
             } else {
-                leave_chance += 1;
+                Ok(a)
+            }
+        })
+        .unwrap();
+    best
+}
+
+fn route(kingdoms: &Kingdoms, moons: &Moons, args: &[String]) -> Result<(), RandomizerError> {
+    let settings = settings(kingdoms, moons, args)?;
+    let mut estimates = Estimates::new(moons);
+    if let Some(path) = option_value(args, "--times") {
+        estimates.load(moons, path)?;
+    }
+    let text = match option_value(args, "--format") {
+        Some("full") | None => false,
+        Some("text") => true,
+        Some(f) => {
+            eprintln!("Unknown route format: {} (expected full or text)", f);
+            process::exit(1);
+        }
+    };
+    let (score, state) = best_route(kingdoms, moons, &settings)?;
+
+    // print out the moons, after the permalink to share them with and the
+    // fingerprint to check them by
+    let permalink = settings.permalink(kingdoms, moons);
+    let fingerprint = Fingerprint::new(kingdoms, moons, state.moons_ordered())?;
+    if text {
+        println!("# Permalink: {}", permalink);
+        println!("# Fingerprint: {}", fingerprint);
+        print!("{}", state.route_text(kingdoms, moons, &estimates)?);
+        Ok(())
+    } else {
+        println!("Permalink: {}", permalink);
+        println!("Fingerprint: {}", fingerprint);
+        score.print();
+        state.print_moons(kingdoms, moons, &estimates)
+    }
+}
+
+fn reveal(kingdoms: &Kingdoms, moons: &Moons, args: &[String]) -> Result<(), RandomizerError> {
+    let settings = settings(kingdoms, moons, args)?;
+    let step = match option_value(args, "--by") {
+        Some(b) => match Step::from_name(b) {
+            Some(step) => step,
+            None => {
+                eprintln!("Unknown step: {} (expected visit or moon)", b);
+                process::exit(1);
             }
+        },
+        None => Step::Visit,
+    };
+    let log = option_value(args, "--spoiler-log").unwrap_or("spoiler_log.txt");
+    // refused before the reveal starts, rather than losing the log at the end
+    let overwrite = args.iter().any(|a| a == "--overwrite");
+    if !overwrite && Path::new(log).exists() {
+        return Err(RandomizerError::Io {
+            path: String::from(log),
+            message: String::from("already exists, pass --overwrite to replace it"),
+        });
+    }
+    let mut estimates = Estimates::new(moons);
+    if let Some(path) = option_value(args, "--times") {
+        estimates.load(moons, path)?;
+    }
+    let (_, state) = best_route(kingdoms, moons, &settings)?;
+    let permalink = settings.permalink(kingdoms, moons);
+    let fingerprint = Fingerprint::new(kingdoms, moons, state.moons_ordered())?;
+
+    println!("Permalink: {}", permalink);
+    println!("Fingerprint: {}", fingerprint);
+    println!("Press enter for the next {}, type peek to look further ahead or quit to stop",
+             match step {
+                 Step::Visit => "visit",
+                 Step::Moon => "moon",
+             });
+    let mut reveal = Reveal::new(moons, &state, &estimates, step)?;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    while !reveal.finished() {
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+        match line.trim() {
+            "" => {
+                match step {
+                    Step::Visit => {
+                        let number = reveal.visit_number();
+                        let first = reveal.collected() + 1;
+                        let (kingdom, ids) = reveal.next_visit().unwrap();
+                        println!("==={}=== (visit {})", kingdoms.kingdom(kingdom)?.name(),
+                                 number);
+                        print_reveal_moons(moons, &state, ids, Some(first))?;
+                        if let Some(subtotal) = reveal.subtotal(number) {
+                            println!("\t{}", subtotal);
+                        }
+                    }
+                    Step::Moon => {
+                        let number = reveal.visit_number();
+                        let first = reveal.collected() + 1;
+                        let (id, arrived) = reveal.next_moon().unwrap();
+                        if arrived {
+                            println!("==={}=== (visit {})",
+                                     kingdoms.kingdom(moons.moon(id)?.kingdom())?.name(),
+                                     number);
+                        }
+                        print_reveal_moons(moons, &state, &[id], Some(first))?;
+                        // the last moon of a visit moves the reveal on to the next
+                        if reveal.visit_number() != number {
+                            if let Some(subtotal) = reveal.subtotal(number) {
+                                println!("\t{}", subtotal);
+                            }
+                        }
+                    }
+                }
+            }
+            "peek" => {
+                let first = reveal.collected() + 1;
+                match reveal.peek() {
+                    Some((kingdom, ids)) => {
+                        println!("Peeking ahead, this is logged");
+                        println!("---{}---", kingdoms.kingdom(kingdom)?.name());
+                        // peeking at the visit after next skips the numbers
+                        // of the next one, so these are left unnumbered
+                        let first = match step {
+                            Step::Visit => None,
+                            Step::Moon => Some(first),
+                        };
+                        print_reveal_moons(moons, &state, ids, first)?;
+                    }
+                    None => println!("Nothing further ahead"),
+                }
+            }
+            "quit" => break,
+            other => println!("Unknown command: {} (expected enter, peek or quit)", other),
         }
     }
+    if reveal.finished() {
+        println!("Projected finish: {}", format_duration(reveal.finish()));
+        println!("That's every moon!");
+    }
 
-    // print out the moons
-    state.print_moons(&kingdoms, &moons);
+    // the whole route, for after the run, with every peek noted at the top
+    let mut spoilers = format!("# Permalink: {}\n# Fingerprint: {}\n", permalink,
+                               fingerprint);
+    for p in reveal.peeks() {
+        spoilers.push_str(&format!("# {}\n", p));
+    }
+    spoilers.push_str(&state.route_text(kingdoms, moons, &estimates)?);
+    // create_new still catches a log written by something else mid reveal
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options.open(log)
+        .and_then(|mut f| f.write_all(spoilers.as_bytes()))
+        .map_err(|e| RandomizerError::Io {
+            path: String::from(log),
+            message: e.to_string(),
+        })?;
+    println!("Spoiler log written to {}", log);
+    Ok(())
 }
 
+fn print_reveal_moons(moons: &Moons, state: &State, ids: &[MoonID],
+                      first: Option<u16>) -> Result<(), RandomizerError> {
+    let mut number = first;
+    for id in ids {
+        let moon = moons.moon(*id)?;
+        let notes = state.capture_notes(moons, *id)?;
+        match number {
+            Some(x) if moon.count() > 1 => println!("{}.\t{} ({}){}", x, moon.name(),
+                                                    moon.count(), notes),
+            Some(x) if moon.count() == 1 => println!("{}.\t{}{}", x, moon.name(), notes),
+            _ => println!("\t{}{}", moon.name(), notes),
+        }
+        number = number.map(|x| x + moon.count());
+    }
+    Ok(())
+}
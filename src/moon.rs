@@ -1,66 +1,102 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::capture::Capture;
+use crate::data::Data;
+use crate::error::RandomizerError;
+use crate::hint_art::HintArt;
 use crate::kingdom::{Kingdoms, KingdomName};
+use crate::outfit::Outfit;
+use crate::requirement::{Event, Flag, Requirement};
 use crate::state::State;
+use crate::tag::Tag;
 
 pub type MoonID = usize;
 
+// the moons compiled from data/odyssey.txt by build.rs
+include!(concat!(env!("OUT_DIR"), "/moons.rs"));
+
+#[derive(Clone, Hash)]
 pub struct Moon {
-    name: String,
+    name: Cow<'static, str>,
     count: u16,
     kingdom: KingdomName,
-    prerequisite_kingdoms: Vec<(KingdomName, u8)>,
-    prerequisite_moons: Vec<MoonID>,
-    prerequisite_moon_count: u16,
+    prerequisites: Cow<'static, [Requirement]>,
+    achievement: bool,
+    advances_phase: bool,
+    captures: Cow<'static, [Capture]>,
+    coin_cost: u16,
+    breaks_moon_rock: bool,
+    hint_art: Option<HintArt>,
+    tags: Cow<'static, [Tag]>,
+    difficulty: u8,
+    time: u32,
 }
 
 impl Moon {
-    fn new(name: &str, kingdom: KingdomName) -> Self {
-        Moon {
-            name: String::from(name),
-            count: 1,
-            kingdom: kingdom,
-            prerequisite_kingdoms: Vec::new(),
-            prerequisite_moons: Vec::new(),
-            prerequisite_moon_count: 0,
-        }
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    fn new_multi(name: &str, kingdom: KingdomName) -> Self {
-        Moon {
-            name: String::from(name),
-            count: 3,
-            kingdom: kingdom,
-            prerequisite_kingdoms: Vec::new(),
-            prerequisite_moons: Vec::new(),
-            prerequisite_moon_count: 0,
-        }
+    pub fn kingdom(&self) -> KingdomName {
+        self.kingdom
     }
 
-    fn add_prereq_kingdom(&mut self, kingdom: KingdomName) {
-        self.prerequisite_kingdoms.push((kingdom, 1));
+    pub fn count(&self) -> u16 {
+        self.count
     }
 
-    fn add_prereq_kingdom_count(&mut self, kingdom: KingdomName, visited: u8) {
-        self.prerequisite_kingdoms.push((kingdom, visited));
+    pub fn achievement(&self) -> bool {
+        self.achievement
     }
 
-    fn add_prereq_moon(&mut self, moon: MoonID) {
-        self.prerequisite_moons.push(moon);
+    pub fn hint_art(&self) -> Option<&HintArt> {
+        self.hint_art.as_ref()
     }
 
-    fn set_prereq_moon_count(&mut self, count: u16) {
-        self.prerequisite_moon_count = count;
+    pub fn difficulty(&self) -> u8 {
+        self.difficulty
     }
 
-    pub fn name(&self) -> &str {
-        &self.name
+    pub fn time(&self) -> u32 {
+        self.time
     }
 
-    pub fn kingdom(&self) -> KingdomName {
-        self.kingdom
+    pub fn breaks_moon_rock(&self) -> bool {
+        self.breaks_moon_rock
     }
 
-    pub fn count(&self) -> u16 {
-        self.count
+    pub fn advances_phase(&self) -> bool {
+        self.advances_phase
+    }
+
+    pub fn coin_cost(&self) -> u16 {
+        self.coin_cost
+    }
+
+    // the outfits to buy for this moon, for the requirements it meets now
+    pub fn outfits<'a>(&'a self, state: &'a State) -> impl Iterator<Item = Outfit> + 'a {
+        self.prerequisites.iter().flat_map(move |r| r.outfits(state))
+    }
+
+    pub fn captures(&self) -> &[Capture] {
+        &self.captures
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    pub fn prerequisites(&self) -> &[Requirement] {
+        &self.prerequisites
+    }
+
+    pub fn prerequisite_kingdoms(&self) -> impl Iterator<Item = (KingdomName, u8)> + '_ {
+        self.prerequisites.iter().flat_map(|r| r.kingdoms())
+    }
+
+    pub fn prerequisite_moons(&self) -> impl Iterator<Item = MoonID> + '_ {
+        self.prerequisites.iter().flat_map(|r| r.moons())
     }
 
     pub fn available(&self, state: &State) -> bool {
@@ -69,2724 +105,568 @@ impl Moon {
             return false;
         }
 
-        // all prerequisite kingdoms must be scheduled
-        for (p, c) in &self.prerequisite_kingdoms {
-            if !state.kingdom_scheduled(*p, *c) {
-                return false;
-            }
-        }
-
-        // all prerequisite moons must be scheduled
-        for p in &self.prerequisite_moons {
-            if !state.moon_scheduled(*p) {
-                return false;
-            }
-        }
-
-        // enough moons need to be scheduled
-        if state.total_moons() < self.prerequisite_moon_count {
+        // the coin balance has to cover the cost of the moon
+        if state.coins() < u32::from(self.coin_cost) {
             return false;
         }
 
-        true
+        // every prerequisite must be satisfied
+        self.prerequisites.iter().all(|r| r.satisfied(state))
     }
 }
 
 pub struct Moons {
-    moons: Vec<Moon>,
-    offset: Vec<(usize, usize)>,
-    ids: Vec<MoonID>,
+    moons: Cow<'static, [Moon]>,
+    offset: Cow<'static, [(usize, usize)]>,
+    prerequisites: Vec<Vec<MoonID>>,
+    dependents: Vec<Vec<MoonID>>,
+    story: Vec<bool>,
+    // moons waiting on each moon being scheduled, once per requirement
+    waiting: Vec<Vec<MoonID>>,
+    // the events each moon and each kingdom visit set off
+    triggers: Vec<Vec<usize>>,
+    visits: Vec<Vec<usize>>,
+    // moon requirements to recheck on each event, from the lowest level
+    watchers: Vec<Vec<(MoonID, usize)>>,
+    // requirements each moon has unmet at the start of a route
+    unmet: Vec<u16>,
+    // how many of each event's watchers are met at the start of a route
+    reached: Vec<usize>,
 }
 
 impl Moons {
-    pub fn moon(&self, id: MoonID) -> &Moon {
-        &self.moons[id]
+    pub fn moon(&self, id: MoonID) -> Result<&Moon, RandomizerError> {
+        self.moons.get(id).ok_or(RandomizerError::BadMoonId(id))
     }
 
-    pub fn return_available(&mut self, state: &mut State) -> Vec<MoonID> {
-        let kingdom = state.current_kingdom();
-        let (s, e) = self.offset[kingdom as usize];
-        if s == e {
-            // no moons left in kingdom
-            state.complete_kingdom(kingdom);
-            return Vec::new();
-        }
+    pub fn iter(&self) -> impl Iterator<Item = (MoonID, &Moon)> {
+        self.moons.iter().enumerate()
+    }
 
-        // go from start to end, check availability
+    pub fn find(&self, name: &str) -> Vec<MoonID> {
         let mut ret = Vec::new();
-        let mut swap_point = s;
-        for x in s..e {
-            let id = self.ids[x];
-            if self.moons[id].available(&state) {
+        for (id, moon) in self.iter() {
+            if moon.name.eq_ignore_ascii_case(name) {
                 ret.push(id);
-                // swap x with start
-                self.ids[x] = self.ids[swap_point];
-                self.ids[swap_point] = id;
-                swap_point += 1;
             }
         }
+        ret
+    }
+
+    pub fn prerequisites(&self, id: MoonID) -> &Vec<MoonID> {
+        &self.prerequisites[id]
+    }
+
+    pub fn dependents(&self, id: MoonID) -> &Vec<MoonID> {
+        &self.dependents[id]
+    }
 
-        // finally update start by the amount copied out
-        let new_s = swap_point;
-        self.offset[kingdom as usize] = (new_s, e);
+    pub fn transitive_dependents(&self, id: MoonID) -> Vec<MoonID> {
+        // breadth first walk over the reverse edges
+        let mut ret = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::new();
+        seen.insert(id);
+        queue.push_back(id);
+        while let Some(current) = queue.pop_front() {
+            for d in &self.dependents[current] {
+                if seen.insert(*d) {
+                    ret.push(*d);
+                    queue.push_back(*d);
+                }
+            }
+        }
         ret
     }
 
-    pub fn new(kingdoms: &mut Kingdoms) -> Self {
-        let mut moons = Vec::new();
-        let mut offset = Vec::new();
-        // cap kingdom
-        let cap1 = moons.len();
-        moons.push(Moon::new("Frog-Jumping Above the Fog", KingdomName::Cap));
-        moons[cap1].add_prereq_kingdom(KingdomName::Cascade);
-        let cap2 = moons.len();
-        moons.push(Moon::new("Frog-Jumping from the Top Deck", KingdomName::Cap));
-        moons[cap2].add_prereq_kingdom(KingdomName::Cascade);
-        let cap3 = moons.len();
-        moons.push(Moon::new("Cap Kingdom Timer Challenge 1", KingdomName::Cap));
-        moons[cap3].add_prereq_kingdom(KingdomName::Cascade);
-        let cap4 = moons.len();
-        moons.push(Moon::new("Good Evening, Captain Toad!", KingdomName::Cap));
-        moons[cap4].add_prereq_kingdom(KingdomName::Cascade);
-        let cap5 = moons.len();
-        moons.push(Moon::new("Shopping in Bonneton", KingdomName::Cap));
-        moons[cap5].add_prereq_kingdom(KingdomName::Cascade);
-        let cap6 = moons.len();
-        moons.push(Moon::new("Skimming the Poison Tide", KingdomName::Cap));
-        moons[cap6].add_prereq_kingdom(KingdomName::Cascade);
-        let cap7 = moons.len();
-        moons.push(Moon::new("Slipping Through the Poison Tide", KingdomName::Cap));
-        moons[cap7].add_prereq_kingdom(KingdomName::Cascade);
-        let cap8 = moons.len();
-        moons.push(Moon::new("Push-Block Peril", KingdomName::Cap));
-        moons[cap8].add_prereq_kingdom(KingdomName::Cascade);
-        let cap9 = moons.len();
-        moons.push(Moon::new("Hidden Among the Push-Blocks", KingdomName::Cap));
-        moons[cap9].add_prereq_kingdom(KingdomName::Cascade);
-        let cap10 = moons.len();
-        moons.push(Moon::new("Searching the Frog Pond", KingdomName::Cap));
-        moons[cap10].add_prereq_kingdom(KingdomName::Cascade);
-        let cap11 = moons.len();
-        moons.push(Moon::new("Secrets of the Frog Pond", KingdomName::Cap));
-        moons[cap11].add_prereq_kingdom(KingdomName::Cascade);
-        let cap12 = moons.len();
-        moons.push(Moon::new("The Forgotten Treasure", KingdomName::Cap));
-        moons[cap12].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap13 = moons.len();
-        moons.push(Moon::new("Taxi Flying Through Bonneton", KingdomName::Cap));
-        moons[cap13].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap14 = moons.len();
-        moons.push(Moon::new("Bonneter Blockade", KingdomName::Cap));
-        moons[cap14].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap15 = moons.len();
-        moons.push(Moon::new("Cap Kingdom Regular Cup", KingdomName::Cap));
-        moons[cap15].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap16 = moons.len();
-        moons.push(Moon::new("Peach in the Cap Kingdom", KingdomName::Cap));
-        moons[cap16].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap18 = moons.len();
-        moons.push(Moon::new("Next to Glasses Bridge", KingdomName::Cap));
-        moons[cap18].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap19 = moons.len();
-        moons.push(Moon::new("Danger Sign", KingdomName::Cap));
-        moons[cap19].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap20 = moons.len();
-        moons.push(Moon::new("Under the Big One's Brim", KingdomName::Cap));
-        moons[cap20].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap21 = moons.len();
-        moons.push(Moon::new("Fly to the Edge of the Fog", KingdomName::Cap));
-        moons[cap21].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap22 = moons.len();
-        moons.push(Moon::new("Spin the Hat, Get a Prize", KingdomName::Cap));
-        moons[cap22].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap23 = moons.len();
-        moons.push(Moon::new("Hidden in a Sunken Hat", KingdomName::Cap));
-        moons[cap23].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap24 = moons.len();
-        moons.push(Moon::new("Fog-Shrouded Platform", KingdomName::Cap));
-        moons[cap24].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap25 = moons.len();
-        moons.push(Moon::new("Fog-Shrouded Platform", KingdomName::Cap));
-        moons[cap25].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap26 = moons.len();
-        moons.push(Moon::new("Caught Hopping Near the Ship!", KingdomName::Cap));
-        moons[cap26].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap27 = moons.len();
-        moons.push(Moon::new("Taking Notes: In the Fog", KingdomName::Cap));
-        moons[cap27].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap28 = moons.len();
-        moons.push(Moon::new("Cap Kingdom Timer Challenge 2", KingdomName::Cap));
-        moons[cap28].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap29 = moons.len();
-        moons.push(Moon::new("Cap Kingdom Master Cup", KingdomName::Cap));
-        moons[cap29].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[cap29].add_prereq_moon(cap15);
-        let cap30 = moons.len();
-        moons.push(Moon::new("Roll On and On", KingdomName::Cap));
-        moons[cap30].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap31 = moons.len();
-        moons.push(Moon::new("Precision Rolling", KingdomName::Cap));
-        moons[cap31].add_prereq_kingdom(KingdomName::Mushroom);
-        let mushroom41 = moons.len();
-        moons.push(Moon::new("Found with Mushroom kingdom Art", KingdomName::Cap));
-        moons[mushroom41].add_prereq_kingdom(KingdomName::Mushroom);
-        offset.push((cap1, moons.len()));
+    pub fn print_dependents(&self, kingdoms: &Kingdoms,
+                            id: MoonID) -> Result<(), RandomizerError> {
+        let moon = self.moon(id)?;
+        println!("{} ({})", moon.name(),
+                 kingdoms.kingdom(moon.kingdom())?.name());
+        let direct = self.dependents(id);
+        let transitive: Vec<MoonID> = self.transitive_dependents(id)
+            .into_iter()
+            .filter(|d| !direct.contains(d))
+            .collect();
+        println!("Directly unlocks:");
+        self.print_grouped(kingdoms, direct)?;
+        println!("Transitively unlocks:");
+        self.print_grouped(kingdoms, &transitive)
+    }
 
-        // cascade kingdom
-        let cascade1 = moons.len();
-        moons.push(Moon::new("Our First Power Moon", KingdomName::Cascade));
-        let cascade2 = moons.len();
-        moons.push(Moon::new_multi("Multi Moon Atop the Falls", KingdomName::Cascade));
-        moons[cascade2].add_prereq_moon(cascade1);
-        kingdoms.kingdom_mut(KingdomName::Cascade).set_exit_moon(cascade2);
-        let cascade3 = moons.len();
-        moons.push(Moon::new("Chomp Through the Rocks", KingdomName::Cascade));
-        moons[cascade3].add_prereq_moon(cascade1);
-        let cascade4 = moons.len();
-        moons.push(Moon::new("Behind the Waterfall", KingdomName::Cascade));
-        moons[cascade4].add_prereq_moon(cascade1);
-        let cascade5 = moons.len();
-        moons.push(Moon::new("On Top of the Rubble", KingdomName::Cascade));
-        moons[cascade5].add_prereq_moon(cascade2);
-        let cascade6 = moons.len();
-        moons.push(Moon::new("Treasure of the Waterfall Basin", KingdomName::Cascade));
-        moons[cascade6].add_prereq_moon(cascade2);
-        let cascade7 = moons.len();
-        moons.push(Moon::new("Above a High Cliff", KingdomName::Cascade));
-        moons[cascade7].add_prereq_moon(cascade2);
-        let cascade8 = moons.len();
-        moons.push(Moon::new("Across the Floating Isles", KingdomName::Cascade));
-        moons[cascade8].add_prereq_moon(cascade2);
-        let cascade9 = moons.len();
-        moons.push(Moon::new("Cascade Kingdom Timer Challenge 1", KingdomName::Cascade));
-        moons[cascade9].add_prereq_moon(cascade2);
-        let cascade10 = moons.len();
-        moons.push(Moon::new("Cascade Kingdom Timer Challenge 2", KingdomName::Cascade));
-        moons[cascade10].add_prereq_moon(cascade2);
-        let cascade11 = moons.len();
-        moons.push(Moon::new("Good Morning, Captain Toad!", KingdomName::Cascade));
-        moons[cascade11].add_prereq_moon(cascade2);
-        let cascade12 = moons.len();
-        moons.push(Moon::new("Dinosaur Nest: Big Cleanup!", KingdomName::Cascade));
-        moons[cascade12].add_prereq_moon(cascade2);
-        let cascade13 = moons.len();
-        moons.push(Moon::new("Dinosaur Nest: Running Wild!", KingdomName::Cascade));
-        moons[cascade13].add_prereq_moon(cascade2);
-        let cascade14 = moons.len();
-        moons.push(Moon::new("Nice Shot with the Chain Chomp!", KingdomName::Cascade));
-        moons[cascade14].add_prereq_moon(cascade2);
-        let cascade15 = moons.len();
-        moons.push(Moon::new("Very Nice Shot with the Chain Chomp!", KingdomName::Cascade));
-        moons[cascade15].add_prereq_moon(cascade2);
-        let cascade16 = moons.len();
-        moons.push(Moon::new("Past the Chasm Lifts", KingdomName::Cascade));
-        moons[cascade16].add_prereq_moon(cascade2);
-        let cascade17 = moons.len();
-        moons.push(Moon::new("Hidden Chasm Passage", KingdomName::Cascade));
-        moons[cascade17].add_prereq_moon(cascade2);
-        let cascade18 = moons.len();
-        moons.push(Moon::new("Secret Path to Fossil Falls", KingdomName::Cascade));
-        let cascade19 = moons.len();
-        moons.push(Moon::new("A Tourist in the Cascade Kingdom", KingdomName::Cascade));
-        let cascade20 = moons.len();
-        moons.push(Moon::new("Rolling Rock by the Falls", KingdomName::Cascade));
-        moons[cascade20].add_prereq_kingdom_count(KingdomName::Cascade, 2);
-        let cascade21 = moons.len();
-        moons.push(Moon::new("Peach in the Cascade Kingdom", KingdomName::Cascade));
-        moons[cascade21].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade22 = moons.len();
-        moons.push(Moon::new("Cascade Kingdom Regular Cup", KingdomName::Cascade));
-        moons[cascade22].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade23 = moons.len();
-        moons.push(Moon::new("Caveman Cave-Fan", KingdomName::Cascade));
-        moons[cascade23].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade24 = moons.len();
-        moons.push(Moon::new("Shopping in Fossil Falls", KingdomName::Cascade));
-        moons[cascade24].add_prereq_kingdom_count(KingdomName::Cascade, 2);
-        let cascade25 = moons.len();
-        moons.push(Moon::new("Sphynx Traveling to the Waterfall", KingdomName::Cascade));
-        moons[cascade25].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade26 = moons.len();
-        moons.push(Moon::new("Bottom of the Waterfall Basin", KingdomName::Cascade));
-        moons[cascade26].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade27 = moons.len();
-        moons.push(Moon::new("Just a Hat, Skip, and a Jump", KingdomName::Cascade));
-        moons[cascade27].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade28 = moons.len();
-        moons.push(Moon::new("Treasure Under the Cliff", KingdomName::Cascade));
-        moons[cascade28].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade29 = moons.len();
-        moons.push(Moon::new("Next to the Stone Arch", KingdomName::Cascade));
-        moons[cascade29].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade30 = moons.len();
-        moons.push(Moon::new("Guarded by a Colossal Fossil", KingdomName::Cascade));
-        moons[cascade30].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade31 = moons.len();
-        moons.push(Moon::new("Under the Old Electrical Pole", KingdomName::Cascade));
-        moons[cascade31].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade32 = moons.len();
-        moons.push(Moon::new("Under the Ground", KingdomName::Cascade));
-        moons[cascade32].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade33 = moons.len();
-        moons.push(Moon::new("Inside the Busted Fossil", KingdomName::Cascade));
-        moons[cascade33].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade34 = moons.len();
-        moons.push(Moon::new("Caught Hopping at the Waterfall", KingdomName::Cascade));
-        moons[cascade34].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade35 = moons.len();
-        moons.push(Moon::new("Taking Notes: Hurry Upward", KingdomName::Cascade));
-        moons[cascade35].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade36 = moons.len();
-        moons.push(Moon::new("Cascade Kingdom Master Cup", KingdomName::Cascade));
-        moons[cascade36].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade37 = moons.len();
-        moons.push(Moon::new("Across the Mysterious Clouds", KingdomName::Cascade));
-        moons[cascade37].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade38 = moons.len();
-        moons.push(Moon::new("Atop a Wall Among the Clouds", KingdomName::Cascade));
-        moons[cascade38].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade39 = moons.len();
-        moons.push(Moon::new("Across the Gusty Bridges", KingdomName::Cascade));
-        moons[cascade39].add_prereq_kingdom(KingdomName::Mushroom);
-        let cascade40 = moons.len();
-        moons.push(Moon::new("Flying Far Away from Gusty Bridges", KingdomName::Cascade));
-        moons[cascade40].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake27 = moons.len();
-        moons.push(Moon::new("Found with Lake Kingdom Art", KingdomName::Cascade));
-        moons[lake27].add_prereq_kingdom(KingdomName::Lake);
-        moons[lake27].add_prereq_kingdom_count(KingdomName::Cascade, 2);
-        let dark15 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 1", KingdomName::Cascade));
-        moons[dark15].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((cascade1, moons.len()));
+    fn print_grouped(&self, kingdoms: &Kingdoms,
+                     ids: &[MoonID]) -> Result<(), RandomizerError> {
+        if ids.is_empty() {
+            println!("\tnothing");
+            return Ok(());
+        }
+        // group by kingdom, keeping data order within each kingdom
+        let mut sorted = ids.to_vec();
+        sorted.sort_by_key(|id| (self.moons[*id].kingdom() as usize, *id));
+        let mut current_kingdom = None;
+        for id in sorted {
+            let moon = &self.moons[id];
+            if current_kingdom != Some(moon.kingdom()) {
+                println!("==={}===", kingdoms.kingdom(moon.kingdom())?.name());
+                current_kingdom = Some(moon.kingdom());
+            }
+            let mut line = format!("\t{}", moon.name());
+            if let Some(k) = kingdoms.exit_for(id) {
+                line.push_str(&format!(" [exit: {}]", k.name()));
+            }
+            if moon.achievement() {
+                line.push_str(" [achievement]");
+            }
+            println!("{}", line);
+        }
+        Ok(())
+    }
 
-        // sand kingdom
-        let sand1 = moons.len();
-        moons.push(Moon::new("Atop the Highest Tower", KingdomName::Sand));
-        let sand2 = moons.len();
-        moons.push(Moon::new("Moon Shards in the Sand", KingdomName::Sand));
-        moons[sand2].add_prereq_moon(sand1);
-        let sand3 = moons.len();
-        moons.push(Moon::new_multi("Showdown on the Inverted Pyramid", KingdomName::Sand));
-        moons[sand3].add_prereq_moon(sand2);
-        let sand4 = moons.len();
-        moons.push(Moon::new_multi("The Hole in the Desert", KingdomName::Sand));
-        moons[sand4].add_prereq_moon(sand3);
-        kingdoms.kingdom_mut(KingdomName::Sand).set_exit_moon(sand4);
-        moons.push(Moon::new("Overlooking the Desert Town", KingdomName::Sand));
-        moons.push(Moon::new("Alcove in the Ruins", KingdomName::Sand));
-        moons.push(Moon::new("On the Leaning Pillar", KingdomName::Sand));
-        moons.push(Moon::new("Hidden Room in the Flowing Sands", KingdomName::Sand));
-        let sand9 = moons.len();
-        moons.push(Moon::new("Secret of the Mural", KingdomName::Sand));
-        let sand10 = moons.len();
-        moons.push(Moon::new("Secret of the Inverted Mural", KingdomName::Sand));
-        moons[sand10].add_prereq_moon(sand2);
-        moons.push(Moon::new("On Top of the Stone Archway", KingdomName::Sand));
-        moons.push(Moon::new("From a Crate in the Ruins", KingdomName::Sand));
-        moons.push(Moon::new("On the Lone Pillar", KingdomName::Sand));
-        let sand14 = moons.len();
-        moons.push(Moon::new("On the Statue's Tail", KingdomName::Sand));
-        moons[sand14].add_prereq_moon(sand2);
-        let sand15 = moons.len();
-        moons.push(Moon::new("Hang Your Hat on the Fountain", KingdomName::Sand));
-        moons[sand15].add_prereq_moon(sand4);
-        let sand16 = moons.len();
-        moons.push(Moon::new("Where the Birds Gather", KingdomName::Sand));
-        let sand17 = moons.len();
-        moons.push(Moon::new("Top of a Dune", KingdomName::Sand));
-        let sand18 = moons.len();
-        moons.push(Moon::new("Lost in the Luggage", KingdomName::Sand));
-        let sand19 = moons.len();
-        moons.push(Moon::new("Bullet Bill Breakthrough", KingdomName::Sand));
-        moons[sand19].add_prereq_moon(sand4);
-        moons.push(Moon::new("Inside a Block is a Hard Place", KingdomName::Sand));
-        let sand21 = moons.len();
-        moons.push(Moon::new("Bird Traveling the Desert", KingdomName::Sand));
-        let sand22 = moons.len();
-        moons.push(Moon::new("Bird Traveling the Wastes", KingdomName::Sand));
-        moons[sand22].add_prereq_moon(sand4);
-        let sand23 = moons.len();
-        moons.push(Moon::new("The Lurker Under the Stone", KingdomName::Sand));
-        moons[sand23].add_prereq_moon(sand4);
-        let sand24 = moons.len();
-        moons.push(Moon::new("The Treasure of Jaxi Ruins", KingdomName::Sand));
-        let sand25 = moons.len();
-        moons.push(Moon::new("Desert Gardening: Plaza Seed", KingdomName::Sand));
-        let sand26 = moons.len();
-        moons.push(Moon::new("Desert Gardening: Ruins Seed", KingdomName::Sand));
-        let sand27 = moons.len();
-        moons.push(Moon::new("Desert Gardening: Seed on the Cliff", KingdomName::Sand));
-        let sand28 = moons.len();
-        moons.push(Moon::new("Sand Kingdom Timer Challenge 1", KingdomName::Sand));
-        moons[sand28].add_prereq_moon(sand4);
-        let sand29 = moons.len();
-        moons.push(Moon::new("Sand Kingdom Timer Challenge 2", KingdomName::Sand));
-        moons[sand29].add_prereq_moon(sand4);
-        let sand30 = moons.len();
-        moons.push(Moon::new("Sand Kingdom Timer Challenge 3", KingdomName::Sand));
-        moons[sand30].add_prereq_moon(sand4);
-        let sand31 = moons.len();
-        moons.push(Moon::new("Found in the Sand! Good Dog!", KingdomName::Sand));
-        moons[sand31].add_prereq_moon(sand4);
-        let sand32 = moons.len();
-        moons.push(Moon::new("Taking Notes: Jump on the Palm", KingdomName::Sand));
-        let sand33 = moons.len();
-        moons.push(Moon::new("Herding Sheep in the Dunes", KingdomName::Sand));
-        let sand34 = moons.len();
-        moons.push(Moon::new("Fishing in the Oasis", KingdomName::Sand));
-        moons[sand34].add_prereq_moon(sand4);
-        let sand35 = moons.len();
-        moons.push(Moon::new("Love in the Heart of the Desert", KingdomName::Sand));
-        moons[sand35].add_prereq_moon(sand4);
-        moons.push(Moon::new("Among the Five Cactuses", KingdomName::Sand));
-        let sand37 = moons.len();
-        moons.push(Moon::new("You're Quite a Catch, Captain Toad!", KingdomName::Sand));
-        moons[sand37].add_prereq_moon(sand4);
-        let sand38 = moons.len();
-        moons.push(Moon::new("Jaxi Reunion!", KingdomName::Sand));
-        moons[sand38].add_prereq_moon(sand4);
-        let sand39 = moons.len();
-        moons.push(Moon::new("Welcome Back, Jaxi!", KingdomName::Sand));
-        moons[sand39].add_prereq_moon(sand4);
-        let sand40 = moons.len();
-        moons.push(Moon::new("Wandering Cactus", KingdomName::Sand));
-        let sand41 = moons.len();
-        moons.push(Moon::new("Sand Quiz: Wonderful!", KingdomName::Sand));
-        moons.push(Moon::new("Shopping in Tostarena", KingdomName::Sand));
-        moons.push(Moon::new("Employees Only", KingdomName::Sand));
-        let sand44 = moons.len();
-        moons.push(Moon::new("Sand Kingdom Slots", KingdomName::Sand));
-        let sand45 = moons.len();
-        moons.push(Moon::new("Walking the Desert", KingdomName::Sand));
-        let sand46 = moons.len();
-        moons.push(Moon::new("Hidden Room in the Inverted Pyramid", KingdomName::Sand));
-        moons[sand46].add_prereq_moon(sand2);
-        let sand47 = moons.len();
-        moons.push(Moon::new("Underground Treasure Chest", KingdomName::Sand));
-        moons[sand47].add_prereq_moon(sand3);
-        let sand48 = moons.len();
-        moons.push(Moon::new("Goomba Tower Assembly", KingdomName::Sand));
-        moons[sand48].add_prereq_moon(sand3);
-        let sand49 = moons.len();
-        moons.push(Moon::new("Under the Mummy's Curse", KingdomName::Sand));
-        moons[sand49].add_prereq_moon(sand4);
-        moons.push(Moon::new("Ice Cave Treasure", KingdomName::Sand));
-        let sand51 = moons.len();
-        moons.push(Moon::new("Sphynx's Treasure Vault", KingdomName::Sand));
-        let sand52 = moons.len();
-        moons.push(Moon::new("A Rumble from the Sandy Floor", KingdomName::Sand));
-        let sand53 = moons.len();
-        moons.push(Moon::new("Dancing with New Friends", KingdomName::Sand));
-        moons.push(Moon::new("The Invisible Maze", KingdomName::Sand));
-        let sand55 = moons.len();
-        moons.push(Moon::new("Skull Sign in the Transparent Maze", KingdomName::Sand));
-        moons.push(Moon::new("The Bullet Bill Maze: Break Through!", KingdomName::Sand));
-        moons.push(Moon::new("The Bullet Bill Maze: Side Path", KingdomName::Sand));
-        moons.push(Moon::new("Jaxi Driver", KingdomName::Sand));
-        moons.push(Moon::new("Jaxi Stunt Driving", KingdomName::Sand));
-        let sand60 = moons.len();
-        moons.push(Moon::new("Strange Neighborhood", KingdomName::Sand));
-        moons[sand60].add_prereq_moon(sand4);
-        let sand61 = moons.len();
-        moons.push(Moon::new("Above a Strange Neighborhood", KingdomName::Sand));
-        moons[sand61].add_prereq_moon(sand4);
-        let sand62 = moons.len();
-        moons.push(Moon::new("Secret Path to Tostarena!", KingdomName::Sand));
-        moons[sand62].add_prereq_kingdom(KingdomName::Lake);
-        let sand64 = moons.len();
-        moons.push(Moon::new("Jammin' in the Sand Kingdom", KingdomName::Sand));
-        moons[sand64].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand65 = moons.len();
-        moons.push(Moon::new("Hat-and-Seek: In the Sand", KingdomName::Sand));
-        moons[sand65].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand66 = moons.len();
-        moons.push(Moon::new("Sand Kingdom Regular Cup", KingdomName::Sand));
-        moons[sand66].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand67 = moons.len();
-        moons.push(Moon::new("Binding Band Returned", KingdomName::Sand));
-        moons[sand67].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand68 = moons.len();
-        moons.push(Moon::new("Round-the-World Tourist", KingdomName::Sand));
-        let sand69 = moons.len();
-        moons.push(Moon::new("Peach in the Sand Kingdom", KingdomName::Sand));
-        moons[sand69].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand70 = moons.len();
-        moons.push(Moon::new("Mighty Leap from the Palm Tree!", KingdomName::Sand));
-        moons[sand70].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand71 = moons.len();
-        moons.push(Moon::new("On the North Pillar", KingdomName::Sand));
-        moons[sand71].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand72 = moons.len();
-        moons.push(Moon::new("Into the Flowing Sands", KingdomName::Sand));
-        moons[sand72].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand73 = moons.len();
-        moons.push(Moon::new("In the Skies Above the Canyon", KingdomName::Sand));
-        moons[sand73].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand74 = moons.len();
-        moons.push(Moon::new("Island in the Poison Swamp", KingdomName::Sand));
-        moons[sand74].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand75 = moons.len();
-        moons.push(Moon::new("An Invisible Gleam", KingdomName::Sand));
-        moons[sand75].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand76 = moons.len();
-        moons.push(Moon::new("On the Eastern Pillar", KingdomName::Sand));
-        moons[sand76].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand77 = moons.len();
-        moons.push(Moon::new("Caught Hopping in the Desert!", KingdomName::Sand));
-        moons[sand77].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand78 = moons.len();
-        moons.push(Moon::new("Poster Cleanup", KingdomName::Sand));
-        moons[sand78].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand79 = moons.len();
-        moons.push(Moon::new("Taking Notes: Running Down", KingdomName::Sand));
-        moons[sand79].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand80 = moons.len();
-        moons.push(Moon::new("Taking Notes: In the Wall Painting", KingdomName::Sand));
-        moons[sand80].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand81 = moons.len();
-        moons.push(Moon::new("Love at the Edge of the Desert", KingdomName::Sand));
-        moons[sand81].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand82 = moons.len();
-        moons.push(Moon::new("More Walking in the Desert!", KingdomName::Sand));
-        moons[sand82].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[sand82].add_prereq_moon(sand45);
-        let sand83 = moons.len();
-        moons.push(Moon::new("Sand Kingdom Master Cup", KingdomName::Sand));
-        moons[sand83].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[sand83].add_prereq_moon(sand66);
-        let sand84 = moons.len();
-        moons.push(Moon::new("Where the Transparent Platforms End", KingdomName::Sand));
-        moons[sand84].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand85 = moons.len();
-        moons.push(Moon::new("Jump Onto the Transparent Lift", KingdomName::Sand));
-        moons[sand85].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand86 = moons.len();
-        moons.push(Moon::new("Colossal Ruins: Dash! Jump!", KingdomName::Sand));
-        moons[sand86].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand87 = moons.len();
-        moons.push(Moon::new("Sinking Colossal Ruins: Hurry!", KingdomName::Sand));
-        moons[sand87].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand88 = moons.len();
-        moons.push(Moon::new("Through the Freezing Waterway", KingdomName::Sand));
-        moons[sand88].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand89 = moons.len();
-        moons.push(Moon::new("Freezing Waterway: Hidden Room", KingdomName::Sand));
-        moons[sand89].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded50 = moons.len();
-        moons.push(Moon::new("Found with Wooded Kingdom Art", KingdomName::Sand));
-        moons[wooded50].add_prereq_kingdom(KingdomName::Wooded);
-        moons[wooded50].add_prereq_kingdom_count(KingdomName::Sand, 2);
-        let bowser45 = moons.len();
-        moons.push(Moon::new("Found with Bowser's Kingdom Art", KingdomName::Sand));
-        moons[bowser45].add_prereq_kingdom(KingdomName::Bowser);
-        moons[bowser45].add_prereq_kingdom_count(KingdomName::Sand, 2);
-        offset.push((sand1, moons.len()));
+    pub fn total_count(&self) -> u16 {
+        self.moons.iter().map(|m| m.count).sum()
+    }
 
-        // lake kingdom
-        let lake1 = moons.len();
-        moons.push(Moon::new_multi("Broodals Over the Lake", KingdomName::Lake));
-        kingdoms.kingdom_mut(KingdomName::Lake).set_exit_moon(lake1);
-        moons.push(Moon::new("Dorrie-Back Rider", KingdomName::Lake));
-        moons.push(Moon::new("Cheep Cheep Crossing", KingdomName::Lake));
-        moons.push(Moon::new("End of the Hidden Passage", KingdomName::Lake));
-        moons.push(Moon::new("What's in the Box?", KingdomName::Lake));
-        let lake6 = moons.len();
-        moons.push(Moon::new("On the Lakeshore", KingdomName::Lake));
-        let lake7 = moons.len();
-        moons.push(Moon::new("From the Broken Pillar", KingdomName::Lake));
-        let lake8 = moons.len();
-        moons.push(Moon::new("Treasure in the Spiky Waterway", KingdomName::Lake));
-        let lake9 = moons.len();
-        moons.push(Moon::new("Lake Gardening: Spiky Passage Seed", KingdomName::Lake));
-        moons[lake9].add_prereq_moon(lake1);
-        let lake10 = moons.len();
-        moons.push(Moon::new("Lake Kingdom Timer Challenge 1", KingdomName::Lake));
-        moons[lake10].add_prereq_moon(lake1);
-        let lake11 = moons.len();
-        moons.push(Moon::new("Lake Kingdom Timer Challenge 2", KingdomName::Lake));
-        moons[lake11].add_prereq_moon(lake1);
-        moons.push(Moon::new("Moon Shards in the Lake", KingdomName::Lake));
-        let lake13 = moons.len();
-        moons.push(Moon::new("Taking Notes: Dive and Swim", KingdomName::Lake));
-        let lake14 = moons.len();
-        moons.push(Moon::new("Taking Notes: In the Cliffside", KingdomName::Lake));
-        let lake15 = moons.len();
-        moons.push(Moon::new("Lake Fishing", KingdomName::Lake));
-        moons[lake15].add_prereq_moon(lake1);
-        let lake16 = moons.len();
-        moons.push(Moon::new("I Met a Lake Cheep Cheep!", KingdomName::Lake));
-        moons[lake16].add_prereq_moon(lake1);
-        moons.push(Moon::new("Our Secret Little Room", KingdomName::Lake));
-        let lake18 = moons.len();
-        moons.push(Moon::new("Let's Go Swimming, Captain Toad!", KingdomName::Lake));
-        moons.push(Moon::new("Shopping in Lake Lamode", KingdomName::Lake));
-        let lake20 = moons.len();
-        moons.push(Moon::new("A Successful Repair Job", KingdomName::Lake));
-        moons[lake20].add_prereq_moon(lake1);
-        let lake21 = moons.len();
-        moons.push(Moon::new("I Feel Underdressed", KingdomName::Lake));
-        moons.push(Moon::new("Unzip the Chasm", KingdomName::Lake));
-        moons.push(Moon::new("Super-Secret Zipper", KingdomName::Lake));
-        moons.push(Moon::new("Jump, Grab, Cling, and Climb", KingdomName::Lake));
-        let lake25 = moons.len();
-        moons.push(Moon::new("Jump, Grab, and Climb Some More", KingdomName::Lake));
-        let lake26 = moons.len();
-        moons.push(Moon::new("Secret Path to Lake Lamode!", KingdomName::Lake));
-        moons[lake27].add_prereq_moon(lake1);
-        let lake28 = moons.len();
-        moons.push(Moon::new("Taxi Flying Through Lake Lamode", KingdomName::Lake));
-        moons[lake28].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake29 = moons.len();
-        moons.push(Moon::new("That Trendy \"Pirate\" Look", KingdomName::Lake));
-        moons[lake29].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake30 = moons.len();
-        moons.push(Moon::new("Space Is \"In\" Right Now", KingdomName::Lake));
-        moons[lake30].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake31 = moons.len();
-        moons.push(Moon::new("That \"Old West\" Style", KingdomName::Lake));
-        moons[lake31].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake32 = moons.len();
-        moons.push(Moon::new("Lake Kingdom Regular Cup", KingdomName::Lake));
-        moons[lake32].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake33 = moons.len();
-        moons.push(Moon::new("Peach in the Lake Kingdom", KingdomName::Lake));
-        moons[lake33].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake34 = moons.len();
-        moons.push(Moon::new("Behind the Floodgate", KingdomName::Lake));
-        moons[lake34].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake35 = moons.len();
-        moons.push(Moon::new("High-Flying Leap", KingdomName::Lake));
-        moons[lake35].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake36 = moons.len();
-        moons.push(Moon::new("Deep, Deep Down", KingdomName::Lake));
-        moons[lake36].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake37 = moons.len();
-        moons.push(Moon::new("Rooftop of the Water Plaza", KingdomName::Lake));
-        moons[lake37].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake38 = moons.len();
-        moons.push(Moon::new("Bird Traveling Over the Lake", KingdomName::Lake));
-        moons[lake38].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake39 = moons.len();
-        moons.push(Moon::new("Love by the Lake", KingdomName::Lake));
-        moons[lake39].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake40 = moons.len();
-        moons.push(Moon::new("Lake Kingdom Master Cup", KingdomName::Lake));
-        moons[lake40].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[lake40].add_prereq_moon(lake32);
-        let lake41 = moons.len();
-        moons.push(Moon::new("Waves of Poison: Hoppin' Over", KingdomName::Lake));
-        moons[lake41].add_prereq_kingdom(KingdomName::Mushroom);
-        let lake42 = moons.len();
-        moons.push(Moon::new("Waves of Poison: Hop to It!", KingdomName::Lake));
-        moons[lake42].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro53 = moons.len();
-        moons.push(Moon::new("Found With Metro Kingdom Art", KingdomName::Lake));
-        moons[metro53].add_prereq_kingdom(KingdomName::Metro);
-        let dark23 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 9", KingdomName::Lake));
-        moons[dark23].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((lake1, moons.len()));
+    pub fn story(&self, id: MoonID) -> bool {
+        self.story[id]
+    }
 
-        // wooded kingdom
-        let wooded1 = moons.len();
-        moons.push(Moon::new("Road to Sky Garden", KingdomName::Wooded));
-        let wooded2 = moons.len();
-        moons.push(Moon::new_multi("Flower Thieves of Sky Garden", KingdomName::Wooded));
-        moons[wooded2].add_prereq_moon(wooded1);
-        moons[sand62].add_prereq_moon(wooded2);
-        let wooded3 = moons.len();
-        moons.push(Moon::new("Path to the Secret Flower Field", KingdomName::Wooded));
-        moons[wooded3].add_prereq_moon(wooded2);
-        let wooded4 = moons.len();
-        moons.push(Moon::new_multi("Defend the Secret Flower Field!", KingdomName::Wooded));
-        moons[wooded4].add_prereq_moon(wooded3);
-        kingdoms.kingdom_mut(KingdomName::Wooded).set_exit_moon(wooded4);
-        let wooded5 = moons.len();
-        moons.push(Moon::new("Behind the Rock Wall", KingdomName::Wooded));
-        moons[wooded5].add_prereq_moon(wooded2);
-        let wooded6 = moons.len();
-        moons.push(Moon::new("Back Way Up the Mountain", KingdomName::Wooded));
-        moons[wooded6].add_prereq_moon(wooded2);
-        moons.push(Moon::new("Rolling Rock in the Woods", KingdomName::Wooded));
-        let wooded8 = moons.len();
-        moons.push(Moon::new("Caught Hopping in the Forest!", KingdomName::Wooded));
-        let wooded9 = moons.len();
-        moons.push(Moon::new("Thanks for the Charge!", KingdomName::Wooded));
-        moons[wooded9].add_prereq_moon(wooded2);
-        moons.push(Moon::new("Atop the Tall Tree", KingdomName::Wooded));
-        moons.push(Moon::new("Tucked Way Inside the Tunnel", KingdomName::Wooded));
-        let wooded12 = moons.len();
-        moons.push(Moon::new("Over the Cliff's Edge", KingdomName::Wooded));
-        moons[wooded12].add_prereq_moon(wooded2);
-        moons.push(Moon::new("The Nut Round the Corner", KingdomName::Wooded));
-        moons.push(Moon::new("Climb the Cliff to Get the Nut", KingdomName::Wooded));
-        moons.push(Moon::new("The Nut in the Red Maze", KingdomName::Wooded));
-        moons.push(Moon::new("The Nut at the Dead End", KingdomName::Wooded));
-        let wooded17 = moons.len();
-        moons.push(Moon::new("Cracked Nut on a Crumbling Tower", KingdomName::Wooded));
-        moons[wooded17].add_prereq_moon(wooded1);
-        let wooded18 = moons.len();
-        moons.push(Moon::new("The Nut that Grew on the Tall Fence", KingdomName::Wooded));
-        moons[wooded18].add_prereq_moon(wooded2);
-        moons.push(Moon::new("Fire in the Cave", KingdomName::Wooded));
-        let wooded20 = moons.len();
-        moons.push(Moon::new("Hey Out There, Captain Toad!", KingdomName::Wooded));
-        moons[wooded20].add_prereq_moon(wooded4);
-        let wooded21 = moons.len();
-        moons.push(Moon::new("Love in the Forest Ruins", KingdomName::Wooded));
-        moons[wooded21].add_prereq_moon(wooded2);
-        let wooded22 = moons.len();
-        moons.push(Moon::new("Inside a Rock in the Forest", KingdomName::Wooded));
-        moons[wooded22].add_prereq_moon(wooded4);
-        moons.push(Moon::new("Shopping in Steam Gardens", KingdomName::Wooded));
-        let wooded24 = moons.len();
-        moons.push(Moon::new("Nut Planted in the Tower", KingdomName::Wooded));
-        moons[wooded24].add_prereq_moon(wooded1);
-        let wooded25 = moons.len();
-        moons.push(Moon::new("Stretching Your Legs", KingdomName::Wooded));
-        moons[wooded25].add_prereq_moon(wooded1);
-        let wooded26 = moons.len();
-        moons.push(Moon::new("Spinning-Platforms Treasure", KingdomName::Wooded));
-        moons[wooded26].add_prereq_moon(wooded2);
-        let wooded27 = moons.len();
-        moons.push(Moon::new("Make the Secret Flower Field Bloom", KingdomName::Wooded));
-        moons[wooded27].add_prereq_moon(wooded4);
-        moons.push(Moon::new("Rolling Rock in the Deep Woods", KingdomName::Wooded));
-        moons.push(Moon::new("Glowing in the Deep Woods", KingdomName::Wooded));
-        moons.push(Moon::new("Past the Peculiar Pipes", KingdomName::Wooded));
-        let wooded31 = moons.len();
-        moons.push(Moon::new("By the Babbling Brook in Deep Woods", KingdomName::Wooded));
-        let wooded32 = moons.len();
-        moons.push(Moon::new("The Hard Rock in Deep Woods", KingdomName::Wooded));
-        moons.push(Moon::new("A Treasure Made from Coins", KingdomName::Wooded));
-        let wooded34 = moons.len();
-        moons.push(Moon::new("Beneath the Roots of the Moving Tree", KingdomName::Wooded));
-        let wooded35 = moons.len();
-        moons.push(Moon::new("Deep Woods Treasure Trap", KingdomName::Wooded));
-        let wooded36 = moons.len();
-        moons.push(Moon::new("Exploring for Treasure", KingdomName::Wooded));
-        let wooded37 = moons.len();
-        moons.push(Moon::new("Wooded Kingdom Timer Challenge 1", KingdomName::Wooded));
-        moons[wooded37].add_prereq_moon(wooded4);
-        let wooded38 = moons.len();
-        moons.push(Moon::new("Wooded Kingdom Timer Challenge 2", KingdomName::Wooded));
-        moons[wooded38].add_prereq_moon(wooded4);
-        moons.push(Moon::new("Flooding Pipeway", KingdomName::Wooded));
-        moons.push(Moon::new("Flooding Pipeway Ceiling Secret", KingdomName::Wooded));
-        let wooded41 = moons.len();
-        moons.push(Moon::new("Wandering in the Fog", KingdomName::Wooded));
-        moons[wooded41].add_prereq_moon(wooded2);
-        let wooded42 = moons.len();
-        moons.push(Moon::new("Nut Hidden in the Fog", KingdomName::Wooded));
-        moons[wooded42].add_prereq_moon(wooded2);
-        let wooded43 = moons.len();
-        moons.push(Moon::new("Flower Road Run", KingdomName::Wooded));
-        moons[wooded43].add_prereq_moon(wooded2);
-        let wooded44 = moons.len();
-        moons.push(Moon::new("Flower Road Reach", KingdomName::Wooded));
-        moons[wooded44].add_prereq_moon(wooded2);
-        let wooded45 = moons.len();
-        moons.push(Moon::new("Elevator Escalation", KingdomName::Wooded));
-        moons[wooded45].add_prereq_moon(wooded2);
-        let wooded46 = moons.len();
-        moons.push(Moon::new("Elevator Blind Spot", KingdomName::Wooded));
-        moons[wooded46].add_prereq_moon(wooded2);
-        let wooded47 = moons.len();
-        moons.push(Moon::new("Walking on Clouds", KingdomName::Wooded));
-        moons[wooded47].add_prereq_moon(wooded4);
-        let wooded48 = moons.len();
-        moons.push(Moon::new("Above the Clouds", KingdomName::Wooded));
-        moons[wooded48].add_prereq_moon(wooded4);
-        let wooded49 = moons.len();
-        moons.push(Moon::new("Secret Path to Steam Gardens!", KingdomName::Wooded));
-        let wooded51 = moons.len();
-        moons.push(Moon::new("Swing Around Secret Flower Field", KingdomName::Wooded));
-        moons[wooded51].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded52 = moons.len();
-        moons.push(Moon::new("Jammin' in the Wooded Kingdom", KingdomName::Wooded));
-        moons[wooded52].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded53 = moons.len();
-        moons.push(Moon::new("Wooded Kingdom Regular Cup", KingdomName::Wooded));
-        moons[wooded53].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded54 = moons.len();
-        moons.push(Moon::new("Peach in the Wooded Kingdom", KingdomName::Wooded));
-        moons[wooded54].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded55 = moons.len();
-        moons.push(Moon::new("High Up in the Cave", KingdomName::Wooded));
-        moons[wooded55].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded56 = moons.len();
-        moons.push(Moon::new("Lost in the Tall Trees", KingdomName::Wooded));
-        moons[wooded56].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded57 = moons.len();
-        moons.push(Moon::new("Looking Down on the Goombas", KingdomName::Wooded));
-        moons[wooded57].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded58 = moons.len();
-        moons.push(Moon::new("High Up on a Rock Wall", KingdomName::Wooded));
-        moons[wooded58].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded59 = moons.len();
-        moons.push(Moon::new("The Nut in the Robot Storeroom", KingdomName::Wooded));
-        moons[wooded59].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded60 = moons.len();
-        moons.push(Moon::new("Above the Iron Mountain Path", KingdomName::Wooded));
-        moons[wooded60].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded61 = moons.len();
-        moons.push(Moon::new("The Nut Under the Observation Deck", KingdomName::Wooded));
-        moons[wooded61].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded62 = moons.len();
-        moons.push(Moon::new("Bird Traveling the Forest", KingdomName::Wooded));
-        moons[wooded62].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded63 = moons.len();
-        moons.push(Moon::new("Invader in the Sky Garden", KingdomName::Wooded));
-        moons[wooded63].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded64 = moons.len();
-        moons.push(Moon::new("Hot, Hot, Hot from the Campfire", KingdomName::Wooded));
-        moons[wooded64].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded65 = moons.len();
-        moons.push(Moon::new("Wooded Kingdom Timer Challenge 3", KingdomName::Wooded));
-        moons[wooded65].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded66 = moons.len();
-        moons.push(Moon::new("Moon Shards in the Forest", KingdomName::Wooded));
-        moons[wooded66].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded67 = moons.len();
-        moons.push(Moon::new("Taking Notes: On Top of the Wall", KingdomName::Wooded));
-        moons[wooded67].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded68 = moons.len();
-        moons.push(Moon::new("Taking Notes: Stretching", KingdomName::Wooded));
-        moons[wooded68].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded69 = moons.len();
-        moons.push(Moon::new("Wooded Kingdom Master Cup", KingdomName::Wooded));
-        moons[wooded69].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[wooded69].add_prereq_moon(wooded53);
-        let wooded70 = moons.len();
-        moons.push(Moon::new("I Met an Uproot!", KingdomName::Wooded));
-        moons[wooded70].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded71 = moons.len();
-        moons.push(Moon::new("Invisible Road: Danger!", KingdomName::Wooded));
-        moons[wooded71].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded72 = moons.len();
-        moons.push(Moon::new("Invisible Road: Hidden Room", KingdomName::Wooded));
-        moons[wooded72].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded73 = moons.len();
-        moons.push(Moon::new("Herding Sheep Above the Forest Fog", KingdomName::Wooded));
-        moons[wooded73].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded74 = moons.len();
-        moons.push(Moon::new("Herding Sheep on the Iron Bridge", KingdomName::Wooded));
-        moons[wooded74].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded75 = moons.len();
-        moons.push(Moon::new("Down and Back Breakdown Road", KingdomName::Wooded));
-        moons[wooded75].add_prereq_kingdom(KingdomName::Mushroom);
-        let wooded76 = moons.len();
-        moons.push(Moon::new("Below Breakdown Road", KingdomName::Wooded));
-        moons[wooded76].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon27 = moons.len();
-        moons.push(Moon::new("Found with Moon Kingdom Art", KingdomName::Wooded));
-        moons[moon27].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[moon27].add_prereq_kingdom_count(KingdomName::Moon, 2);
-        offset.push((wooded1, moons.len()));
+    pub fn new(kingdoms: &Kingdoms) -> Result<Self, RandomizerError> {
+        Moons::build(kingdoms, Cow::Borrowed(&MOONS), Cow::Borrowed(&OFFSET))
+    }
 
-        // cloud kingdom
-        let cloud1 = moons.len();
-        moons.push(Moon::new("Picture Match: Basically a Goomba", KingdomName::Cloud));
-        moons[cloud1].add_prereq_kingdom(KingdomName::Lost);
-        moons[cloud1].add_prereq_kingdom_count(KingdomName::Cloud, 2);
-        let cloud2 = moons.len();
-        moons.push(Moon::new("Peach in the Cloud Kingdom", KingdomName::Cloud));
-        moons[cloud2].add_prereq_kingdom(KingdomName::Mushroom);
-        let cloud3 = moons.len();
-        moons.push(Moon::new("Digging in the...Cloud?", KingdomName::Cloud));
-        moons[cloud3].add_prereq_kingdom(KingdomName::Mushroom);
-        let cloud4 = moons.len();
-        moons.push(Moon::new("High, High Above the Clouds", KingdomName::Cloud));
-        moons[cloud4].add_prereq_kingdom(KingdomName::Mushroom);
-        let cloud5 = moons.len();
-        moons.push(Moon::new("Crossing the Cloud Sea", KingdomName::Cloud));
-        moons[cloud5].add_prereq_kingdom(KingdomName::Mushroom);
-        let cloud6 = moons.len();
-        moons.push(Moon::new("Taking Notes: Up and Down", KingdomName::Cloud));
-        moons[cloud6].add_prereq_kingdom(KingdomName::Mushroom);
-        let cloud7 = moons.len();
-        moons.push(Moon::new("Picture Match: A Stellar Goomba", KingdomName::Cloud));
-        moons[cloud7].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[cloud7].add_prereq_moon(cloud1);
-        let cloud8 = moons.len();
-        moons.push(Moon::new("King of the Cube!", KingdomName::Cloud));
-        moons[cloud8].add_prereq_kingdom(KingdomName::Mushroom);
-        let cloud9 = moons.len();
-        moons.push(Moon::new("The Sixth Face", KingdomName::Cloud));
-        moons[cloud9].add_prereq_kingdom(KingdomName::Mushroom);
-        let dark18 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 4", KingdomName::Cloud));
-        moons[dark18].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((cloud1, moons.len()));
+    pub fn load(kingdoms: &Kingdoms, data: &Data) -> Result<Self, RandomizerError> {
+        let mut moons = Vec::new();
+        for m in &data.moons {
+            let mut prerequisites = Vec::new();
+            for r in &m.prerequisites {
+                prerequisites.push(Requirement::load(r, data, m.line)?);
+            }
+            let mut captures = Vec::new();
+            for c in &m.captures {
+                captures.push(Capture::from_name(c).ok_or_else(|| {
+                    data.error(m.line, format!("unknown capture {}", c))
+                })?);
+            }
+            let mut tags = Vec::new();
+            for t in &m.tags {
+                tags.push(Tag::from_name(t).ok_or_else(|| {
+                    data.error(m.line, format!("unknown tag {}", t))
+                })?);
+            }
+            let kingdom = |k: &str| {
+                KingdomName::from_name(k).map_err(|e| data.error(m.line, e.to_string()))
+            };
+            let hint_art = match &m.hint_art {
+                Some((k, location)) => {
+                    Some(HintArt::new(kingdom(k)?, Cow::Owned(location.clone())))
+                }
+                None => None,
+            };
+            moons.push(Moon {
+                name: Cow::Owned(m.name.clone()),
+                count: m.count,
+                kingdom: kingdom(&m.kingdom)?,
+                prerequisites: Cow::Owned(prerequisites),
+                achievement: m.achievement,
+                advances_phase: m.advances_phase,
+                captures: Cow::Owned(captures),
+                coin_cost: m.coin_cost,
+                breaks_moon_rock: m.breaks_moon_rock,
+                hint_art,
+                tags: Cow::Owned(tags),
+                difficulty: m.difficulty,
+                time: m.time,
+            });
+        }
+        let moons = Moons::build(kingdoms, Cow::Owned(moons),
+                                 Cow::Owned(data.offset.clone()))?;
+        moons.check(kingdoms)?;
+        Ok(moons)
+    }
 
-        // lost kingdom
-        let lost1 = moons.len();
-        moons.push(Moon::new("Atop a Propeller Pillar", KingdomName::Lost));
-        moons.push(Moon::new("Below the Cliff's Edge", KingdomName::Lost));
-        moons.push(Moon::new("Inside the Stone Cage", KingdomName::Lost));
-        moons.push(Moon::new("On a Tree in the Swamp", KingdomName::Lost));
-        moons.push(Moon::new("Over the Fuzzies, Above the Swamp", KingdomName::Lost));
-        let lost6 = moons.len();
-        moons.push(Moon::new("Avoiding Fuzzies Inside the Wall", KingdomName::Lost));
-        moons.push(Moon::new("Inside the Rising Stone Pillar", KingdomName::Lost));
-        moons.push(Moon::new("Enjoying the View of Forgotten Isle", KingdomName::Lost));
-        moons.push(Moon::new("On the Mountain Road", KingdomName::Lost));
-        moons.push(Moon::new("A Propeller Pillar's Secret", KingdomName::Lost));
-        moons.push(Moon::new("Wrecked Rock Block", KingdomName::Lost));
-        moons.push(Moon::new("A Butterfly's Treasure", KingdomName::Lost));
-        let lost13 = moons.len();
-        moons.push(Moon::new("Caught Hopping in the Jungle!", KingdomName::Lost));
-        moons[lost13].add_prereq_kingdom_count(KingdomName::Lost, 2);
-        moons.push(Moon::new("Cave Gardening", KingdomName::Lost));
-        moons.push(Moon::new("Moon Shards in the Jungle", KingdomName::Lost));
-        moons.push(Moon::new("Peeking Out from Under the Bridge", KingdomName::Lost));
-        moons.push(Moon::new("Twist 'n' Turn-Up Treasure", KingdomName::Lost));
-        moons.push(Moon::new("Soaring Over Forgotten Isle!", KingdomName::Lost));
-        moons.push(Moon::new("The Caged Gold", KingdomName::Lost));
-        let lost20 = moons.len();
-        moons.push(Moon::new("Get Some Rest, Captain Toad", KingdomName::Lost));
-        moons.push(Moon::new("Shopping on Forgotten Isle", KingdomName::Lost));
-        let lost22 = moons.len();
-        moons.push(Moon::new("Taxi Flying Through Forgotten Isle", KingdomName::Lost));
-        moons[lost22].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost23 = moons.len();
-        moons.push(Moon::new("I Met a Tropical Wiggler", KingdomName::Lost));
-        moons[lost23].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost24 = moons.len();
-        moons.push(Moon::new("Lost Kingdom Regular Cup", KingdomName::Lost));
-        moons[lost24].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost25 = moons.len();
-        moons.push(Moon::new("Peach in the Lost Kingdom", KingdomName::Lost));
-        moons[lost25].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost26 = moons.len();
-        moons.push(Moon::new("The Shining Fruit", KingdomName::Lost));
-        moons[lost26].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost27 = moons.len();
-        moons.push(Moon::new("Jump Down to the Top of a Tree", KingdomName::Lost));
-        moons[lost27].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost28 = moons.len();
-        moons.push(Moon::new("Line It Up, Blow It Up", KingdomName::Lost));
-        moons[lost28].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost29 = moons.len();
-        moons.push(Moon::new("Taking Notes: Stretch and Shrink", KingdomName::Lost));
-        moons[lost29].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost30 = moons.len();
-        moons.push(Moon::new("Lost Kingdom Master Cup", KingdomName::Lost));
-        moons[lost30].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[lost30].add_prereq_moon(lost24);
-        let lost31 = moons.len();
-        moons.push(Moon::new("Lost Kingdom Timer Challenge", KingdomName::Lost));
-        moons[lost31].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost32 = moons.len();
-        moons.push(Moon::new("Stretch and Traverse the Jungle", KingdomName::Lost));
-        moons[lost32].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost33 = moons.len();
-        moons.push(Moon::new("Aglow in the Jungle", KingdomName::Lost));
-        moons[lost33].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost34 = moons.len();
-        moons.push(Moon::new("Chasing Klepto", KingdomName::Lost));
-        moons[lost34].add_prereq_kingdom(KingdomName::Mushroom);
-        let lost35 = moons.len();
-        moons.push(Moon::new("Extremely Hot Bath", KingdomName::Lost));
-        moons[lost35].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow34 = moons.len();
-        moons.push(Moon::new("Found with Snow Kingdom Art", KingdomName::Lost));
-        moons[snow34].add_prereq_kingdom(KingdomName::Snow);
-        let dark21 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 7", KingdomName::Lost));
-        moons[dark21].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((lost1, moons.len()));
+    pub fn check(&self, kingdoms: &Kingdoms) -> Result<(), RandomizerError> {
+        // moon totals and tags can only count the moons there are
+        let total = self.total_count();
+        for moon in self.moons.iter() {
+            for r in moon.prerequisites.iter() {
+                for c in r.moon_counts() {
+                    if c > total {
+                        return Err(RandomizerError::UnsatisfiableData(format!(
+                            "{} needs {} moons, but there are only {}",
+                            moon.name(), c, total)));
+                    }
+                }
+                for (t, c) in r.tags() {
+                    let tagged = self.moons.iter().filter(|m| m.tags.contains(&t)).count();
+                    if usize::from(c) > tagged {
+                        return Err(RandomizerError::UnsatisfiableData(format!(
+                            "{} needs {} moons tagged {:?}, but there are only {}",
+                            moon.name(), c, t, tagged)));
+                    }
+                }
+            }
+        }
 
-        // metro kingdom
-        let metro1 = moons.len();
-        moons.push(Moon::new_multi("New Donk City's Pest Problem", KingdomName::Metro));
-        moons[lake26].add_prereq_moon(metro1);
-        moons[wooded49].add_prereq_moon(metro1);
-        let metro2 = moons.len();
-        moons.push(Moon::new("Drummer on Board!", KingdomName::Metro));
-        moons[metro2].add_prereq_moon(metro1);
-        let metro3 = moons.len();
-        moons.push(Moon::new("Guitarist on Board!", KingdomName::Metro));
-        moons[metro3].add_prereq_moon(metro1);
-        let metro4 = moons.len();
-        moons.push(Moon::new("Bassist on Board!", KingdomName::Metro));
-        moons[metro4].add_prereq_moon(metro1);
-        let metro5 = moons.len();
-        moons.push(Moon::new("Trumpeter on Board!", KingdomName::Metro));
-        moons[metro5].add_prereq_moon(metro1);
-        let metro6 = moons.len();
-        moons.push(Moon::new("Powering Up the Station", KingdomName::Metro));
-        moons[metro6].add_prereq_moon(metro2);
-        moons[metro6].add_prereq_moon(metro3);
-        moons[metro6].add_prereq_moon(metro4);
-        moons[metro6].add_prereq_moon(metro5);
-        let metro7 = moons.len();
-        moons.push(Moon::new_multi("A Traditional Festival", KingdomName::Metro));
-        kingdoms.kingdom_mut(KingdomName::Metro).set_exit_moon(metro7);
-        moons[metro7].add_prereq_moon(metro6);
-        moons.push(Moon::new("Inside an Iron Girder", KingdomName::Metro));
-        moons.push(Moon::new("Swaying in the Breeze", KingdomName::Metro));
-        moons.push(Moon::new("Girder Sandwich", KingdomName::Metro));
-        let metro11 = moons.len();
-        moons.push(Moon::new("Glittering Above the Pool", KingdomName::Metro));
-        moons[metro11].add_prereq_moon(metro1);
-        let metro12 = moons.len();
-        moons.push(Moon::new("Dizzying Heights", KingdomName::Metro));
-        moons[metro12].add_prereq_moon(metro1);
-        let metro13 = moons.len();
-        moons.push(Moon::new("Secret Girder Tunnel!", KingdomName::Metro));
-        moons[metro13].add_prereq_moon(metro1);
-        let metro14 = moons.len();
-        moons.push(Moon::new("Who Piled Garbage on This?", KingdomName::Metro));
-        moons[metro14].add_prereq_moon(metro1);
-        let metro15 = moons.len();
-        moons.push(Moon::new("Hidden in the Scrap", KingdomName::Metro));
-        moons[metro15].add_prereq_moon(metro1);
-        let metro16 = moons.len();
-        moons.push(Moon::new("Left at the Cafe?", KingdomName::Metro));
-        moons[metro16].add_prereq_moon(metro1);
-        let metro17 = moons.len();
-        moons.push(Moon::new("Caught Hopping on a Building", KingdomName::Metro));
-        moons[metro17].add_prereq_moon(metro7);
-        let metro18 = moons.len();
-        moons.push(Moon::new("How Do They Take Out the Trash?", KingdomName::Metro));
-        moons[metro18].add_prereq_moon(metro1);
-        let metro19 = moons.len();
-        moons.push(Moon::new("Metro Kingdom Timer Challenge 1", KingdomName::Metro));
-        moons[metro19].add_prereq_moon(metro7);
-        let metro20 = moons.len();
-        moons.push(Moon::new("Metro Kingdom Timer Challenge 2", KingdomName::Metro));
-        moons[metro20].add_prereq_moon(metro7);
-        let metro21 = moons.len();
-        moons.push(Moon::new("City Gardening: Building Planter", KingdomName::Metro));
-        moons[metro21].add_prereq_moon(metro1);
-        let metro22 = moons.len();
-        moons.push(Moon::new("City Gardening: Plaza Planter", KingdomName::Metro));
-        moons[metro22].add_prereq_moon(metro1);
-        let metro23 = moons.len();
-        moons.push(Moon::new("City Gardening: Rooftop Planter", KingdomName::Metro));
-        moons[metro23].add_prereq_moon(metro1);
-        let metro24 = moons.len();
-        moons.push(Moon::new("How You Doin' Captain Toad?", KingdomName::Metro));
-        moons[metro24].add_prereq_moon(metro1);
-        let metro25 = moons.len();
-        moons.push(Moon::new("Free Parking: Rooftop Hop", KingdomName::Metro));
-        moons[metro25].add_prereq_moon(metro1);
-        let metro26 = moons.len();
-        moons.push(Moon::new("Bench Friends", KingdomName::Metro));
-        moons[metro26].add_prereq_moon(metro1);
-        moons.push(Moon::new("Shopping in New Donk City", KingdomName::Metro));
-        let metro28 = moons.len();
-        moons.push(Moon::new("Metro Kingdom Slots", KingdomName::Metro));
-        moons[metro28].add_prereq_moon(metro1);
-        let metro29 = moons.len();
-        moons.push(Moon::new("Jump-Rope Hero", KingdomName::Metro));
-        moons[metro29].add_prereq_moon(metro1);
-        let metro30 = moons.len();
-        moons.push(Moon::new("Jump-Rope Genius", KingdomName::Metro));
-        moons[metro30].add_prereq_moon(metro1);
-        moons[metro30].add_prereq_moon(metro29);
-        let metro31 = moons.len();
-        moons.push(Moon::new("Remotely Captured Car", KingdomName::Metro));
-        moons[metro31].add_prereq_moon(metro1);
-        let metro32 = moons.len();
-        moons.push(Moon::new("RC Car Pro!", KingdomName::Metro));
-        moons[metro32].add_prereq_moon(metro31);
-        let metro33 = moons.len();
-        moons.push(Moon::new("Taking Notes: In the Private Room", KingdomName::Metro));
-        moons[metro33].add_prereq_moon(metro1);
-        let metro34 = moons.len();
-        moons.push(Moon::new("City Hall Lost & Found", KingdomName::Metro));
-        let metro35 = moons.len();
-        moons.push(Moon::new("Sewer Treasure", KingdomName::Metro));
-        moons[metro35].add_prereq_moon(metro2);
-        moons[metro35].add_prereq_moon(metro3);
-        moons[metro35].add_prereq_moon(metro4);
-        moons[metro35].add_prereq_moon(metro5);
-        let metro36 = moons.len();
-        moons.push(Moon::new("Celebrating in the Streets!", KingdomName::Metro));
-        moons[metro36].add_prereq_moon(metro6);
-        let metro37 = moons.len();
-        moons.push(Moon::new("Pushing Through the Crowd", KingdomName::Metro));
-        moons[metro37].add_prereq_moon(metro1);
-        let metro38 = moons.len();
-        moons.push(Moon::new("High Over the Crowd", KingdomName::Metro));
-        moons[metro38].add_prereq_moon(metro1);
-        let metro39 = moons.len();
-        moons.push(Moon::new("Rewiring the Neighborhood", KingdomName::Metro));
-        moons[metro39].add_prereq_moon(metro1);
-        let metro40 = moons.len();
-        moons.push(Moon::new("Off the Beaten Wire", KingdomName::Metro));
-        moons[metro40].add_prereq_moon(metro1);
-        let metro41 = moons.len();
-        moons.push(Moon::new("Moon Shards Under Siege", KingdomName::Metro));
-        moons[metro41].add_prereq_moon(metro1);
-        let metro42 = moons.len();
-        moons.push(Moon::new("Sharpshooting Under Siege", KingdomName::Metro));
-        moons[metro42].add_prereq_moon(metro1);
-        let metro43 = moons.len();
-        moons.push(Moon::new("Inside the Rotating Maze", KingdomName::Metro));
-        moons[metro43].add_prereq_moon(metro1);
-        let metro44 = moons.len();
-        moons.push(Moon::new("Outside the Rotating Maze", KingdomName::Metro));
-        moons[metro44].add_prereq_moon(metro1);
-        let metro45 = moons.len();
-        moons.push(Moon::new("Hanging from a High-Rise", KingdomName::Metro));
-        moons[metro45].add_prereq_moon(metro1);
-        let metro46 = moons.len();
-        moons.push(Moon::new("Vaulting Up a High-Rise", KingdomName::Metro));
-        moons[metro46].add_prereq_moon(metro1);
-        let metro47 = moons.len();
-        moons.push(Moon::new("Bullet Billding", KingdomName::Metro));
-        moons[metro47].add_prereq_moon(metro1);
-        let metro48 = moons.len();
-        moons.push(Moon::new("One Man's Trash...", KingdomName::Metro));
-        moons[metro48].add_prereq_moon(metro1);
-        let metro49 = moons.len();
-        moons.push(Moon::new("Motor Scooter: Escape", KingdomName::Metro));
-        moons[metro49].add_prereq_moon(metro1);
-        let metro50 = moons.len();
-        moons.push(Moon::new("Big Jump: Escape!", KingdomName::Metro));
-        moons[metro50].add_prereq_moon(metro1);
-        let metro51 = moons.len();
-        moons.push(Moon::new("Secret Path to New Donk City!", KingdomName::Metro));
-        moons[metro51].add_prereq_moon(metro1);
-        moons[metro51].add_prereq_moon(sand10);
-        moons[metro51].add_prereq_kingdom_count(KingdomName::Sand, 2);
-        let metro52 = moons.len();
-        moons.push(Moon::new("A Tourist in the Metro Kingdom!", KingdomName::Metro));
-        moons[metro52].add_prereq_moon(metro7);
-        moons[metro52].add_prereq_moon(sand4);
-        moons[metro53].add_prereq_moon(metro1);
-        moons[cascade19].add_prereq_moon(metro52);
-        let metro54 = moons.len();
-        moons.push(Moon::new("Bird Traveling the City", KingdomName::Metro));
-        moons[metro54].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro55 = moons.len();
-        moons.push(Moon::new("Mario Signs His Name", KingdomName::Metro));
-        moons[metro55].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro56 = moons.len();
-        moons.push(Moon::new("Surprise Clown!", KingdomName::Metro));
-        moons[metro56].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro57 = moons.len();
-        moons.push(Moon::new("A Request from the Mayor", KingdomName::Metro));
-        moons[metro57].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro58 = moons.len();
-        moons.push(Moon::new("Jammin' in the Metro Kingdom", KingdomName::Metro));
-        moons[metro58].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro59 = moons.len();
-        moons.push(Moon::new("Sphynx in the City", KingdomName::Metro));
-        moons[metro59].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro60 = moons.len();
-        moons.push(Moon::new("Free Parking: Leap of Faith", KingdomName::Metro));
-        moons[metro60].add_prereq_moon(metro7);
-        moons[metro60].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro61 = moons.len();
-        moons.push(Moon::new("Moon Kingdom Regular Cup", KingdomName::Metro));
-        moons[metro61].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro62 = moons.len();
-        moons.push(Moon::new("Hat-and-Seek: In the City", KingdomName::Metro));
-        moons[metro62].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro63 = moons.len();
-        moons.push(Moon::new("Powering Up the Power Plant", KingdomName::Metro));
-        moons[metro63].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro64 = moons.len();
-        moons.push(Moon::new("Up on the Big Screen", KingdomName::Metro));
-        moons[metro64].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro65 = moons.len();
-        moons.push(Moon::new("Down Inside the Big Screen", KingdomName::Metro));
-        moons[metro65].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro66 = moons.len();
-        moons.push(Moon::new("Peach in the Metro Kingdom", KingdomName::Metro));
-        moons[metro66].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro67 = moons.len();
-        moons.push(Moon::new("Hanging Between Buildings", KingdomName::Metro));
-        moons[metro67].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro68 = moons.len();
-        moons.push(Moon::new("Crossing Lines", KingdomName::Metro));
-        moons[metro68].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro69 = moons.len();
-        moons.push(Moon::new("Out of a Crate in the City", KingdomName::Metro));
-        moons[metro69].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro70 = moons.len();
-        moons.push(Moon::new("Bird Traveling in the Park", KingdomName::Metro));
-        moons[metro70].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro71 = moons.len();
-        moons.push(Moon::new("Metro Kingdom Timer Challenge 3", KingdomName::Metro));
-        moons[metro71].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro72 = moons.len();
-        moons.push(Moon::new("Found in the Park! Good Dog!", KingdomName::Metro));
-        moons[metro72].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro73 = moons.len();
-        moons.push(Moon::new("RC Car Champ", KingdomName::Metro));
-        moons[metro73].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[metro73].add_prereq_moon(metro32);
-        let metro74 = moons.len();
-        moons.push(Moon::new("Metro Kingdom Master Cup", KingdomName::Metro));
-        moons[metro74].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[metro74].add_prereq_moon(metro61);
-        let metro75 = moons.len();
-        moons.push(Moon::new("Hat-and-Seek: In the Crowd", KingdomName::Metro));
-        moons[metro75].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro76 = moons.len();
-        moons.push(Moon::new("Scaling Pitchblack Mountain", KingdomName::Metro));
-        moons[metro76].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro77 = moons.len();
-        moons.push(Moon::new("Reaching Pitchblack Island", KingdomName::Metro));
-        moons[metro77].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro78 = moons.len();
-        moons.push(Moon::new("Swinging Scaffolding: Jump!", KingdomName::Metro));
-        moons[metro78].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro79 = moons.len();
-        moons.push(Moon::new("Swinging Scaffolding: Break!", KingdomName::Metro));
-        moons[metro79].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro80 = moons.len();
-        moons.push(Moon::new("Motor Scooter Daredevil!", KingdomName::Metro));
-        moons[metro80].add_prereq_kingdom(KingdomName::Mushroom);
-        let metro81 = moons.len();
-        moons.push(Moon::new("Full-Throttle Scooting!", KingdomName::Metro));
-        moons[metro81].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside50 = moons.len();
-        moons.push(Moon::new("Found with Seaside Kingdom Art", KingdomName::Metro));
-        moons[seaside50].add_prereq_kingdom(KingdomName::Seaside);
-        let dark16 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 2", KingdomName::Metro));
-        moons[dark16].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((metro1, moons.len()));
+        // a moon that needs itself, through any chain of moons, is never
+        // available, so find the moons that can be collected to find one
+        let possible = self.possible(kingdoms, &vec![false; self.moons.len()]);
+        match possible.iter().position(|p| !p) {
+            Some(id) => Err(RandomizerError::UnsatisfiableData(format!(
+                "{} depends on itself", self.moons[id].name()))),
+            None => Ok(()),
+        }
+    }
 
-        // snow kingdom
-        let snow1 = moons.len();
-        moons.push(Moon::new("The Icicle Barrier", KingdomName::Snow));
-        let snow2 = moons.len();
-        moons.push(Moon::new("The Ice Wall Barrier", KingdomName::Snow));
-        let snow3 = moons.len();
-        moons.push(Moon::new("The Gusty Barrier", KingdomName::Snow));
-        let snow4 = moons.len();
-        moons.push(Moon::new("The Snowy Mountain Barrier", KingdomName::Snow));
-        let snow5 = moons.len();
-        moons.push(Moon::new_multi("The Bound Bowl Grand Prix", KingdomName::Snow));
-        moons[snow5].add_prereq_moon(snow1);
-        moons[snow5].add_prereq_moon(snow2);
-        moons[snow5].add_prereq_moon(snow3);
-        moons[snow5].add_prereq_moon(snow4);
-        kingdoms.kingdom_mut(KingdomName::Snow).set_exit_moon(snow5);
-        moons[cascade18].add_prereq_moon(snow5);
-        moons[lake26].add_prereq_moon(snow5);
-        moons[wooded49].add_prereq_moon(snow5);
-        moons.push(Moon::new("Entrance to Shiveria", KingdomName::Snow));
-        moons.push(Moon::new("Behind Snowy Mountain", KingdomName::Snow));
-        let snow8 = moons.len();
-        moons.push(Moon::new("Shining in the Snow in Town", KingdomName::Snow));
-        let snow9 = moons.len();
-        moons.push(Moon::new("Atop a Blustery Arch", KingdomName::Snow));
-        let snow10 = moons.len();
-        moons.push(Moon::new("Caught Hopping in the Snow!", KingdomName::Snow));
-        moons[snow10].add_prereq_moon(snow5);
-        let snow11 = moons.len();
-        moons.push(Moon::new("The Shiverian Treasure Chest", KingdomName::Snow));
-        let snow12 = moons.len();
-        moons.push(Moon::new("Treasure in the Ice Wall", KingdomName::Snow));
-        let snow13 = moons.len();
-        moons.push(Moon::new("Snow Kingdom Timer Challenge 1", KingdomName::Snow));
-        moons[snow13].add_prereq_moon(snow5);
-        let snow14 = moons.len();
-        moons.push(Moon::new("Snow Kingdom Timer Challenge 2", KingdomName::Snow));
-        moons[snow14].add_prereq_moon(snow5);
-        let snow15 = moons.len();
-        moons.push(Moon::new("Moon Shards in the Snow", KingdomName::Snow));
-        moons[snow15].add_prereq_moon(snow5);
-        let snow16 = moons.len();
-        moons.push(Moon::new("Taking Notes: Snow Path Dash", KingdomName::Snow));
-        moons[snow16].add_prereq_moon(snow5);
-        let snow17 = moons.len();
-        moons.push(Moon::new("Fishing in the Glacier!", KingdomName::Snow));
-        moons[snow17].add_prereq_moon(snow5);
-        moons.push(Moon::new("Ice-Dodging Goomba Stack", KingdomName::Snow));
-        let snow19 = moons.len();
-        moons.push(Moon::new("Captain Toad is Chilly!", KingdomName::Snow));
-        let snow20 = moons.len();
-        moons.push(Moon::new("I'm Not Cold!", KingdomName::Snow));
-        moons[snow20].add_prereq_moon(snow5);
-        moons.push(Moon::new("Shopping in Shiveria", KingdomName::Snow));
-        let snow22 = moons.len();
-        moons.push(Moon::new("Walking on Ice!", KingdomName::Snow));
-        moons[snow22].add_prereq_moon(snow5);
-        let snow23 = moons.len();
-        moons.push(Moon::new("Snowline Circuit Class S", KingdomName::Snow));
-        moons[snow23].add_prereq_moon(snow5);
-        let snow24 = moons.len();
-        moons.push(Moon::new("Dashing Over Cold Water!", KingdomName::Snow));
-        moons[snow24].add_prereq_moon(snow5);
-        let snow25 = moons.len();
-        moons.push(Moon::new("Dashing Above and Beyond!", KingdomName::Snow));
-        moons[snow25].add_prereq_moon(snow5);
-        let snow26 = moons.len();
-        moons.push(Moon::new("Jump 'n' Swim in the Freezing Water", KingdomName::Snow));
-        moons[snow26].add_prereq_moon(snow5);
-        let snow27 = moons.len();
-        moons.push(Moon::new("Freezing Water Near the Ceiling", KingdomName::Snow));
-        moons[snow27].add_prereq_moon(snow5);
-        let snow28 = moons.len();
-        moons.push(Moon::new("Blowing and Sliding", KingdomName::Snow));
-        moons[snow28].add_prereq_moon(snow5);
-        moons.push(Moon::new("Moon Shards in the Cold Room", KingdomName::Snow));
-        let snow30 = moons.len();
-        moons.push(Moon::new("Slip Behind the Ice", KingdomName::Snow));
-        let snow31 = moons.len();
-        moons.push(Moon::new("Spinning Above the Clouds", KingdomName::Snow));
-        moons[snow31].add_prereq_moon(snow5);
-        let snow32 = moons.len();
-        moons.push(Moon::new("High-Altitude Spinning", KingdomName::Snow));
-        moons[snow32].add_prereq_moon(snow5);
-        let snow33 = moons.len();
-        moons.push(Moon::new("Secret Path to Shiveria", KingdomName::Snow));
-        moons[snow33].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[snow33].add_prereq_kingdom_count(KingdomName::Snow, 2);
-        let snow35 = moons.len();
-        moons.push(Moon::new("Snow Kingdom Regular Cup", KingdomName::Snow));
-        moons[snow35].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow36 = moons.len();
-        moons.push(Moon::new("Hat-and-Seek in the Snow", KingdomName::Snow));
-        moons[snow36].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow37 = moons.len();
-        moons.push(Moon::new("Peach in the Snow Kingdom", KingdomName::Snow));
-        moons[snow37].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow38 = moons.len();
-        moons.push(Moon::new("Shining on High", KingdomName::Snow));
-        moons[snow38].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow39 = moons.len();
-        moons.push(Moon::new("Above the Freezing Fish Pond", KingdomName::Snow));
-        moons[snow39].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow40 = moons.len();
-        moons.push(Moon::new("Ice Floe Swimming", KingdomName::Snow));
-        moons[snow40].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow41 = moons.len();
-        moons.push(Moon::new("Icy Jump Challenge", KingdomName::Snow));
-        moons[snow41].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow42 = moons.len();
-        moons.push(Moon::new("Forgotten in the Holding Room", KingdomName::Snow));
-        moons[snow42].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow43 = moons.len();
-        moons.push(Moon::new("It Popped Out of the Ice", KingdomName::Snow));
-        moons[snow43].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow44 = moons.len();
-        moons.push(Moon::new("Deep in the Cold, Cold Water", KingdomName::Snow));
-        moons[snow44].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow45 = moons.len();
-        moons.push(Moon::new("Water Pooling in the Crevasse", KingdomName::Snow));
-        moons[snow45].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow46 = moons.len();
-        moons.push(Moon::new("Squirming Under Ice", KingdomName::Snow));
-        moons[snow46].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow47 = moons.len();
-        moons.push(Moon::new("Snow Kingdom Timer Challenge 3", KingdomName::Snow));
-        moons[snow47].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow48 = moons.len();
-        moons.push(Moon::new("Stacked-Up Ice Climb", KingdomName::Snow));
-        moons[snow48].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow49 = moons.len();
-        moons.push(Moon::new("I Met a Snow Cheep Cheep!", KingdomName::Snow));
-        moons[snow49].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow50 = moons.len();
-        moons.push(Moon::new("Even More Walking on Ice", KingdomName::Snow));
-        moons[snow50].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[snow50].add_prereq_moon(snow22);
-        let snow51 = moons.len();
-        moons.push(Moon::new("Snow Kingdom Master Cup", KingdomName::Snow));
-        moons[snow51].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[snow51].add_prereq_moon(snow35);
-        let snow52 = moons.len();
-        moons.push(Moon::new("Iceburn Circuit Class A", KingdomName::Snow));
-        moons[snow52].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow53 = moons.len();
-        moons.push(Moon::new("Iceburn Circuit Class S", KingdomName::Snow));
-        moons[snow53].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[snow53].add_prereq_moon(snow52);
-        let snow54 = moons.len();
-        moons.push(Moon::new("Running the Flower Road", KingdomName::Snow));
-        moons[snow54].add_prereq_kingdom(KingdomName::Mushroom);
-        let snow55 = moons.len();
-        moons.push(Moon::new("Looking Back on the Flower Road", KingdomName::Snow));
-        moons[snow55].add_prereq_kingdom(KingdomName::Mushroom);
-        let dark19 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 5", KingdomName::Snow));
-        moons[dark19].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((snow1, moons.len()));
+    fn possible(&self, kingdoms: &Kingdoms, removed: &[bool]) -> Vec<bool> {
+        // the moons that can be collected without the removed ones, marking
+        // moons whose requirements can be met until nothing more can be
+        let phases: Vec<&[MoonID]> = kingdoms.iter().map(|(_, k)| k.phase_moons()).collect();
+        let mut possible = vec![false; self.moons.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, moon) in self.moons.iter().enumerate() {
+                if possible[id] || removed[id] {
+                    continue;
+                }
+                if moon.prerequisites.iter()
+                    .all(|r| self.can_meet(&phases, r, &possible)) {
+                    possible[id] = true;
+                    changed = true;
+                }
+            }
+        }
+        possible
+    }
 
-        // seaside kingdom
-        let seaside1 = moons.len();
-        moons.push(Moon::new("The Stone Pillar Seal", KingdomName::Seaside));
-        let seaside2 = moons.len();
-        moons.push(Moon::new("The Lighthouse Seal", KingdomName::Seaside));
-        let seaside3 = moons.len();
-        moons.push(Moon::new("The Hot Sprint Seal", KingdomName::Seaside));
-        let seaside4 = moons.len();
-        moons.push(Moon::new("The Seal Above the Canyon", KingdomName::Seaside));
-        let seaside5 = moons.len();
-        moons.push(Moon::new_multi("The Glass is Half Full", KingdomName::Seaside));
-        moons[seaside5].add_prereq_moon(seaside1);
-        moons[seaside5].add_prereq_moon(seaside2);
-        moons[seaside5].add_prereq_moon(seaside3);
-        moons[seaside5].add_prereq_moon(seaside4);
-        kingdoms.kingdom_mut(KingdomName::Seaside).set_exit_moon(seaside5);
-        moons[cascade18].add_prereq_moon(seaside5);
-        moons[lake26].add_prereq_moon(seaside5);
-        moons[wooded49].add_prereq_moon(seaside5);
-        moons.push(Moon::new("On the Cliff Overlooking the Beach", KingdomName::Seaside));
-        moons.push(Moon::new("Ride the Jetstream", KingdomName::Seaside));
-        let seaside8 = moons.len();
-        moons.push(Moon::new("Ocean-Bottom Maze: Treasure", KingdomName::Seaside));
-        let seaside9 = moons.len();
-        moons.push(Moon::new("Ocean-Bottom Maze: Hidden Room", KingdomName::Seaside));
-        moons.push(Moon::new("Underwater Highway Tunnel", KingdomName::Seaside));
-        moons.push(Moon::new("Shh! It's a Shortcut!", KingdomName::Seaside));
-        moons.push(Moon::new("Gap in the Ocean Trench", KingdomName::Seaside));
-        moons.push(Moon::new("Slip Through the Nesting Spot", KingdomName::Seaside));
-        moons.push(Moon::new("Merci, Dorrie!", KingdomName::Seaside));
-        let seaside15 = moons.len();
-        moons.push(Moon::new("Bonjour, Dorrie!", KingdomName::Seaside));
-        moons[seaside15].add_prereq_moon(seaside5);
-        let seaside16 = moons.len();
-        moons.push(Moon::new("Under a Dangerous Ceiling", KingdomName::Seaside));
-        let seaside17 = moons.len();
-        moons.push(Moon::new("What the Waves Left Behind", KingdomName::Seaside));
-        let seaside18 = moons.len();
-        moons.push(Moon::new("The Back Canyon: Excavate!", KingdomName::Seaside));
-        let seaside19 = moons.len();
-        moons.push(Moon::new("Bubblaine Northern Reaches", KingdomName::Seaside));
-        let seaside20 = moons.len();
-        moons.push(Moon::new("Wriggling on the Sandy Bottom", KingdomName::Seaside));
-        let seaside21 = moons.len();
-        moons.push(Moon::new("Glass Palace Treasure Chest", KingdomName::Seaside));
-        let seaside22 = moons.len();
-        moons.push(Moon::new("Treasure Trap Hidden in the Inlet", KingdomName::Seaside));
-        let seaside23 = moons.len();
-        moons.push(Moon::new("Sea Gardening: Inlet Seed", KingdomName::Seaside));
-        let seaside24 = moons.len();
-        moons.push(Moon::new("Sea Gardening: Canyon Seed", KingdomName::Seaside));
-        let seaside25 = moons.len();
-        moons.push(Moon::new("Sea Gardening: Hot-Spring Seed", KingdomName::Seaside));
-        moons.push(Moon::new("Sea Gardening: Ocean Trench Seed", KingdomName::Seaside));
-        let seaside27 = moons.len();
-        moons.push(Moon::new("Seaside Kingdom Timer Challenge 1", KingdomName::Seaside));
-        let seaside28 = moons.len();
-        moons.push(Moon::new("Seaside Kingdom Timer Challenge 2", KingdomName::Seaside));
-        moons[seaside28].add_prereq_moon(seaside5);
-        let seaside29 = moons.len();
-        moons.push(Moon::new("Found on the Beach! Good Dog!", KingdomName::Seaside));
-        moons[seaside29].add_prereq_moon(seaside5);
-        moons.push(Moon::new("Moon Shards in the Sea", KingdomName::Seaside));
-        let seaside31 = moons.len();
-        moons.push(Moon::new("Taking Notes: Ocean Surface Dash", KingdomName::Seaside));
-        let seaside32 = moons.len();
-        moons.push(Moon::new("Love by the Seaside", KingdomName::Seaside));
-        let seaside33 = moons.len();
-        moons.push(Moon::new("Lighthouse Leaper", KingdomName::Seaside));
-        moons[seaside33].add_prereq_moon(seaside5);
-        let seaside34 = moons.len();
-        moons.push(Moon::new("Good Job, Captain Toad!", KingdomName::Seaside));
-        let seaside35 = moons.len();
-        moons.push(Moon::new("Ocean Quiz: Good!", KingdomName::Seaside));
-        moons.push(Moon::new("Shopping in Bubblaine", KingdomName::Seaside));
-        let seaside37 = moons.len();
-        moons.push(Moon::new("Beach Volleyball: Champ", KingdomName::Seaside));
-        moons[seaside37].add_prereq_moon(seaside5);
-        let seaside38 = moons.len();
-        moons.push(Moon::new("Beach Volleyball: Hero of the Beach!", KingdomName::Seaside));
-        moons[seaside38].add_prereq_moon(seaside37);
-        moons.push(Moon::new("Looking Back in the Dark Waterway", KingdomName::Seaside));
-        moons.push(Moon::new("The Sphynx's Underwater Vault", KingdomName::Seaside));
-        let seaside41 = moons.len();
-        moons.push(Moon::new("A Rumble on the Seaside Floor", KingdomName::Seaside));
-        let seaside42 = moons.len();
-        moons.push(Moon::new("A Relaxing Dance", KingdomName::Seaside));
-        moons.push(Moon::new("Wading in the Cloud Sea", KingdomName::Seaside));
-        moons.push(Moon::new("Sunken Treasure in the Cloud Sea", KingdomName::Seaside));
-        moons.push(Moon::new("Fly Through the Narrow Valley", KingdomName::Seaside));
-        let seaside46 = moons.len();
-        moons.push(Moon::new("Treasure Chest in the Narrow Valley", KingdomName::Seaside));
-        moons.push(Moon::new("Hurry and Stretch", KingdomName::Seaside));
-        moons.push(Moon::new("Stretch on the Side Path", KingdomName::Seaside));
-        let seaside49 = moons.len();
-        moons.push(Moon::new("Secret Path to Bubblaine!", KingdomName::Seaside));
-        moons[seaside49].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside51 = moons.len();
-        moons.push(Moon::new("Seaside Kingdom Regular Cup", KingdomName::Seaside));
-        moons[seaside51].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside52 = moons.len();
-        moons.push(Moon::new("Peach in the Seaside Kingdom", KingdomName::Seaside));
-        moons[seaside52].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside53 = moons.len();
-        moons.push(Moon::new("Above the Parasol: Catch!", KingdomName::Seaside));
-        moons[seaside53].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside54 = moons.len();
-        moons.push(Moon::new("What Shines Inside the Glass", KingdomName::Seaside));
-        moons[seaside54].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside55 = moons.len();
-        moons.push(Moon::new("A Fine Detail on the Glass", KingdomName::Seaside));
-        moons[seaside55].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside56 = moons.len();
-        moons.push(Moon::new("Underwater Highway West: Explore!", KingdomName::Seaside));
-        moons[seaside56].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside57 = moons.len();
-        moons.push(Moon::new("Underwater Highway East: Explore!", KingdomName::Seaside));
-        moons[seaside57].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside58 = moons.len();
-        moons.push(Moon::new("Rapid Ascent on Hot Spring Island", KingdomName::Seaside));
-        moons[seaside58].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside59 = moons.len();
-        moons.push(Moon::new("A Light Next to the Lighthouse", KingdomName::Seaside));
-        moons[seaside59].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside60 = moons.len();
-        moons.push(Moon::new("The Tall Rock Shelf in the Deep Ocean", KingdomName::Seaside));
-        moons[seaside60].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside61 = moons.len();
-        moons.push(Moon::new("At the Base of the Lighthouse", KingdomName::Seaside));
-        moons[seaside61].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside62 = moons.len();
-        moons.push(Moon::new("Bird Traveling Over the Ocean", KingdomName::Seaside));
-        moons[seaside62].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside63 = moons.len();
-        moons.push(Moon::new("Caught Hopping at Glass Palace!", KingdomName::Seaside));
-        moons[seaside63].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside64 = moons.len();
-        moons.push(Moon::new("Seaside Kingdom Timer Challenge 3", KingdomName::Seaside));
-        moons[seaside64].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside65 = moons.len();
-        moons.push(Moon::new("Taking Notes: Ocean-Bottom Maze", KingdomName::Seaside));
-        moons[seaside65].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside66 = moons.len();
-        moons.push(Moon::new("Taking Notes in the Sea", KingdomName::Seaside));
-        moons[seaside66].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside67 = moons.len();
-        moons.push(Moon::new("Seaside Kingdom Master Cup", KingdomName::Seaside));
-        moons[seaside67].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[seaside67].add_prereq_moon(seaside51);
-        let seaside68 = moons.len();
-        moons.push(Moon::new("Aim! Poke!", KingdomName::Seaside));
-        moons[seaside68].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside69 = moons.len();
-        moons.push(Moon::new("Poke! Roll!", KingdomName::Seaside));
-        moons[seaside69].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside70 = moons.len();
-        moons.push(Moon::new("The Spinning Maze: Search!", KingdomName::Seaside));
-        moons[seaside70].add_prereq_kingdom(KingdomName::Mushroom);
-        let seaside71 = moons.len();
-        moons.push(Moon::new("The Spinning Maze: Open!", KingdomName::Seaside));
-        moons[seaside71].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon49 = moons.len();
-        moons.push(Moon::new("Found with Luncheon Kingdom Art", KingdomName::Seaside));
-        moons[luncheon49].add_prereq_kingdom(KingdomName::Luncheon);
-        let dark20 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 6", KingdomName::Seaside));
-        moons[dark20].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((seaside1, moons.len()));
+    fn can_meet(&self, phases: &[&[MoonID]], r: &Requirement, possible: &[bool]) -> bool {
+        // whether the requirement can be met by the possible moons, where
+        // an any only needs one of its branches
+        match r {
+            Requirement::Moon(m) => possible[*m],
+            Requirement::MoonCount(c) => *c <= self.total_count(),
+            Requirement::PhaseAtLeast(k, p) | Requirement::PhaseOnly(k, p) => {
+                let phases = phases[*k as usize];
+                phases.len() >= *p as usize
+                    && phases[..*p as usize].iter().all(|m| possible[*m])
+            }
+            Requirement::Capture(c) => self.moons.iter().enumerate()
+                .any(|(id, m)| possible[id] && m.captures.contains(c)),
+            Requirement::Tagged(t, c) => {
+                let tagged = self.moons.iter().enumerate()
+                    .filter(|(id, m)| possible[*id] && m.tags.contains(t))
+                    .count();
+                tagged >= usize::from(*c)
+            }
+            Requirement::Flag(Flag::MoonRockBroken(k)) => self.moons.iter().enumerate()
+                .any(|(id, m)| possible[id] && m.breaks_moon_rock && m.kingdom == *k),
+            Requirement::All(r) => r.iter().all(|r| self.can_meet(phases, r, possible)),
+            Requirement::Any(r) => r.iter().any(|r| self.can_meet(phases, r, possible)),
+            _ => true,
+        }
+    }
 
-        // luncheon kingdom
-        let luncheon1 = moons.len();
-        moons.push(Moon::new("The Broodals Are After Some Cookin'", KingdomName::Luncheon));
-        let luncheon2 = moons.len();
-        moons.push(Moon::new("Under the Cheese Rocks", KingdomName::Luncheon));
-        moons[luncheon2].add_prereq_moon(luncheon1);
-        let luncheon3 = moons.len();
-        moons.push(Moon::new_multi("Big Pot on the Volcano: Dive In!", KingdomName::Luncheon));
-        moons[luncheon3].add_prereq_moon(luncheon2);
-        let luncheon4 = moons.len();
-        moons.push(Moon::new("Climb Up the Cascading Magma", KingdomName::Luncheon));
-        moons[luncheon4].add_prereq_moon(luncheon3);
-        let luncheon5 = moons.len();
-        moons.push(Moon::new_multi("Cookatiel Showdown!", KingdomName::Luncheon));
-        moons[luncheon5].add_prereq_moon(luncheon4);
-        kingdoms.kingdom_mut(KingdomName::Luncheon).set_exit_moon(luncheon5);
-        moons.push(Moon::new("Piled on the Salt", KingdomName::Luncheon));
-        moons.push(Moon::new("Lurking in the Pillar's Shadow", KingdomName::Luncheon));
-        let luncheon8 = moons.len();
-        moons.push(Moon::new("Atop the Jutting Crag", KingdomName::Luncheon));
-        moons[luncheon8].add_prereq_moon(luncheon3);
-        let luncheon9 = moons.len();
-        moons.push(Moon::new("Is This an Ingredient Too?!", KingdomName::Luncheon));
-        moons[luncheon9].add_prereq_moon(luncheon1);
-        let luncheon10 = moons.len();
-        moons.push(Moon::new("Atop a Column in a Row", KingdomName::Luncheon));
-        moons[luncheon10].add_prereq_moon(luncheon1);
-        let luncheon11 = moons.len();
-        moons.push(Moon::new("Surrounded by Tall Mountains", KingdomName::Luncheon));
-        moons[luncheon11].add_prereq_moon(luncheon2);
-        let luncheon12 = moons.len();
-        moons.push(Moon::new("Island of Salt Floating in the Lava", KingdomName::Luncheon));
-        moons[luncheon12].add_prereq_moon(luncheon1);
-        let luncheon13 = moons.len();
-        moons.push(Moon::new("Overlooking a Bunch of Ingredients", KingdomName::Luncheon));
-        moons[luncheon13].add_prereq_moon(luncheon2);
-        let luncheon14 = moons.len();
-        moons.push(Moon::new("Light the Lantern on the Small Island", KingdomName::Luncheon));
-        moons[luncheon14].add_prereq_moon(luncheon2);
-        let luncheon15 = moons.len();
-        moons.push(Moon::new("Golden Turnip Recipe 1", KingdomName::Luncheon));
-        moons[luncheon15].add_prereq_moon(luncheon1);
-        let luncheon16 = moons.len();
-        moons.push(Moon::new("Golden Turnip Recipe 2", KingdomName::Luncheon));
-        moons[luncheon16].add_prereq_moon(luncheon3);
-        let luncheon17 = moons.len();
-        moons.push(Moon::new("Golden Turnip Recipe 3", KingdomName::Luncheon));
-        moons[luncheon17].add_prereq_moon(luncheon2);
-        let luncheon18 = moons.len();
-        moons.push(Moon::new("Luncheon Kingdom Timer Challenge 1", KingdomName::Luncheon));
-        let luncheon19 = moons.len();
-        moons.push(Moon::new("Luncheon Kingdom Timer Challenge 2", KingdomName::Luncheon));
-        moons[luncheon19].add_prereq_moon(luncheon2);
-        let luncheon20 = moons.len();
-        moons.push(Moon::new("Luncheon Kingdom Timer Challenge 3", KingdomName::Luncheon));
-        moons[luncheon20].add_prereq_moon(luncheon5);
-        let luncheon21 = moons.len();
-        moons.push(Moon::new("Beneath the Rolling Vegetables", KingdomName::Luncheon));
-        moons[luncheon21].add_prereq_moon(luncheon2);
-        let luncheon22 = moons.len();
-        moons.push(Moon::new("All the Cracks are Fixed", KingdomName::Luncheon));
-        moons[luncheon22].add_prereq_moon(luncheon2);
-        let luncheon23 = moons.len();
-        moons.push(Moon::new("Taking Notes: Swimming in Magma", KingdomName::Luncheon));
-        moons[luncheon23].add_prereq_moon(luncheon2);
-        let luncheon24 = moons.len();
-        moons.push(Moon::new("Love Above the Lava", KingdomName::Luncheon));
-        let luncheon25 = moons.len();
-        moons.push(Moon::new("Shopping in Mount Volbono", KingdomName::Luncheon));
-        moons[luncheon25].add_prereq_moon(luncheon1);
-        let luncheon26 = moons.len();
-        moons.push(Moon::new("Luncheon Kingdom Slots", KingdomName::Luncheon));
-        moons[luncheon26].add_prereq_moon(luncheon1);
-        let luncheon27 = moons.len();
-        moons.push(Moon::new("A Strong Simmer", KingdomName::Luncheon));
-        moons[luncheon27].add_prereq_moon(luncheon1);
-        let luncheon28 = moons.len();
-        moons.push(Moon::new("An Extreme Simmer", KingdomName::Luncheon));
-        moons[luncheon28].add_prereq_moon(luncheon1);
-        let luncheon29 = moons.len();
-        moons.push(Moon::new("Alcove Behind the Pillars of Magma", KingdomName::Luncheon));
-        moons[luncheon29].add_prereq_moon(luncheon3);
-        let luncheon30 = moons.len();
-        moons.push(Moon::new("Treasure Beneath the Cheese Rocks", KingdomName::Luncheon));
-        moons[luncheon30].add_prereq_moon(luncheon3);
-        let luncheon31 = moons.len();
-        moons.push(Moon::new("Light the Two Flames", KingdomName::Luncheon));
-        moons[luncheon31].add_prereq_moon(luncheon3);
-        let luncheon32 = moons.len();
-        moons.push(Moon::new("Light the Far-Off Lanterns", KingdomName::Luncheon));
-        moons[luncheon32].add_prereq_moon(luncheon4);
-        let luncheon33 = moons.len();
-        moons.push(Moon::new("Bon Appetit, Captain Toad!", KingdomName::Luncheon));
-        moons[luncheon33].add_prereq_moon(luncheon4);
-        let luncheon34 = moons.len();
-        moons.push(Moon::new("The Treasure Chest in the Veggies", KingdomName::Luncheon));
-        moons[luncheon34].add_prereq_moon(luncheon3);
-        let luncheon35 = moons.len();
-        moons.push(Moon::new("Caught Hopping in the Volcano!", KingdomName::Luncheon));
-        moons[luncheon35].add_prereq_moon(luncheon5);
-        let luncheon36 = moons.len();
-        moons.push(Moon::new("Taking Notes: Big Pot Swim", KingdomName::Luncheon));
-        moons[luncheon36].add_prereq_moon(luncheon5);
-        let luncheon37 = moons.len();
-        moons.push(Moon::new("Magma Swamp: Floating and Sinking", KingdomName::Luncheon));
-        moons[luncheon37].add_prereq_moon(luncheon1);
-        let luncheon38 = moons.len();
-        moons.push(Moon::new("Corner of the Magma Swamp", KingdomName::Luncheon));
-        moons[luncheon38].add_prereq_moon(luncheon1);
-        let luncheon39 = moons.len();
-        moons.push(Moon::new("Magma Narrow Swamp", KingdomName::Luncheon));
-        moons[luncheon39].add_prereq_moon(luncheon2);
-        let luncheon40 = moons.len();
-        moons.push(Moon::new("Crossing to the Magma", KingdomName::Luncheon));
-        moons[luncheon40].add_prereq_moon(luncheon2);
-        let luncheon41 = moons.len();
-        moons.push(Moon::new("Fork Flickin' to the Summit", KingdomName::Luncheon));
-        moons[luncheon41].add_prereq_moon(luncheon1);
-        let luncheon42 = moons.len();
-        moons.push(Moon::new("Fork Flickin' Detour", KingdomName::Luncheon));
-        moons[luncheon42].add_prereq_moon(luncheon1);
-        let luncheon43 = moons.len();
-        moons.push(Moon::new("Excavate 'n' Search the Cheese Rocks", KingdomName::Luncheon));
-        moons[luncheon43].add_prereq_moon(luncheon1);
-        let luncheon44 = moons.len();
-        moons.push(Moon::new("Climb the Cheese Rocks", KingdomName::Luncheon));
-        moons[luncheon44].add_prereq_moon(luncheon1);
-        let luncheon45 = moons.len();
-        moons.push(Moon::new("Spinning Athletics End Goal", KingdomName::Luncheon));
-        moons[luncheon45].add_prereq_moon(luncheon2);
-        let luncheon46 = moons.len();
-        moons.push(Moon::new("Taking Notes: Spinning Athletics", KingdomName::Luncheon));
-        moons[luncheon46].add_prereq_moon(luncheon2);
-        let luncheon47 = moons.len();
-        moons.push(Moon::new("Secret Path to Mount Volbono!", KingdomName::Luncheon));
-        moons[luncheon47].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[luncheon47].add_prereq_kingdom_count(KingdomName::Lake, 2);
-        moons[luncheon47].add_prereq_kingdom_count(KingdomName::Wooded, 2);
-        moons[luncheon47].add_prereq_moon(wooded2);
-        let luncheon48 = moons.len();
-        moons.push(Moon::new("A Tourist in the Luncheon Kingdom!", KingdomName::Luncheon));
-        moons[luncheon48].add_prereq_moon(luncheon5);
-        moons[luncheon48].add_prereq_moon(cascade19);
-        moons[luncheon49].add_prereq_moon(luncheon5);
-        let luncheon50 = moons.len();
-        moons.push(Moon::new("The Rooftop Lantern", KingdomName::Luncheon));
-        moons[luncheon50].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon51 = moons.len();
-        moons.push(Moon::new("Jammin' in the Luncheon Kingdom", KingdomName::Luncheon));
-        moons[luncheon51].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon52 = moons.len();
-        moons.push(Moon::new("Mechanic: Repairs Complete!", KingdomName::Luncheon));
-        moons[luncheon52].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon53 = moons.len();
-        moons.push(Moon::new("Diving from the Big Pot!", KingdomName::Luncheon));
-        moons[luncheon53].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[luncheon53].add_prereq_moon(luncheon5);
-        let luncheon54 = moons.len();
-        moons.push(Moon::new("Hat-and-Seek: Among the Food", KingdomName::Luncheon));
-        moons[luncheon54].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon55 = moons.len();
-        moons.push(Moon::new("Luncheon Kingdom: Regular Cup", KingdomName::Luncheon));
-        moons[luncheon55].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon56 = moons.len();
-        moons.push(Moon::new("Peach in the Luncheon Kingdom", KingdomName::Luncheon));
-        moons[luncheon56].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon57 = moons.len();
-        moons.push(Moon::new("From Inside a Bright Stone", KingdomName::Luncheon));
-        moons[luncheon57].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon58 = moons.len();
-        moons.push(Moon::new("Under the Meat Plateau", KingdomName::Luncheon));
-        moons[luncheon58].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon59 = moons.len();
-        moons.push(Moon::new("On Top of a Tall, Tall Roof", KingdomName::Luncheon));
-        moons[luncheon59].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon60 = moons.len();
-        moons.push(Moon::new("From a Crack in the Hard Ground", KingdomName::Luncheon));
-        moons[luncheon60].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon61 = moons.len();
-        moons.push(Moon::new("By the Cannon Pointed at the Big Pot", KingdomName::Luncheon));
-        moons[luncheon61].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon62 = moons.len();
-        moons.push(Moon::new("Luncheon Kingdom: Master Cup", KingdomName::Luncheon));
-        moons[luncheon62].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[luncheon62].add_prereq_moon(luncheon55);
-        let luncheon63 = moons.len();
-        moons.push(Moon::new("Stepping Over the Gears", KingdomName::Luncheon));
-        moons[luncheon63].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon64 = moons.len();
-        moons.push(Moon::new("Lanterns on the Gear Steps", KingdomName::Luncheon));
-        moons[luncheon64].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon65 = moons.len();
-        moons.push(Moon::new("Volcano Cave Cruisin'", KingdomName::Luncheon));
-        moons[luncheon65].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon66 = moons.len();
-        moons.push(Moon::new("Volcano Cave and Mysterious Clouds", KingdomName::Luncheon));
-        moons[luncheon66].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon67 = moons.len();
-        moons.push(Moon::new("Treasure of the Lava Islands", KingdomName::Luncheon));
-        moons[luncheon67].add_prereq_kingdom(KingdomName::Mushroom);
-        let luncheon68 = moons.len();
-        moons.push(Moon::new("Flying Over the Lava Islands", KingdomName::Luncheon));
-        moons[luncheon68].add_prereq_kingdom(KingdomName::Mushroom);
-        let dark22 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 8", KingdomName::Luncheon));
-        moons[dark22].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((luncheon1, moons.len()));
+    fn build(kingdoms: &Kingdoms, moons: Cow<'static, [Moon]>,
+             offset: Cow<'static, [(usize, usize)]>) -> Result<Self, RandomizerError> {
+        // resolve every moon each moon depends on, including the moons that
+        // advance a kingdom to a required phase or break a required moon
+        // rock, then build the reverse index over those and the kingdoms
+        // exit moons unlock, leaving out the moons that teach a capture or
+        // count towards a tag since any one of them will do, which the event
+        // watchers keep track of
+        let mut prerequisites = Vec::new();
+        let mut providers = Vec::new();
+        for moon in moons.iter() {
+            let mut p: Vec<MoonID> = moon.prerequisite_moons().collect();
+            let mut any = Vec::new();
+            for r in moon.prerequisites.iter() {
+                for (k, phase) in r.phases() {
+                    let phase_moons = kingdoms.kingdom(k)?.phase_moons();
+                    p.extend(phase_moons.iter().take(phase as usize));
+                }
+                for c in r.captures() {
+                    any.extend((0..moons.len())
+                               .filter(|m| moons[*m].captures.contains(&c)));
+                }
+                for (t, _) in r.tags() {
+                    any.extend((0..moons.len())
+                               .filter(|m| moons[*m].tags.contains(&t)));
+                }
+                for f in r.flags() {
+                    if let Flag::MoonRockBroken(k) = f {
+                        p.extend((0..moons.len()).filter(|m| {
+                            moons[*m].breaks_moon_rock && moons[*m].kingdom == k
+                        }));
+                    }
+                }
+            }
+            let mut resolved: Vec<MoonID> = Vec::new();
+            for m in p {
+                if !resolved.contains(&m) {
+                    resolved.push(m);
+                }
+            }
+            prerequisites.push(resolved);
+            providers.push(any);
+        }
+        let mut dependents = vec![Vec::new(); moons.len()];
+        for (id, p) in prerequisites.iter().enumerate() {
+            for m in p {
+                dependents[*m].push(id);
+            }
+        }
 
-        // ruined kingdom
-        let ruined1 = moons.len();
-        moons.push(Moon::new_multi("Battle with the Lord of Lightning!", KingdomName::Ruined));
-        kingdoms.kingdom_mut(KingdomName::Ruined).set_exit_moon(ruined1);
-        let ruined2 = moons.len();
-        moons.push(Moon::new("In the Ancient Treasure Chest", KingdomName::Ruined));
-        let ruined3 = moons.len();
-        moons.push(Moon::new("Roulette Tower: Climbed", KingdomName::Ruined));
-        moons[ruined3].add_prereq_moon(ruined1);
-        let ruined4 = moons.len();
-        moons.push(Moon::new("Roulette Tower: Stopped", KingdomName::Ruined));
-        moons[ruined4].add_prereq_moon(ruined1);
-        let ruined5 = moons.len();
-        moons.push(Moon::new("Peach in the Ruined Kingdom", KingdomName::Ruined));
-        moons[ruined5].add_prereq_kingdom(KingdomName::Mushroom);
-        let ruined6 = moons.len();
-        moons.push(Moon::new("Caught on a Big Horn", KingdomName::Ruined));
-        moons[ruined6].add_prereq_kingdom(KingdomName::Mushroom);
-        let ruined7 = moons.len();
-        moons.push(Moon::new("Upon the Broken Arch", KingdomName::Ruined));
-        moons[ruined7].add_prereq_kingdom(KingdomName::Mushroom);
-        let ruined8 = moons.len();
-        moons.push(Moon::new("Rolling Rock on the Battlefield", KingdomName::Ruined));
-        moons[ruined8].add_prereq_kingdom(KingdomName::Mushroom);
-        let ruined9 = moons.len();
-        moons.push(Moon::new("Charging Through an Army", KingdomName::Ruined));
-        moons[ruined9].add_prereq_kingdom(KingdomName::Mushroom);
-        let ruined10 = moons.len();
-        moons.push(Moon::new("The Mummy Army's Curse", KingdomName::Ruined));
-        moons[ruined10].add_prereq_kingdom(KingdomName::Mushroom);
-        let dark24 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 10", KingdomName::Ruined));
-        moons[dark24].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((ruined1, moons.len()));
+        // an exit moon also unlocks the kingdoms after its kingdom, and so
+        // every moon in them or needing a visit to them, and the main game
+        // ends in Mushroom, so its exit moon opens up the post game
+        let mut needing = vec![Vec::new(); KingdomName::ALL.len()];
+        for (id, moon) in moons.iter().enumerate() {
+            let mut needs = vec![moon.kingdom];
+            needs.extend(moon.prerequisite_kingdoms().map(|(k, _)| k));
+            if moon.prerequisites.iter()
+                .any(|r| r.flags().any(|f| f == Flag::CompletedMainGame)) {
+                needs.push(KingdomName::Mushroom);
+            }
+            for k in needs {
+                if !needing[k as usize].contains(&id) {
+                    needing[k as usize].push(id);
+                }
+            }
+        }
+        for (k, kingdom) in kingdoms.iter() {
+            let exit = match kingdom.exit_moon() {
+                Some(exit) => exit,
+                None => continue,
+            };
+            let mut unlocked = kingdom.next().to_vec();
+            if k == KingdomName::Mushroom {
+                unlocked.push(k);
+            }
+            for n in unlocked {
+                for m in &needing[n as usize] {
+                    if *m != exit && !dependents[exit].contains(m) {
+                        dependents[exit].push(*m);
+                    }
+                }
+            }
+        }
 
-        // bowser's kingdom
-        let bowser1 = moons.len();
-        moons.push(Moon::new("Infiltrate Bowser's Castle", KingdomName::Bowser));
-        let bowser2 = moons.len();
-        moons.push(Moon::new("Smart Bombing", KingdomName::Bowser));
-        moons[bowser2].add_prereq_moon(bowser1);
-        let bowser3 = moons.len();
-        moons.push(Moon::new("Big Broodal Battle", KingdomName::Bowser));
-        moons[bowser3].add_prereq_moon(bowser2);
-        let bowser4 = moons.len();
-        moons.push(Moon::new_multi("Showdown at Bowser's Castle", KingdomName::Bowser));
-        kingdoms.kingdom_mut(KingdomName::Bowser).set_exit_moon(bowser4);
-        moons[bowser4].add_prereq_moon(bowser3);
-        moons.push(Moon::new("Behind the Big Wall", KingdomName::Bowser));
-        let bowser6 = moons.len();
-        moons.push(Moon::new("Treasure Inside the Turret", KingdomName::Bowser));
-        moons[bowser6].add_prereq_moon(bowser1);
-        let bowser7 = moons.len();
-        moons.push(Moon::new("From the Side Above the Castle Gate", KingdomName::Bowser));
-        moons[bowser7].add_prereq_moon(bowser2);
-        let bowser8 = moons.len();
-        moons.push(Moon::new("Sunken Treasure in the Moat", KingdomName::Bowser));
-        moons[bowser8].add_prereq_moon(bowser3);
-        let bowser9 = moons.len();
-        moons.push(Moon::new("Past the Moving Wall", KingdomName::Bowser));
-        moons[bowser9].add_prereq_moon(bowser4);
-        let bowser10 = moons.len();
-        moons.push(Moon::new("Above the Poison Swamp", KingdomName::Bowser));
-        moons[bowser10].add_prereq_moon(bowser4);
-        let bowser11 = moons.len();
-        moons.push(Moon::new("Knocking Down the Nice Frame", KingdomName::Bowser));
-        moons[bowser11].add_prereq_moon(bowser4);
-        let bowser12 = moons.len();
-        moons.push(Moon::new("Caught on the Iron Fence", KingdomName::Bowser));
-        let bowser13 = moons.len();
-        moons.push(Moon::new("On the Giant Bowser Statue's Nose", KingdomName::Bowser));
-        moons[bowser13].add_prereq_moon(bowser3);
-        let bowser14 = moons.len();
-        moons.push(Moon::new("Inside a Block in the Castle", KingdomName::Bowser));
-        moons[bowser14].add_prereq_moon(bowser3);
-        let bowser15 = moons.len();
-        moons.push(Moon::new("Caught Hopping at Bowser's Castle", KingdomName::Bowser));
-        moons[bowser15].add_prereq_moon(bowser4);
-        let bowser16 = moons.len();
-        moons.push(Moon::new("Exterminate the Ogres", KingdomName::Bowser));
-        moons[bowser16].add_prereq_moon(bowser2);
-        let bowser17 = moons.len();
-        moons.push(Moon::new("Bowser's Kingdom Timer Challenge 1", KingdomName::Bowser));
-        moons[bowser17].add_prereq_moon(bowser4);
-        let bowser18 = moons.len();
-        moons.push(Moon::new("Taking Notes: Between Spinies", KingdomName::Bowser));
-        moons.push(Moon::new("Stack Up Above the Wall", KingdomName::Bowser));
-        let bowser20 = moons.len();
-        moons.push(Moon::new("Hidden Corridor Under the Floor", KingdomName::Bowser));
-        moons[bowser20].add_prereq_moon(bowser3);
-        let bowser21 = moons.len();
-        moons.push(Moon::new("Poking Your Nose in the Plaster Wall", KingdomName::Bowser));
-        moons[bowser21].add_prereq_moon(bowser1);
-        let bowser22 = moons.len();
-        moons.push(Moon::new("Poking the Turret Wall", KingdomName::Bowser));
-        moons[bowser22].add_prereq_moon(bowser1);
-        let bowser23 = moons.len();
-        moons.push(Moon::new("Poking Your Nose by the Great Gate", KingdomName::Bowser));
-        moons[bowser23].add_prereq_moon(bowser3);
-        let bowser24 = moons.len();
-        moons.push(Moon::new("Jizo All in a Row", KingdomName::Bowser));
-        moons[bowser24].add_prereq_moon(bowser2);
-        let bowser25 = moons.len();
-        moons.push(Moon::new("Underground Jizo", KingdomName::Bowser));
-        moons[bowser25].add_prereq_moon(bowser2);
-        let bowser26 = moons.len();
-        moons.push(Moon::new("Found Behind Bars!", KingdomName::Bowser));
-        moons[bowser26].add_prereq_moon(bowser3);
-        let bowser27 = moons.len();
-        moons.push(Moon::new("Fishing(?) in Bowser's Castle", KingdomName::Bowser));
-        moons[bowser27].add_prereq_moon(bowser4);
-        let bowser28 = moons.len();
-        moons.push(Moon::new("Good to See You, Captain Toad!", KingdomName::Bowser));
-        moons[bowser28].add_prereq_moon(bowser3);
-        let bowser29 = moons.len();
-        moons.push(Moon::new("Shopping at Bowser's Castle", KingdomName::Bowser));
-        moons[bowser29].add_prereq_moon(bowser1);
-        let bowser30 = moons.len();
-        moons.push(Moon::new("Bowser's Castle Treasure Vault", KingdomName::Bowser));
-        moons[bowser30].add_prereq_moon(bowser4);
-        let bowser31 = moons.len();
-        moons.push(Moon::new("Scene of Crossing the Poison Swamp", KingdomName::Bowser));
-        moons[bowser31].add_prereq_moon(bowser1);
-        let bowser32 = moons.len();
-        moons.push(Moon::new("Taking Notes: In the Folding Screen", KingdomName::Bowser));
-        moons[bowser32].add_prereq_moon(bowser1);
-        let bowser33 = moons.len();
-        moons.push(Moon::new("On Top of the Spinning Tower", KingdomName::Bowser));
-        moons[bowser33].add_prereq_moon(bowser4);
-        let bowser34 = moons.len();
-        moons.push(Moon::new("Down and Up the Spinning Tower", KingdomName::Bowser));
-        moons[bowser34].add_prereq_moon(bowser4);
-        let bowser35 = moons.len();
-        moons.push(Moon::new("Jizo's Big Adventure", KingdomName::Bowser));
-        moons[bowser35].add_prereq_moon(bowser4);
-        let bowser36 = moons.len();
-        moons.push(Moon::new("Jizo and the Hidden Room", KingdomName::Bowser));
-        moons[bowser36].add_prereq_moon(bowser4);
-        let bowser37 = moons.len();
-        moons.push(Moon::new("Dashing Above the Clouds", KingdomName::Bowser));
-        moons[bowser37].add_prereq_moon(bowser3);
-        let bowser38 = moons.len();
-        moons.push(Moon::new("Dashing Through the Clouds", KingdomName::Bowser));
-        moons[bowser38].add_prereq_moon(bowser3);
-        let bowser39 = moons.len();
-        moons.push(Moon::new("Sphynx Over Bowser's Castle", KingdomName::Bowser));
-        moons[bowser39].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser40 = moons.len();
-        moons.push(Moon::new("I Met a Pokio!", KingdomName::Bowser));
-        moons[bowser40].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser41 = moons.len();
-        moons.push(Moon::new("Bowser's Kingdom Regular Cup", KingdomName::Bowser));
-        moons[bowser41].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser42 = moons.len();
-        moons.push(Moon::new("A Rumble Under the Arena Floor", KingdomName::Bowser));
-        moons[bowser42].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser43 = moons.len();
-        moons.push(Moon::new("Secret Path to Bowser's Castle", KingdomName::Bowser));
-        moons[bowser43].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[bowser43].add_prereq_kingdom_count(KingdomName::Cascade, 2);
-        let bowser44 = moons.len();
-        moons.push(Moon::new("Peach in Bowser's Kingdom", KingdomName::Bowser));
-        moons[bowser44].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser46 = moons.len();
-        moons.push(Moon::new("Behind the Tall Wall: Poke, Poke!", KingdomName::Bowser));
-        moons[bowser46].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser47 = moons.len();
-        moons.push(Moon::new("From Crates in the Moat", KingdomName::Bowser));
-        moons[bowser47].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser48 = moons.len();
-        moons.push(Moon::new("Caught on the Giant Horn", KingdomName::Bowser));
-        moons[bowser48].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser49 = moons.len();
-        moons.push(Moon::new("Inside a Block at the Gate", KingdomName::Bowser));
-        moons[bowser49].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser50 = moons.len();
-        moons.push(Moon::new("Small Bird in Bowser's Castle", KingdomName::Bowser));
-        moons[bowser50].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser51 = moons.len();
-        moons.push(Moon::new("Invader in Bowser's Castle", KingdomName::Bowser));
-        moons[bowser51].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser52 = moons.len();
-        moons.push(Moon::new("Jumping from Flag to Flag", KingdomName::Bowser));
-        moons[bowser52].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser53 = moons.len();
-        moons.push(Moon::new("Bowser's Kingdom Timer Challenge 2", KingdomName::Bowser));
-        moons[bowser53].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser54 = moons.len();
-        moons.push(Moon::new("Taking Notes: On the Wall", KingdomName::Bowser));
-        moons[bowser54].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser55 = moons.len();
-        moons.push(Moon::new("Taking Notes with a Spinning Throw", KingdomName::Bowser));
-        moons[bowser55].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser56 = moons.len();
-        moons.push(Moon::new("Third Courtyard Outskirts", KingdomName::Bowser));
-        moons[bowser56].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser57 = moons.len();
-        moons.push(Moon::new("Stone Wall Circuit", KingdomName::Bowser));
-        moons[bowser57].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser58 = moons.len();
-        moons.push(Moon::new("Bowser's Kingdom Master Cup", KingdomName::Bowser));
-        moons[bowser58].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[bowser58].add_prereq_moon(bowser41);
-        let bowser59 = moons.len();
-        moons.push(Moon::new("Searching Hexagon Tower", KingdomName::Bowser));
-        moons[bowser59].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser60 = moons.len();
-        moons.push(Moon::new("Center of Hexagon Tower", KingdomName::Bowser));
-        moons[bowser60].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser61 = moons.len();
-        moons.push(Moon::new("Climb the Wooden Tower", KingdomName::Bowser));
-        moons[bowser61].add_prereq_kingdom(KingdomName::Mushroom);
-        let bowser62 = moons.len();
-        moons.push(Moon::new("Poke the Wooden Tower", KingdomName::Bowser));
-        moons[bowser62].add_prereq_kingdom(KingdomName::Mushroom);
-        let sand63 = moons.len();
-        moons.push(Moon::new("Found with Sand Kingdom Art", KingdomName::Bowser));
-        moons[sand63].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[sand63].add_prereq_kingdom_count(KingdomName::Sand, 2);
-        offset.push((bowser1, moons.len()));
+        // the story is every moon needed to leave a kingdom or move it on,
+        // and everything those moons depend on, keeping every moon that can
+        // teach a capture or count towards a tag they need
+        let mut queue: VecDeque<MoonID> = kingdoms.iter()
+            .flat_map(|(_, kingdom)| {
+                kingdom.exit_moon().into_iter()
+                    .chain(kingdom.phase_moons().iter().copied())
+            })
+            .collect();
+        let mut story = vec![false; moons.len()];
+        while let Some(id) = queue.pop_front() {
+            if story[id] {
+                continue;
+            }
+            story[id] = true;
+            queue.extend(&prerequisites[id]);
+            queue.extend(&providers[id]);
+        }
 
-        // moon kingdom
-        let moon1 = moons.len();
-        moons.push(Moon::new("Shining Above the Moon", KingdomName::Moon));
-        moons.push(Moon::new("Along the Cliff Face", KingdomName::Moon));
-        let moon3 = moons.len();
-        moons.push(Moon::new("The Tip of a White Spire", KingdomName::Moon));
-        moons.push(Moon::new("Rolling Rock on the Moon", KingdomName::Moon));
-        let moon5 = moons.len();
-        moons.push(Moon::new("Caught Hopping on the Moon!", KingdomName::Moon));
-        let moon6 = moons.len();
-        moons.push(Moon::new("Cliffside Treasure Chest", KingdomName::Moon));
-        let moon7 = moons.len();
-        moons.push(Moon::new("Moon Kingdom Timer Challenge 1", KingdomName::Moon));
-        let moon8 = moons.len();
-        moons.push(Moon::new("Taking Notes: On the Moon's Surface", KingdomName::Moon));
-        moons.push(Moon::new("Under the Bowser Statue", KingdomName::Moon));
-        moons.push(Moon::new("In a Hole in the Magma", KingdomName::Moon));
-        moons.push(Moon::new("Around the Barrier Wall", KingdomName::Moon));
-        moons.push(Moon::new("On Top of the Cannon", KingdomName::Moon));
-        let moon13 = moons.len();
-        moons.push(Moon::new("Fly to the Treasure Chest and Back", KingdomName::Moon));
-        moons.push(Moon::new("Up in the Rafters", KingdomName::Moon));
-        let moon15 = moons.len();
-        moons.push(Moon::new("Sneaking Around in the Crater", KingdomName::Moon));
-        moons[moon15].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon16 = moons.len();
-        moons.push(Moon::new("Found on the Moon, Good Dog!", KingdomName::Moon));
-        moons[moon16].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon17 = moons.len();
-        moons.push(Moon::new("Moon Shards on the Moon", KingdomName::Moon));
-        moons[moon17].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon18 = moons.len();
-        moons.push(Moon::new("Moon Quiz: Amazing!", KingdomName::Moon));
-        moons[moon18].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon19 = moons.len();
-        moons.push(Moon::new("Thanks, Captain Toad!", KingdomName::Moon));
-        moons[moon19].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon20 = moons.len();
-        moons.push(Moon::new("Shopping in Honeylune Ridge", KingdomName::Moon));
-        moons[moon20].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon21 = moons.len();
-        moons.push(Moon::new("Walking on the Moon!", KingdomName::Moon));
-        moons[moon21].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon22 = moons.len();
-        moons.push(Moon::new("Moon Kingdom Regular Cup!", KingdomName::Moon));
-        moons[moon22].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon23 = moons.len();
-        moons.push(Moon::new("Doctor in the House", KingdomName::Moon));
-        moons[moon23].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon24 = moons.len();
-        moons.push(Moon::new("Sphynx's Hidden Vault", KingdomName::Moon));
-        moons[moon24].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon25 = moons.len();
-        moons.push(Moon::new("A Tourist in the Moon Kingdom!", KingdomName::Moon));
-        moons[moon25].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[moon25].add_prereq_moon(luncheon48);
-        let moon26 = moons.len();
-        moons.push(Moon::new("Peach in the Moon Kingdom", KingdomName::Moon));
-        moons[moon26].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[moon26].add_prereq_moon(cap16);
-        moons[moon26].add_prereq_moon(cascade21);
-        moons[moon26].add_prereq_moon(sand69);
-        moons[moon26].add_prereq_moon(lake33);
-        moons[moon26].add_prereq_moon(wooded54);
-        moons[moon26].add_prereq_moon(cloud2);
-        moons[moon26].add_prereq_moon(lost25);
-        moons[moon26].add_prereq_moon(metro66);
-        moons[moon26].add_prereq_moon(snow37);
-        moons[moon26].add_prereq_moon(seaside52);
-        moons[moon26].add_prereq_moon(luncheon56);
-        moons[moon26].add_prereq_moon(ruined5);
-        moons[moon26].add_prereq_moon(bowser44);
-        let moon28 = moons.len();
-        moons.push(Moon::new("Mysterious Flying Object", KingdomName::Moon));
-        moons[moon28].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon29 = moons.len();
-        moons.push(Moon::new("Hidden on the Side of the Cliff", KingdomName::Moon));
-        moons[moon29].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon30 = moons.len();
-        moons.push(Moon::new("Jumping High as a Frog", KingdomName::Moon));
-        moons[moon30].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon31 = moons.len();
-        moons.push(Moon::new("Moon Kingdom Timer Challenge 2", KingdomName::Moon));
-        moons[moon31].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon32 = moons.len();
-        moons.push(Moon::new("Walking on the Moon: Again!", KingdomName::Moon));
-        moons[moon32].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[moon32].add_prereq_moon(moon21);
-        let moon33 = moons.len();
-        moons.push(Moon::new("Moon Kingdom Master Cup", KingdomName::Moon));
-        moons[moon33].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[moon33].add_prereq_moon(moon22);
-        let moon34 = moons.len();
-        moons.push(Moon::new("Taking Notes: In Low Gravity", KingdomName::Moon));
-        moons[moon34].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon35 = moons.len();
-        moons.push(Moon::new("Center of the Galaxy", KingdomName::Moon));
-        moons[moon35].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon36 = moons.len();
-        moons.push(Moon::new("Edge of the Galaxy", KingdomName::Moon));
-        moons[moon36].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon37 = moons.len();
-        moons.push(Moon::new("Navigating Giant Swings", KingdomName::Moon));
-        moons[moon37].add_prereq_kingdom(KingdomName::Mushroom);
-        let moon38 = moons.len();
-        moons.push(Moon::new("A Swing on Top of a Swing", KingdomName::Moon));
-        moons[moon38].add_prereq_kingdom(KingdomName::Mushroom);
-        let cap17 = moons.len();
-        moons.push(Moon::new("Found with Cap Kingdom Art", KingdomName::Moon));
-        moons[cap17].add_prereq_kingdom(KingdomName::Mushroom);
-        moons[cap17].add_prereq_kingdom_count(KingdomName::Cap, 2);
-        offset.push((moon1, moons.len()));
+        // index the requirements by what can satisfy them, in the order the
+        // event meets them, so a route only rechecks the moons something has
+        // just happened for
+        let start = State::new();
+        let mut waiting = vec![Vec::new(); moons.len()];
+        let mut events = HashMap::new();
+        let mut watchers: Vec<Vec<(u16, MoonID, usize)>> = Vec::new();
+        let mut unmet = Vec::new();
+        for (id, moon) in moons.iter().enumerate() {
+            let mut count = 0;
+            for (r, requirement) in moon.prerequisites.iter().enumerate() {
+                if let Requirement::Moon(m) = requirement {
+                    waiting[*m].push(id);
+                    count += 1;
+                } else if let Some((e, level)) = requirement.event() {
+                    let next = watchers.len();
+                    let event = *events.entry(e).or_insert(next);
+                    if event == next {
+                        watchers.push(Vec::new());
+                    }
+                    watchers[event].push((level, id, r));
+                    if !requirement.satisfied(&start) {
+                        count += 1;
+                    }
+                }
+            }
+            unmet.push(count);
+        }
+        let watchers: Vec<Vec<(MoonID, usize)>> = watchers.into_iter()
+            .map(|mut w| {
+                w.sort_by_key(|(level, _, _)| *level);
+                w.into_iter().map(|(_, id, r)| (id, r)).collect()
+            })
+            .collect();
+        let find = |e: Event| events.get(&e).copied();
+        let triggers = moons.iter()
+            .map(|moon| {
+                let mut e = vec![Event::MoonCount];
+                e.extend(moon.tags.iter().map(|t| Event::Tag(*t)));
+                if moon.advances_phase {
+                    e.push(Event::Phase(moon.kingdom));
+                }
+                if moon.breaks_moon_rock {
+                    e.push(Event::MoonRock(moon.kingdom));
+                }
+                e.extend(moon.captures.iter().map(|c| Event::Capture(*c)));
+                e.into_iter().filter_map(find).collect()
+            })
+            .collect();
+        let visits = KingdomName::ALL.iter()
+            .map(|k| {
+                vec![Event::Visit(*k), Event::PostGameVisit(*k)].into_iter()
+                    .filter_map(find).collect()
+            })
+            .collect();
+        let reached = watchers.iter()
+            .map(|w| {
+                w.iter()
+                    .take_while(|(id, r)| moons[*id].prerequisites[*r].satisfied(&start))
+                    .count()
+            })
+            .collect();
+
+        Ok(Moons {
+            moons,
+            offset,
+            prerequisites,
+            dependents,
+            story,
+            waiting,
+            triggers,
+            visits,
+            watchers,
+            unmet,
+            reached,
+        })
+    }
+}
+
+// the moons left to schedule in one route, kept apart from the moon data so
+// many routes can share it
+#[derive(Clone)]
+pub struct MoonPool {
+    // requirements still unmet for each moon, ready to check at zero
+    unmet: Vec<u16>,
+    // how far through each event's watchers the route has got
+    reached: Vec<usize>,
+    excluded: Vec<bool>,
+    // moons with nothing unmet, waiting on their kingdom, coins or phase
+    ready: Vec<Vec<MoonID>>,
+    // moons not yet returned in each kingdom
+    remaining: Vec<usize>,
+}
 
-        // mushroom
-        let mushroom1 = moons.len();
-        moons.push(Moon::new("Perched on the Castle Roof", KingdomName::Mushroom));
-        let mushroom2 = moons.len();
-        moons.push(Moon::new("Pops Out of the Tail", KingdomName::Mushroom));
-        let mushroom3 = moons.len();
-        moons.push(Moon::new("Caught Hopping at Peach's Castle!", KingdomName::Mushroom));
-        moons.push(Moon::new("Gardening for Toad: Garden Seed", KingdomName::Mushroom));
-        moons.push(Moon::new("Gardening for Toad: Field Seed", KingdomName::Mushroom));
-        moons.push(Moon::new("Gardening for Toad: Pasture Seed", KingdomName::Mushroom));
-        moons.push(Moon::new("Gardening for Toad: Lake Seed", KingdomName::Mushroom));
-        moons.push(Moon::new("Grow a Flower Garden", KingdomName::Mushroom));
-        let mushroom9 = moons.len();
-        moons.push(Moon::new("Mushroom Kingdom Timer Challenge", KingdomName::Mushroom));
-        moons.push(Moon::new("Found at Peach's Castle! Good Dog!", KingdomName::Mushroom));
-        let mushroom11 = moons.len();
-        moons.push(Moon::new("Taking Notes: Around the Well", KingdomName::Mushroom));
-        let mushroom12 = moons.len();
-        moons.push(Moon::new("Herding Sheep at Peach's Castle", KingdomName::Mushroom));
-        let mushroom13 = moons.len();
-        moons.push(Moon::new("Gobbling Fruit with Yoshi", KingdomName::Mushroom));
-        let mushroom14 = moons.len();
-        moons.push(Moon::new("Yoshi's Second Helping!", KingdomName::Mushroom));
-        moons[mushroom14].add_prereq_moon(mushroom13);
-        let mushroom15 = moons.len();
-        moons.push(Moon::new("Yoshi's All Filled Up!", KingdomName::Mushroom));
-        moons[mushroom15].add_prereq_moon(mushroom14);
-        let mushroom16 = moons.len();
-        moons.push(Moon::new("Love at Peach's Castle", KingdomName::Mushroom));
-        moons.push(Moon::new("Toad Defender", KingdomName::Mushroom));
-        moons.push(Moon::new("Forever Onward, Captain Toad!", KingdomName::Mushroom));
-        let mushroom19 = moons.len();
-        moons.push(Moon::new("Jammin' in the Mushroom Kingdom", KingdomName::Mushroom));
-        moons.push(Moon::new("Shopping Near Peach's Kingdom", KingdomName::Mushroom));
-        let mushroom21 = moons.len();
-        moons.push(Moon::new("Mushroom Kingdom Regular Cup", KingdomName::Mushroom));
-        let mushroom22 = moons.len();
-        moons.push(Moon::new("Mushroom Kingdom Master Cup", KingdomName::Mushroom));
-        moons[mushroom22].add_prereq_moon(mushroom21);
-        let mushroom23 = moons.len();
-        moons.push(Moon::new("Picture Match: Basically a Mario", KingdomName::Mushroom));
-        let mushroom24 = moons.len();
-        moons.push(Moon::new("Picture Match: A Stellar Mario!", KingdomName::Mushroom));
-        moons[mushroom24].add_prereq_moon(mushroom23);
-        moons.push(Moon::new("Light from the Ceiling", KingdomName::Mushroom));
-        moons.push(Moon::new("Loose-Tile Trackdown", KingdomName::Mushroom));
-        let mushroom27 = moons.len();
-        moons.push(Moon::new("Totally Classic", KingdomName::Mushroom));
-        let mushroom28 = moons.len();
-        moons.push(Moon::new("Courtyard Chest Trap", KingdomName::Mushroom));
-        moons.push(Moon::new("Yoshi's Feast in the Sea of Clouds", KingdomName::Mushroom));
-        moons.push(Moon::new("Sunken Star in the Sea of Clouds", KingdomName::Mushroom));
-        moons.push(Moon::new("Secret 2D Treasure", KingdomName::Mushroom));
-        moons.push(Moon::new("2D Boost from Bullet Bill", KingdomName::Mushroom));
-        moons.push(Moon::new_multi("Tussle in Tostarena: Rematch", KingdomName::Mushroom));
-        moons.push(Moon::new_multi("Struggle in Steam Gardens: Rematch", KingdomName::Mushroom));
-        moons.push(Moon::new_multi("Dust-Up in New Donk City: Rematch", KingdomName::Mushroom));
-        moons.push(Moon::new_multi("Battle in Bubblaine: Rematch", KingdomName::Mushroom));
-        moons.push(Moon::new_multi("Blowup in Mount Volbano: Rematch", KingdomName::Mushroom));
-        moons.push(Moon::new_multi("Rumble in Crumbleden: Rematch", KingdomName::Mushroom));
-        let mushroom39 = moons.len();
-        moons.push(Moon::new("Secret Path to Peach's Castle!", KingdomName::Mushroom));
-        moons[mushroom39].add_prereq_moon(luncheon2);
-        moons[mushroom39].add_prereq_kingdom_count(KingdomName::Luncheon, 2);
-        let mushroom40 = moons.len();
-        moons.push(Moon::new("A Tourist in the Mushroom Kingdom", KingdomName::Mushroom));
-        moons[mushroom40].add_prereq_moon(moon25);
-        moons[sand68].add_prereq_moon(mushroom40);
-        let mushroom42 = moons.len();
-        moons.push(Moon::new("Hat-and-Seek: Mushroom Kingdom", KingdomName::Mushroom));
-        moons[mushroom42].add_prereq_moon(moon26);
-        let mushroom43 = moons.len();
-        moons.push(Moon::new("Princess Peach, Home Again!", KingdomName::Mushroom));
-        moons[mushroom43].add_prereq_moon(moon26);
-        let mushroom44 = moons.len();
-        moons.push(Moon::new("Rescue Princess Peach", KingdomName::Mushroom));
-        let mushroom45 = moons.len();
-        moons.push(Moon::new("Achieve World Peace", KingdomName::Mushroom));
-        moons[mushroom45].add_prereq_moon(mushroom44);
-        moons[mushroom45].add_prereq_moon(cascade2);
-        moons[mushroom45].add_prereq_moon(sand4);
-        moons[mushroom45].add_prereq_moon(lake1);
-        moons[mushroom45].add_prereq_moon(wooded4);
-        moons[mushroom45].add_prereq_moon(metro7);
-        moons[mushroom45].add_prereq_moon(snow5);
-        moons[mushroom45].add_prereq_moon(seaside5);
-        moons[mushroom45].add_prereq_moon(luncheon5);
-        moons[mushroom45].add_prereq_moon(ruined1);
-        moons[mushroom45].add_prereq_moon(bowser4);
-        let mushroom46 = moons.len();
-        moons.push(Moon::new("Power Moon Knight", KingdomName::Mushroom));
-        moons[mushroom46].add_prereq_moon(mushroom45);
-        moons[mushroom46].set_prereq_moon_count(100);
-        let mushroom47 = moons.len();
-        moons.push(Moon::new("Power Moon Wizard", KingdomName::Mushroom));
-        moons[mushroom47].add_prereq_moon(mushroom46);
-        moons[mushroom47].set_prereq_moon_count(300);
-        let mushroom48 = moons.len();
-        moons.push(Moon::new("Power Moon Ruler", KingdomName::Mushroom));
-        moons[mushroom48].add_prereq_moon(mushroom47);
-        moons[mushroom48].set_prereq_moon_count(600);
-        let mushroom49 = moons.len();
-        moons.push(Moon::new("Regional Coin Shopper", KingdomName::Mushroom));
-        moons[mushroom49].add_prereq_moon(mushroom48);
-        let mushroom50 = moons.len();
-        moons.push(Moon::new("Flat Moon Finder", KingdomName::Mushroom));
-        moons[mushroom50].add_prereq_moon(mushroom49);
-        moons[mushroom50].add_prereq_moon(cascade4);
-        moons[mushroom50].add_prereq_moon(cascade17);
-        moons[mushroom50].add_prereq_moon(cascade35);
-        moons[mushroom50].add_prereq_moon(sand9);
-        moons[mushroom50].add_prereq_moon(sand10);
-        moons[mushroom50].add_prereq_moon(cloud8);
-        moons[mushroom50].add_prereq_moon(cloud9);
-        moons[mushroom50].add_prereq_moon(lost6);
-        moons[mushroom50].add_prereq_moon(metro36);
-        moons[mushroom50].add_prereq_moon(metro64);
-        let mushroom51 = moons.len();
-        moons.push(Moon::new("Flat Moon Fanatic", KingdomName::Mushroom));
-        moons[mushroom51].add_prereq_moon(mushroom50);
-        moons[mushroom51].add_prereq_moon(metro65);
-        moons[mushroom51].add_prereq_moon(snow30);
-        moons[mushroom51].add_prereq_moon(seaside8);
-        moons[mushroom51].add_prereq_moon(seaside9);
-        moons[mushroom51].add_prereq_moon(seaside27);
-        moons[mushroom51].add_prereq_moon(seaside65);
-        moons[mushroom51].add_prereq_moon(luncheon21);
-        moons[mushroom51].add_prereq_moon(ruined4);
-        moons[mushroom51].add_prereq_moon(bowser31);
-        moons[mushroom51].add_prereq_moon(bowser32);
-        let mushroom52 = moons.len();
-        moons.push(Moon::new("Treasure Chest Hunter", KingdomName::Mushroom));
-        moons[mushroom52].add_prereq_moon(mushroom51);
-        moons[mushroom52].add_prereq_moon(cascade6);
-        moons[mushroom52].add_prereq_moon(sand24);
-        moons[mushroom52].add_prereq_moon(sand46);
-        moons[mushroom52].add_prereq_moon(sand47);
-        moons[mushroom52].add_prereq_moon(sand51);
-        moons[mushroom52].add_prereq_moon(lake8);
-        moons[mushroom52].add_prereq_moon(lake25);
-        moons[mushroom52].add_prereq_moon(wooded26);
-        moons[mushroom52].add_prereq_moon(wooded35);
-        moons[mushroom52].add_prereq_moon(wooded36);
-        moons[mushroom52].add_prereq_moon(metro34);
-        moons[mushroom52].add_prereq_moon(metro48);
-        moons[mushroom52].add_prereq_moon(snow11);
-        moons[mushroom52].add_prereq_moon(snow12);
-        moons[mushroom52].add_prereq_moon(snow33);
-        let mushroom53 = moons.len();
-        moons.push(Moon::new("Super Treasure Chest Hunter", KingdomName::Mushroom));
-        moons[mushroom53].add_prereq_moon(mushroom52);
-        moons[mushroom53].add_prereq_moon(seaside21);
-        moons[mushroom53].add_prereq_moon(seaside22);
-        moons[mushroom53].add_prereq_moon(seaside46);
-        moons[mushroom53].add_prereq_moon(luncheon34);
-        moons[mushroom53].add_prereq_moon(ruined2);
-        moons[mushroom53].add_prereq_moon(bowser30);
-        moons[mushroom53].add_prereq_moon(moon6);
-        moons[mushroom53].add_prereq_moon(moon13);
-        moons[mushroom53].add_prereq_moon(moon24);
-        moons[mushroom53].add_prereq_moon(mushroom28);
-        let mushroom54 = moons.len();
-        moons.push(Moon::new("Note-Collecting World Tour", KingdomName::Mushroom));
-        moons[mushroom54].add_prereq_moon(mushroom53);
-        moons[mushroom54].add_prereq_moon(cap27);
-        moons[mushroom54].add_prereq_moon(cascade35);
-        moons[mushroom54].add_prereq_moon(sand32);
-        moons[mushroom54].add_prereq_moon(sand79);
-        moons[mushroom54].add_prereq_moon(sand80);
-        let mushroom55 = moons.len();
-        moons.push(Moon::new("Note-Collecting Space Tour", KingdomName::Mushroom));
-        moons[mushroom55].add_prereq_moon(mushroom54);
-        moons[mushroom55].add_prereq_moon(lake13);
-        moons[mushroom55].add_prereq_moon(lake14);
-        moons[mushroom55].add_prereq_moon(wooded67);
-        moons[mushroom55].add_prereq_moon(wooded68);
-        moons[mushroom55].add_prereq_moon(cloud6);
-        moons[mushroom55].add_prereq_moon(lost29);
-        moons[mushroom55].add_prereq_moon(snow16);
-        moons[mushroom55].add_prereq_moon(seaside31);
-        moons[mushroom55].add_prereq_moon(seaside65);
-        moons[mushroom55].add_prereq_moon(seaside66);
-        moons[mushroom55].add_prereq_moon(luncheon23);
-        moons[mushroom55].add_prereq_moon(luncheon36);
-        moons[mushroom55].add_prereq_moon(luncheon46);
-        moons[mushroom55].add_prereq_moon(bowser18);
-        moons[mushroom55].add_prereq_moon(bowser54);
-        moons[mushroom55].add_prereq_moon(bowser55);
-        moons[mushroom55].add_prereq_moon(moon8);
-        moons[mushroom55].add_prereq_moon(moon34);
-        moons[mushroom55].add_prereq_moon(mushroom11);
-        let mushroom56 = moons.len();
-        moons.push(Moon::new("Timer Challenge Amateur", KingdomName::Mushroom));
-        moons[mushroom56].add_prereq_moon(mushroom55);
-        moons[mushroom56].add_prereq_moon(cap3);
-        moons[mushroom56].add_prereq_moon(cap28);
-        moons[mushroom56].add_prereq_moon(cascade9);
-        moons[mushroom56].add_prereq_moon(cascade10);
-        moons[mushroom56].add_prereq_moon(sand28);
-        moons[mushroom56].add_prereq_moon(sand29);
-        moons[mushroom56].add_prereq_moon(sand30);
-        moons[mushroom56].add_prereq_moon(lake10);
-        moons[mushroom56].add_prereq_moon(lake11);
-        moons[mushroom56].add_prereq_moon(wooded37);
-        moons[mushroom56].add_prereq_moon(wooded38);
-        moons[mushroom56].add_prereq_moon(wooded65);
-        moons[mushroom56].add_prereq_moon(lost31);
-        moons[mushroom56].add_prereq_moon(metro19);
-        moons[mushroom56].add_prereq_moon(metro20);
-        let mushroom57 = moons.len();
-        moons.push(Moon::new("Timer Challenge Professional", KingdomName::Mushroom));
-        moons[mushroom57].add_prereq_moon(mushroom56);
-        moons[mushroom57].add_prereq_moon(metro71);
-        moons[mushroom57].add_prereq_moon(snow13);
-        moons[mushroom57].add_prereq_moon(snow14);
-        moons[mushroom57].add_prereq_moon(snow47);
-        moons[mushroom57].add_prereq_moon(seaside27);
-        moons[mushroom57].add_prereq_moon(seaside28);
-        moons[mushroom57].add_prereq_moon(seaside64);
-        moons[mushroom57].add_prereq_moon(luncheon18);
-        moons[mushroom57].add_prereq_moon(luncheon19);
-        moons[mushroom57].add_prereq_moon(luncheon20);
-        moons[mushroom57].add_prereq_moon(bowser17);
-        moons[mushroom57].add_prereq_moon(bowser53);
-        moons[mushroom57].add_prereq_moon(moon7);
-        moons[mushroom57].add_prereq_moon(moon31);
-        moons[mushroom57].add_prereq_moon(mushroom9);
-        let mushroom58 = moons.len();
-        moons.push(Moon::new("Captain Toad Meeter", KingdomName::Mushroom));
-        moons[mushroom58].add_prereq_moon(mushroom57);
-        moons[mushroom58].add_prereq_moon(cap4);
-        moons[mushroom58].add_prereq_moon(cascade11);
-        moons[mushroom58].add_prereq_moon(sand37);
-        moons[mushroom58].add_prereq_moon(lake18);
-        moons[mushroom58].add_prereq_moon(wooded20);
-        let mushroom59 = moons.len();
-        moons.push(Moon::new("Captain Toad Greeter", KingdomName::Mushroom));
-        moons[mushroom59].add_prereq_moon(mushroom58);
-        moons[mushroom59].add_prereq_moon(lost20);
-        moons[mushroom59].add_prereq_moon(metro24);
-        moons[mushroom59].add_prereq_moon(snow19);
-        moons[mushroom59].add_prereq_moon(seaside34);
-        moons[mushroom59].add_prereq_moon(luncheon33);
-        let mushroom60 = moons.len();
-        moons.push(Moon::new("Touring with Princess Peach", KingdomName::Mushroom));
-        moons[mushroom60].add_prereq_moon(mushroom59);
-        moons[mushroom60].add_prereq_moon(cap16);
-        moons[mushroom60].add_prereq_moon(cascade21);
-        moons[mushroom60].add_prereq_moon(sand69);
-        moons[mushroom60].add_prereq_moon(lake33);
-        moons[mushroom60].add_prereq_moon(cloud2);
-        let mushroom61 = moons.len();
-        moons.push(Moon::new("Touring with Princess Peach", KingdomName::Mushroom));
-        moons[mushroom61].add_prereq_moon(mushroom60);
-        moons[mushroom61].add_prereq_moon(lost25);
-        moons[mushroom61].add_prereq_moon(metro66);
-        moons[mushroom61].add_prereq_moon(snow37);
-        moons[mushroom61].add_prereq_moon(seaside52);
-        moons[mushroom61].add_prereq_moon(luncheon56);
-        let mushroom62 = moons.len();
-        moons.push(Moon::new("Master Sheep Herder", KingdomName::Mushroom));
-        moons[mushroom62].add_prereq_moon(mushroom61);
-        moons[mushroom62].add_prereq_moon(sand33);
-        moons[mushroom62].add_prereq_moon(wooded73);
-        moons[mushroom62].add_prereq_moon(wooded74);
-        moons[mushroom62].add_prereq_moon(mushroom12);
-        let mushroom63 = moons.len();
-        moons.push(Moon::new("Gaga for Goombette", KingdomName::Mushroom));
-        moons[mushroom63].add_prereq_moon(mushroom62);
-        moons[mushroom63].add_prereq_moon(sand35);
-        moons[mushroom63].add_prereq_moon(sand81);
-        moons[mushroom63].add_prereq_moon(lake39);
-        moons[mushroom63].add_prereq_moon(wooded21);
-        moons[mushroom63].add_prereq_moon(seaside32);
-        moons[mushroom63].add_prereq_moon(luncheon24);
-        moons[mushroom63].add_prereq_moon(mushroom16);
-        let mushroom64 = moons.len();
-        moons.push(Moon::new("Lakitu Fishing Trip", KingdomName::Mushroom));
-        moons[mushroom64].add_prereq_moon(mushroom63);
-        moons[mushroom64].add_prereq_moon(sand34);
-        moons[mushroom64].add_prereq_moon(sand37);
-        moons[mushroom64].add_prereq_moon(lake15);
-        moons[mushroom64].add_prereq_moon(snow17);
-        moons[mushroom64].add_prereq_moon(bowser27);
-        let mushroom65 = moons.len();
-        moons.push(Moon::new("Flower-Growing Guru", KingdomName::Mushroom));
-        moons[mushroom65].add_prereq_moon(mushroom64);
-        moons[mushroom65].add_prereq_moon(sand25);
-        moons[mushroom65].add_prereq_moon(sand26);
-        moons[mushroom65].add_prereq_moon(sand27);
-        moons[mushroom65].add_prereq_moon(lake9);
-        moons[mushroom65].add_prereq_moon(metro21);
-        let mushroom66 = moons.len();
-        moons.push(Moon::new("Flower-Growing Sage", KingdomName::Mushroom));
-        moons[mushroom66].add_prereq_moon(mushroom65);
-        moons[mushroom66].add_prereq_moon(metro22);
-        moons[mushroom66].add_prereq_moon(metro23);
-        moons[mushroom66].add_prereq_moon(seaside23);
-        moons[mushroom66].add_prereq_moon(seaside24);
-        moons[mushroom66].add_prereq_moon(seaside25);
-        let mushroom67 = moons.len();
-        moons.push(Moon::new("Running with Rabbits", KingdomName::Mushroom));
-        moons[mushroom67].add_prereq_moon(mushroom66);
-        moons[mushroom67].add_prereq_moon(cap26);
-        moons[mushroom67].add_prereq_moon(cascade34);
-        moons[mushroom67].add_prereq_moon(sand77);
-        moons[mushroom67].add_prereq_moon(wooded8);
-        moons[mushroom67].add_prereq_moon(lost13);
-        let mushroom68 = moons.len();
-        moons.push(Moon::new("Racing with Rabbits", KingdomName::Mushroom));
-        moons[mushroom68].add_prereq_moon(mushroom67);
-        moons[mushroom68].add_prereq_moon(snow10);
-        moons[mushroom68].add_prereq_moon(luncheon35);
-        moons[mushroom68].add_prereq_moon(bowser15);
-        moons[mushroom68].add_prereq_moon(moon5);
-        moons[mushroom68].add_prereq_moon(mushroom3);
-        let mushroom69 = moons.len();
-        moons.push(Moon::new("Ground Pound Instructor", KingdomName::Mushroom));
-        moons[mushroom69].add_prereq_moon(mushroom68);
-        moons[mushroom69].add_prereq_moon(cap17);
-        moons[mushroom69].add_prereq_moon(cap24);
-        moons[mushroom69].add_prereq_moon(cascade26);
-        moons[mushroom69].add_prereq_moon(cascade31);
-        moons[mushroom69].add_prereq_moon(cascade32);
-        moons[mushroom69].add_prereq_moon(sand16);
-        moons[mushroom69].add_prereq_moon(sand17);
-        moons[mushroom69].add_prereq_moon(sand18);
-        moons[mushroom69].add_prereq_moon(sand23);
-        moons[mushroom69].add_prereq_moon(sand31);
-        moons[mushroom69].add_prereq_moon(sand40);
-        moons[mushroom69].add_prereq_moon(sand52);
-        moons[mushroom69].add_prereq_moon(sand67);
-        moons[mushroom69].add_prereq_moon(sand75);
-        moons[mushroom69].add_prereq_moon(lake6);
-        moons[mushroom69].add_prereq_moon(lake7);
-        moons[mushroom69].add_prereq_moon(lake27);
-        let mushroom70 = moons.len();
-        moons.push(Moon::new("Ground Pound Professor", KingdomName::Mushroom));
-        moons[mushroom70].add_prereq_moon(mushroom69);
-        moons[mushroom70].add_prereq_moon(lake37);
-        moons[mushroom70].add_prereq_moon(wooded9);
-        moons[mushroom70].add_prereq_moon(wooded31);
-        moons[mushroom70].add_prereq_moon(wooded32);
-        moons[mushroom70].add_prereq_moon(wooded34);
-        moons[mushroom70].add_prereq_moon(wooded50);
-        moons[mushroom70].add_prereq_moon(cloud3);
-        moons[mushroom70].add_prereq_moon(metro14);
-        moons[mushroom70].add_prereq_moon(metro15);
-        moons[mushroom70].add_prereq_moon(metro16);
-        moons[mushroom70].add_prereq_moon(metro53);
-        moons[mushroom70].add_prereq_moon(metro72);
-        moons[mushroom70].add_prereq_moon(metro77);
-        moons[mushroom70].add_prereq_moon(snow8);
-        moons[mushroom70].add_prereq_moon(snow9);
-        moons[mushroom70].add_prereq_moon(snow34);
-        moons[mushroom70].add_prereq_moon(snow43);
-        moons[mushroom70].add_prereq_moon(snow44);
-        moons[mushroom70].add_prereq_moon(snow45);
-        moons[mushroom70].add_prereq_moon(snow46);
-        moons[mushroom70].add_prereq_moon(seaside16);
-        moons[mushroom70].add_prereq_moon(seaside17);
-        moons[mushroom70].add_prereq_moon(seaside18);
-        moons[mushroom70].add_prereq_moon(seaside19);
-        moons[mushroom70].add_prereq_moon(seaside20);
-        moons[mushroom70].add_prereq_moon(seaside29);
-        moons[mushroom70].add_prereq_moon(seaside41);
-        moons[mushroom70].add_prereq_moon(seaside50);
-        moons[mushroom70].add_prereq_moon(seaside61);
-        moons[mushroom70].add_prereq_moon(luncheon10);
-        moons[mushroom70].add_prereq_moon(luncheon11);
-        moons[mushroom70].add_prereq_moon(luncheon12);
-        moons[mushroom70].add_prereq_moon(luncheon13);
-        moons[mushroom70].add_prereq_moon(luncheon22);
-        moons[mushroom70].add_prereq_moon(luncheon30);
-        let mushroom71 = moons.len();
-        moons.push(Moon::new("Rad Hatter", KingdomName::Mushroom));
-        moons[mushroom71].add_prereq_moon(mushroom70);
-        moons[mushroom71].add_prereq_moon(cascade30);
-        moons[mushroom71].add_prereq_moon(sand15);
-        moons[mushroom71].add_prereq_moon(sand55);
-        let mushroom72 = moons.len();
-        moons.push(Moon::new("Super Rad Hatter", KingdomName::Mushroom));
-        moons[mushroom72].add_prereq_moon(mushroom71);
-        moons[mushroom72].add_prereq_moon(seaside59);
-        moons[mushroom72].add_prereq_moon(seaside60);
-        moons[mushroom72].add_prereq_moon(ruined6);
-        moons[mushroom72].add_prereq_moon(bowser12);
-        moons[mushroom72].add_prereq_moon(bowser48);
-        moons[mushroom72].add_prereq_moon(moon3);
-        moons[mushroom72].add_prereq_moon(mushroom2);
-        let mushroom73 = moons.len();
-        moons.push(Moon::new("Traveling-Bird Herder", KingdomName::Mushroom));
-        moons[mushroom73].add_prereq_moon(mushroom72);
-        moons[mushroom73].add_prereq_moon(cascade25);
-        moons[mushroom73].add_prereq_moon(sand21);
-        moons[mushroom73].add_prereq_moon(sand22);
-        moons[mushroom73].add_prereq_moon(lake38);
-        moons[mushroom73].add_prereq_moon(wooded62);
-        moons[mushroom73].add_prereq_moon(metro54);
-        moons[mushroom73].add_prereq_moon(metro70);
-        moons[mushroom73].add_prereq_moon(seaside62);
-        let mushroom74 = moons.len();
-        moons.push(Moon::new("Wearing it Well!", KingdomName::Mushroom));
-        moons[mushroom74].add_prereq_moon(mushroom73);
-        moons[mushroom74].add_prereq_moon(cascade23);
-        moons[mushroom74].add_prereq_moon(sand53);
-        moons[mushroom74].add_prereq_moon(lake21);
-        let mushroom75 = moons.len();
-        moons.push(Moon::new("Wearing it Great!", KingdomName::Mushroom));
-        moons[mushroom75].add_prereq_moon(mushroom74);
-        moons[mushroom75].add_prereq_moon(lake29);
-        moons[mushroom75].add_prereq_moon(lake30);
-        moons[mushroom75].add_prereq_moon(lake31);
-        moons[mushroom75].add_prereq_moon(wooded36);
-        moons[mushroom75].add_prereq_moon(snow20);
-        let mushroom76 = moons.len();
-        moons.push(Moon::new("Wearing it Perfect!", KingdomName::Mushroom));
-        moons[mushroom76].add_prereq_moon(mushroom75);
-        moons[mushroom76].add_prereq_moon(seaside42);
-        moons[mushroom76].add_prereq_moon(luncheon52);
-        moons[mushroom76].add_prereq_moon(bowser31);
-        moons[mushroom76].add_prereq_moon(bowser32);
-        moons[mushroom76].add_prereq_moon(moon23);
-        moons[mushroom76].add_prereq_moon(mushroom27);
-        moons[mushroom76].add_prereq_moon(mushroom28);
-        let mushroom77 = moons.len();
-        moons.push(Moon::new("Hat-Seeking Missile", KingdomName::Mushroom));
-        moons[mushroom77].add_prereq_moon(mushroom76);
-        moons[mushroom77].add_prereq_moon(sand65);
-        moons[mushroom77].add_prereq_moon(metro62);
-        moons[mushroom77].add_prereq_moon(metro75);
-        moons[mushroom77].add_prereq_moon(snow36);
-        moons[mushroom77].add_prereq_moon(luncheon54);
-        moons[mushroom77].add_prereq_moon(mushroom42);
-        let mushroom78 = moons.len();
-        moons.push(Moon::new("Music Maestro", KingdomName::Mushroom));
-        moons[mushroom78].add_prereq_moon(mushroom77);
-        moons[mushroom78].add_prereq_moon(sand64);
-        moons[mushroom78].add_prereq_moon(wooded52);
-        moons[mushroom78].add_prereq_moon(metro58);
-        moons[mushroom78].add_prereq_moon(luncheon51);
-        moons[mushroom78].add_prereq_moon(mushroom19);
-        let mushroom79 = moons.len();
-        moons.push(Moon::new("Art Enthusiast", KingdomName::Mushroom));
-        moons[mushroom79].add_prereq_moon(mushroom78);
-        moons[mushroom79].add_prereq_moon(cap17);
-        moons[mushroom79].add_prereq_moon(sand63);
-        moons[mushroom79].add_prereq_moon(lake27);
-        moons[mushroom79].add_prereq_moon(metro53);
-        moons[mushroom79].add_prereq_moon(snow34);
-        let mushroom80 = moons.len();
-        moons.push(Moon::new("Art Investigator", KingdomName::Mushroom));
-        moons[mushroom80].add_prereq_moon(mushroom79);
-        moons[mushroom80].add_prereq_moon(seaside50);
-        moons[mushroom80].add_prereq_moon(luncheon49);
-        moons[mushroom80].add_prereq_moon(bowser45);
-        moons[mushroom80].add_prereq_moon(moon27);
-        moons[mushroom80].add_prereq_moon(mushroom41);
-        let mushroom81 = moons.len();
-        moons.push(Moon::new("Slots Machine", KingdomName::Mushroom));
-        moons[mushroom81].add_prereq_moon(mushroom80);
-        moons[mushroom81].add_prereq_moon(sand44);
-        moons[mushroom81].add_prereq_moon(metro28);
-        moons[mushroom81].add_prereq_moon(luncheon26);
-        let mushroom82 = moons.len();
-        moons.push(Moon::new("Koopa Freerunning MVP", KingdomName::Mushroom));
-        moons[mushroom82].add_prereq_moon(mushroom81);
-        moons[mushroom82].add_prereq_moon(cap15);
-        moons[mushroom82].add_prereq_moon(cascade22);
-        moons[mushroom82].add_prereq_moon(sand66);
-        moons[mushroom82].add_prereq_moon(lake32);
-        moons[mushroom82].add_prereq_moon(wooded53);
-        moons[mushroom82].add_prereq_moon(lost24);
-        moons[mushroom82].add_prereq_moon(metro61);
-        moons[mushroom82].add_prereq_moon(snow35);
-        moons[mushroom82].add_prereq_moon(seaside51);
-        moons[mushroom82].add_prereq_moon(bowser41);
-        moons[mushroom82].add_prereq_moon(moon22);
-        let mushroom83 = moons.len();
-        moons.push(Moon::new("Koopa Freerunning Hall of Famer", KingdomName::Mushroom));
-        moons[mushroom83].add_prereq_moon(mushroom82);
-        moons[mushroom83].add_prereq_moon(cap29);
-        moons[mushroom83].add_prereq_moon(cascade36);
-        moons[mushroom83].add_prereq_moon(sand83);
-        moons[mushroom83].add_prereq_moon(lake40);
-        moons[mushroom83].add_prereq_moon(wooded69);
-        moons[mushroom83].add_prereq_moon(lost30);
-        moons[mushroom83].add_prereq_moon(metro74);
-        moons[mushroom83].add_prereq_moon(snow51);
-        moons[mushroom83].add_prereq_moon(seaside67);
-        moons[mushroom83].add_prereq_moon(bowser58);
-        moons[mushroom83].add_prereq_moon(moon33);
-        moons[mushroom83].add_prereq_moon(mushroom21);
-        moons[mushroom83].add_prereq_moon(mushroom22);
-        let mushroom84 = moons.len();
-        moons.push(Moon::new("Supernaturally Sure-Footed", KingdomName::Mushroom));
-        moons[mushroom84].add_prereq_moon(mushroom83);
-        moons[mushroom84].add_prereq_moon(sand45);
-        moons[mushroom84].add_prereq_moon(sand82);
-        moons[mushroom84].add_prereq_moon(snow22);
-        moons[mushroom84].add_prereq_moon(snow50);
-        moons[mushroom84].add_prereq_moon(moon21);
-        moons[mushroom84].add_prereq_moon(moon32);
-        let mushroom85 = moons.len();
-        moons.push(Moon::new("Quizmaster", KingdomName::Mushroom));
-        moons[mushroom85].add_prereq_moon(mushroom84);
-        moons[mushroom85].add_prereq_moon(sand41);
-        moons[mushroom85].add_prereq_moon(seaside35);
-        moons[mushroom85].add_prereq_moon(moon18);
-        let mushroom86 = moons.len();
-        moons.push(Moon::new("Souvenir Sampler", KingdomName::Mushroom));
-        moons[mushroom86].add_prereq_moon(mushroom85);
-        let mushroom87 = moons.len();
-        moons.push(Moon::new("Souvenir Sleuth", KingdomName::Mushroom));
-        moons[mushroom87].add_prereq_moon(mushroom86);
-        let mushroom88 = moons.len();
-        moons.push(Moon::new("Souvenir Savant", KingdomName::Mushroom));
-        moons[mushroom88].add_prereq_moon(mushroom87);
-        let mushroom89 = moons.len();
-        moons.push(Moon::new("Capturing Novice", KingdomName::Mushroom));
-        moons[mushroom89].add_prereq_moon(mushroom88);
-        let mushroom90 = moons.len();
-        moons.push(Moon::new("Capturing Apprentice", KingdomName::Mushroom));
-        moons[mushroom90].add_prereq_moon(mushroom89);
-        let mushroom91 = moons.len();
-        moons.push(Moon::new("Capturing Master", KingdomName::Mushroom));
-        moons[mushroom91].add_prereq_moon(mushroom90);
-        let mushroom92 = moons.len();
-        moons.push(Moon::new("Hat Maven", KingdomName::Mushroom));
-        moons[mushroom92].add_prereq_moon(mushroom91);
-        let mushroom93 = moons.len();
-        moons.push(Moon::new("Hat Icon", KingdomName::Mushroom));
-        moons[mushroom93].add_prereq_moon(mushroom92);
-        let mushroom94 = moons.len();
-        moons.push(Moon::new("Fashion Maven", KingdomName::Mushroom));
-        moons[mushroom94].add_prereq_moon(mushroom93);
-        let mushroom95 = moons.len();
-        moons.push(Moon::new("Fashion Icon", KingdomName::Mushroom));
-        moons[mushroom95].add_prereq_moon(mushroom94);
-        let mushroom96 = moons.len();
-        moons.push(Moon::new("Moon Rock Liberator", KingdomName::Mushroom));
-        moons[mushroom96].add_prereq_moon(mushroom95);
-        let mushroom97 = moons.len();
-        moons.push(Moon::new("World Warper", KingdomName::Mushroom));
-        moons[mushroom97].add_prereq_moon(mushroom96);
-        moons[mushroom97].add_prereq_moon(cascade18);
-        moons[mushroom97].add_prereq_moon(sand62);
-        moons[mushroom97].add_prereq_moon(lake26);
-        moons[mushroom97].add_prereq_moon(wooded49);
-        moons[mushroom97].add_prereq_moon(metro51);
-        moons[mushroom97].add_prereq_moon(snow33);
-        moons[mushroom97].add_prereq_moon(seaside49);
-        moons[mushroom97].add_prereq_moon(luncheon47);
-        moons[mushroom97].add_prereq_moon(bowser43);
-        moons[mushroom97].add_prereq_moon(mushroom39);
-        let mushroom98 = moons.len();
-        moons.push(Moon::new("Checkpoint Flagger", KingdomName::Mushroom));
-        moons[mushroom98].add_prereq_moon(mushroom97);
-        let mushroom99 = moons.len();
-        moons.push(Moon::new("Checkpoint Flag Enthusiast", KingdomName::Mushroom));
-        moons[mushroom99].add_prereq_moon(mushroom98);
-        let mushroom100 = moons.len();
-        moons.push(Moon::new("Loaded with Coins", KingdomName::Mushroom));
-        moons[mushroom100].add_prereq_moon(mushroom99);
-        let mushroom101 = moons.len();
-        moons.push(Moon::new("Rolling in Coins", KingdomName::Mushroom));
-        moons[mushroom101].add_prereq_moon(mushroom100);
-        let mushroom102 = moons.len();
-        moons.push(Moon::new("Swimming in Coins", KingdomName::Mushroom));
-        moons[mushroom102].add_prereq_moon(mushroom101);
-        let mushroom103 = moons.len();
-        moons.push(Moon::new("Jump! Jump! Jump!", KingdomName::Mushroom));
-        moons[mushroom103].add_prereq_moon(mushroom102);
-        let mushroom104 = moons.len();
-        moons.push(Moon::new("Fly, Cappy, Fly!", KingdomName::Mushroom));
-        moons[mushroom104].add_prereq_moon(mushroom103);
-        let dark17 = moons.len();
-        moons.push(Moon::new("Found with Dark Side Art 3", KingdomName::Mushroom));
-        moons[dark17].add_prereq_kingdom(KingdomName::Dark);
-        offset.push((mushroom1, moons.len()));
+impl MoonPool {
+    pub fn new(moons: &Moons) -> Self {
+        let mut pool = MoonPool {
+            unmet: moons.unmet.clone(),
+            reached: moons.reached.clone(),
+            excluded: vec![false; moons.moons.len()],
+            ready: vec![Vec::new(); moons.offset.len()],
+            remaining: moons.offset.iter().map(|(s, e)| e - s).collect(),
+        };
+        for (id, moon) in moons.iter() {
+            if pool.unmet[id] == 0 {
+                pool.ready[moon.kingdom as usize].push(id);
+            }
+        }
+        pool
+    }
 
-        // dark side
-        let dark1 = moons.len();
-        moons.push(Moon::new_multi("Arrival at Rabbit Ridge!", KingdomName::Dark));
-        kingdoms.kingdom_mut(KingdomName::Dark).set_exit_moon(dark1);
-        let dark2 = moons.len();
-        moons.push(Moon::new("Captain Toad on the Dark Side!", KingdomName::Dark));
-        moons[dark1].add_prereq_moon(dark2);
-        let dark3 = moons.len();
-        moons.push(Moon::new("Breakdown Road: Hurry!", KingdomName::Dark));
-        moons[dark3].add_prereq_moon(dark1);
-        let dark4 = moons.len();
-        moons.push(Moon::new("Breakdown Road: Final Challenge!", KingdomName::Dark));
-        moons[dark4].add_prereq_moon(dark1);
-        let dark5 = moons.len();
-        moons.push(Moon::new("Invisible Road: Rush!", KingdomName::Dark));
-        moons[dark5].add_prereq_moon(dark1);
-        let dark6 = moons.len();
-        moons.push(Moon::new("Invisible Road: Secret!", KingdomName::Dark));
-        moons[dark6].add_prereq_moon(dark1);
-        let dark7 = moons.len();
-        moons.push(Moon::new("Vanishing Road Rush", KingdomName::Dark));
-        moons[dark7].add_prereq_moon(dark1);
-        let dark8 = moons.len();
-        moons.push(Moon::new("Vanishing Road Challenge", KingdomName::Dark));
-        moons[dark8].add_prereq_moon(dark1);
-        let dark9 = moons.len();
-        moons.push(Moon::new("Yoshi Under Siege", KingdomName::Dark));
-        moons[dark9].add_prereq_moon(dark1);
-        let dark10 = moons.len();
-        moons.push(Moon::new("Fruit Feast Under Siege", KingdomName::Dark));
-        moons[dark10].add_prereq_moon(dark1);
-        let dark11 = moons.len();
-        moons.push(Moon::new("Yoshi on the Sinking Island", KingdomName::Dark));
-        moons[dark11].add_prereq_moon(dark1);
-        let dark12 = moons.len();
-        moons.push(Moon::new("Fruit Feast on the Sinking Island", KingdomName::Dark));
-        moons[dark12].add_prereq_moon(dark1);
-        let dark13 = moons.len();
-        moons.push(Moon::new("Yoshi's Magma Swamp", KingdomName::Dark));
-        moons[dark13].add_prereq_moon(dark1);
-        let dark14 = moons.len();
-        moons.push(Moon::new("Fruit Feast in the Magma Swamp!", KingdomName::Dark));
-        moons[dark14].add_prereq_moon(dark1);
-        moons[dark15].add_prereq_moon(dark1);
-        moons[dark16].add_prereq_moon(dark1);
-        moons[dark17].add_prereq_moon(dark1);
-        moons[dark18].add_prereq_moon(dark1);
-        moons[dark19].add_prereq_moon(dark1);
-        moons[dark20].add_prereq_moon(dark1);
-        moons[dark21].add_prereq_moon(dark1);
-        moons[dark22].add_prereq_moon(dark1);
-        moons[dark23].add_prereq_moon(dark1);
-        moons[dark24].add_prereq_moon(dark1);
-        moons[mushroom80].add_prereq_moon(dark15);
-        moons[mushroom80].add_prereq_moon(dark16);
-        moons[mushroom80].add_prereq_moon(dark17);
-        moons[mushroom80].add_prereq_moon(dark18);
-        moons[mushroom80].add_prereq_moon(dark19);
-        moons[mushroom80].add_prereq_moon(dark20);
-        moons[mushroom80].add_prereq_moon(dark21);
-        moons[mushroom80].add_prereq_moon(dark22);
-        moons[mushroom80].add_prereq_moon(dark23);
-        moons[mushroom80].add_prereq_moon(dark24);
-        offset.push((dark1, moons.len()));
+    pub fn remaining(&self) -> usize {
+        self.remaining.iter().sum()
+    }
+
+    pub fn cap_difficulty(&mut self, kingdoms: &Kingdoms, moons: &Moons, max: u8) {
+        // moons over the cap are left out along with every moon that can't
+        // be collected without them, but the story always stays in
+        let removed: Vec<bool> = moons.iter()
+            .map(|(id, moon)| moon.difficulty > max && !moons.story(id))
+            .collect();
+        let possible = moons.possible(kingdoms, &removed);
+        for (id, moon) in moons.iter() {
+            if possible[id] || moons.story(id) {
+                continue;
+            }
+            let kingdom = moon.kingdom() as usize;
+            self.excluded[id] = true;
+            self.ready[kingdom].retain(|m| *m != id);
+            self.remaining[kingdom] -= 1;
+        }
+    }
+
+    pub fn kingdom_visited(&mut self, moons: &Moons, state: &State) {
+        for e in &moons.visits[state.current_kingdom() as usize] {
+            self.trigger(moons, state, *e);
+        }
+    }
 
-        // darker side
-        let darker1 = moons.len();
-        moons.push(Moon::new_multi("Long Journey's End", KingdomName::Darker));
-        offset.push((darker1, moons.len()));
+    pub fn moon_scheduled(&mut self, moons: &Moons, state: &State, id: MoonID) {
+        for m in &moons.waiting[id] {
+            self.satisfy(moons, *m);
+        }
+        for e in &moons.triggers[id] {
+            self.trigger(moons, state, *e);
+        }
+    }
 
-        // setup the ids - initially monotonic
-        let mut ids = Vec::new();
-        for x in 0..moons.len() {
-            ids.push(x);
+    fn trigger(&mut self, moons: &Moons, state: &State, event: usize) {
+        // the watchers are sorted, so stop at the first one still unmet
+        let watchers = &moons.watchers[event];
+        while let Some((id, r)) = watchers.get(self.reached[event]) {
+            if !moons.moons[*id].prerequisites[*r].satisfied(state) {
+                break;
+            }
+            self.reached[event] += 1;
+            self.satisfy(moons, *id);
         }
+    }
 
-        Moons {
-            moons: moons,
-            offset: offset,
-            ids: ids,
+    fn satisfy(&mut self, moons: &Moons, id: MoonID) {
+        self.unmet[id] -= 1;
+        if self.unmet[id] == 0 && !self.excluded[id] {
+            self.ready[moons.moons[id].kingdom() as usize].push(id);
         }
     }
 
+    pub fn return_available(&mut self, moons: &Moons,
+                            state: &mut State) -> Result<Vec<MoonID>, RandomizerError> {
+        let kingdom = state.current_kingdom();
+        if self.remaining[kingdom as usize] == 0 {
+            // no moons left in kingdom
+            state.complete_kingdom(kingdom);
+            return Ok(Vec::new());
+        }
+
+        // only the ready moons can be available, check the rest of what they
+        // need such as the coins and the kingdom's phase
+        let mut ret = Vec::new();
+        let mut waiting = Vec::new();
+        for id in self.ready[kingdom as usize].drain(..) {
+            let moon = moons.moon(id)?;
+            if moon.available(state) {
+                // worked out before buying, which changes the state, and
+                // moons without an outfit door collect nothing to allocate
+                let outfits: Vec<Outfit> = moon.outfits(state).collect();
+                for o in outfits {
+                    state.buy_outfit(o);
+                }
+                ret.push(id);
+            } else {
+                waiting.push(id);
+            }
+        }
+        self.ready[kingdom as usize] = waiting;
+        self.remaining[kingdom as usize] -= ret.len();
+        Ok(ret)
+    }
 }
@@ -0,0 +1,63 @@
+use crate::kingdom::KingdomName;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Outfit {
+    Caveman,
+    SombreroPoncho,
+    Swimwear,
+    Explorer,
+    Resort,
+    Samurai,
+}
+
+impl Outfit {
+    pub const ALL: [Outfit; 6] = [
+        Outfit::Caveman,
+        Outfit::SombreroPoncho,
+        Outfit::Swimwear,
+        Outfit::Explorer,
+        Outfit::Resort,
+        Outfit::Samurai,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Outfit> {
+        Outfit::ALL.iter()
+            .find(|o| format!("{:?}", o).eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Outfit::Caveman => "Caveman Headwear & Outfit",
+            Outfit::SombreroPoncho => "Sombrero & Poncho",
+            Outfit::Swimwear => "Swimwear",
+            Outfit::Explorer => "Explorer Hat & Outfit",
+            Outfit::Resort => "Resort Hat & Outfit",
+            Outfit::Samurai => "Samurai Helmet & Armor",
+        }
+    }
+
+    // the kingdom whose regional coins buy this outfit
+    pub fn kingdom(&self) -> KingdomName {
+        match self {
+            Outfit::Caveman => KingdomName::Cascade,
+            Outfit::SombreroPoncho => KingdomName::Sand,
+            Outfit::Swimwear => KingdomName::Lake,
+            Outfit::Explorer => KingdomName::Wooded,
+            Outfit::Resort => KingdomName::Seaside,
+            Outfit::Samurai => KingdomName::Bowser,
+        }
+    }
+
+    // rough regional coin cost for the full outfit
+    pub fn cost(&self) -> u16 {
+        match self {
+            Outfit::Caveman => 40,
+            Outfit::SombreroPoncho => 80,
+            Outfit::Swimwear => 35,
+            Outfit::Explorer => 80,
+            Outfit::Resort => 80,
+            Outfit::Samurai => 80,
+        }
+    }
+}
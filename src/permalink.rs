@@ -0,0 +1,199 @@
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+
+use crate::error::RandomizerError;
+use crate::kingdom::Kingdoms;
+use crate::moon::Moons;
+use crate::score::Metric;
+
+// bumped whenever the layout of a permalink changes, or the routes a seed
+// gives do, such as when rand is upgraded past the version pinned in
+// Cargo.toml and StdRng starts giving different numbers
+const VERSION: u8 = 2;
+// url safe, so a permalink can be pasted anywhere
+const ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+// version, data hash, seed, curve, max difficulty, best of and metric
+const LENGTH: usize = 1 + 4 + 8 + 8 + 1 + 4 + 1;
+
+// everything that decides which route gets generated, so a race can share
+// it and everyone gets the same route
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    seed: u64,
+    curve: f64,
+    max_difficulty: Option<u8>,
+    best_of: u32,
+    metric: Metric,
+}
+
+impl Settings {
+    pub fn new(seed: u64, curve: f64, max_difficulty: Option<u8>, best_of: u32,
+               metric: Metric) -> Self {
+        Settings {
+            seed,
+            curve,
+            max_difficulty,
+            best_of,
+            metric,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn curve(&self) -> f64 {
+        self.curve
+    }
+
+    pub fn max_difficulty(&self) -> Option<u8> {
+        self.max_difficulty
+    }
+
+    pub fn best_of(&self) -> u32 {
+        self.best_of
+    }
+
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    pub fn permalink(&self, kingdoms: &Kingdoms, moons: &Moons) -> String {
+        let mut bytes = Vec::with_capacity(LENGTH);
+        bytes.push(VERSION);
+        bytes.extend(&data_hash(kingdoms, moons).to_le_bytes());
+        bytes.extend(&self.seed.to_le_bytes());
+        bytes.extend(&self.curve.to_bits().to_le_bytes());
+        bytes.push(self.max_difficulty.unwrap_or(0));
+        bytes.extend(&self.best_of.to_le_bytes());
+        let metric = Metric::ALL.iter().position(|m| *m == self.metric).unwrap();
+        bytes.push(metric as u8);
+        encode(&bytes)
+    }
+
+    pub fn from_permalink(permalink: &str, kingdoms: &Kingdoms,
+                          moons: &Moons) -> Result<Self, RandomizerError> {
+        let bad = |message: &str| RandomizerError::BadPermalink(String::from(message));
+        let bytes = decode(permalink.trim()).ok_or_else(|| bad("not a permalink"))?;
+        if bytes.len() != LENGTH {
+            return Err(bad("not a permalink"));
+        }
+        if bytes[0] != VERSION {
+            return Err(bad("made by a different version of the randomizer"));
+        }
+        let data = u32::from_le_bytes(<[u8; 4]>::try_from(&bytes[1..5]).unwrap());
+        if data != data_hash(kingdoms, moons) {
+            return Err(bad("made with different moon data"));
+        }
+        let seed = u64::from_le_bytes(<[u8; 8]>::try_from(&bytes[5..13]).unwrap());
+        let curve = f64::from_bits(
+            u64::from_le_bytes(<[u8; 8]>::try_from(&bytes[13..21]).unwrap()));
+        if curve.is_nan() || curve < 0.0 {
+            return Err(bad("invalid curve"));
+        }
+        let max_difficulty = match bytes[21] {
+            0 => None,
+            d @ 1..=5 => Some(d),
+            _ => return Err(bad("invalid difficulty")),
+        };
+        let best_of = u32::from_le_bytes(<[u8; 4]>::try_from(&bytes[22..26]).unwrap());
+        if best_of == 0 {
+            return Err(bad("invalid number of routes"));
+        }
+        let metric = *Metric::ALL.get(bytes[26] as usize)
+            .ok_or_else(|| bad("invalid metric"))?;
+        Ok(Settings::new(seed, curve, max_difficulty, best_of, metric))
+    }
+}
+
+// a hash of the kingdoms and moons, so routes from custom data can't be
+// mistaken for routes from the data they were changed from
+pub fn data_hash(kingdoms: &Kingdoms, moons: &Moons) -> u32 {
+    let mut hasher = Fnv::new();
+    for (_, kingdom) in kingdoms.iter() {
+        kingdom.hash(&mut hasher);
+    }
+    for (_, moon) in moons.iter() {
+        moon.hash(&mut hasher);
+    }
+    let hash = hasher.finish();
+    (hash ^ (hash >> 32)) as u32
+}
+
+// FNV-1a, with every number written little endian so the hash is the same on
+// every platform, which the standard library's hasher doesn't promise, also
+// used for route fingerprints
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+    pub(crate) fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+}
+
+fn encode(bytes: &[u8]) -> String {
+    // base64 without padding, six bits to a character
+    let mut s = String::new();
+    for chunk in bytes.chunks(3) {
+        let mut n = 0u32;
+        for (x, b) in chunk.iter().enumerate() {
+            n |= u32::from(*b) << (16 - 8 * x);
+        }
+        for x in 0..=chunk.len() {
+            s.push(ALPHABET[(n >> (18 - 6 * x) & 0x3f) as usize] as char);
+        }
+    }
+    s
+}
+
+fn decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for chunk in s.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut n = 0u32;
+        for (x, c) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|a| a == c)?;
+            n |= (value as u32) << (18 - 6 * x);
+        }
+        for x in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * x)) as u8);
+        }
+    }
+    Some(bytes)
+}
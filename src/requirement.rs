@@ -0,0 +1,247 @@
+use std::borrow::Cow;
+use std::slice;
+
+use crate::capture::Capture;
+use crate::data::{Data, RequirementData};
+use crate::error::RandomizerError;
+use crate::kingdom::KingdomName;
+use crate::moon::MoonID;
+use crate::outfit::Outfit;
+use crate::state::State;
+use crate::tag::Tag;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Flag {
+    CompletedMainGame,
+    MoonRockBroken(KingdomName),
+}
+
+// something happening in a route that can make a requirement satisfied
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Event {
+    Visit(KingdomName),
+    PostGameVisit(KingdomName),
+    MoonCount,
+    Phase(KingdomName),
+    MoonRock(KingdomName),
+    Capture(Capture),
+    Tag(Tag),
+}
+
+#[derive(Clone, Debug, Hash, PartialEq)]
+pub enum Requirement {
+    Moon(MoonID),
+    Kingdom(KingdomName, u8),
+    MoonCount(u16),
+    Flag(Flag),
+    // kingdom has reached at least this phase
+    PhaseAtLeast(KingdomName, u8),
+    // kingdom is exactly in this phase, before the story moves it on
+    PhaseOnly(KingdomName, u8),
+    Capture(Capture),
+    Outfit(Outfit),
+    // the hint art in a kingdom has been seen after the main game
+    HintArt(KingdomName),
+    // at least this many moons with the tag have been collected
+    Tagged(Tag, u16),
+    All(Cow<'static, [Requirement]>),
+    Any(Cow<'static, [Requirement]>),
+}
+
+impl Requirement {
+    pub fn satisfied(&self, state: &State) -> bool {
+        match self {
+            Requirement::Moon(m) => state.moon_scheduled(*m),
+            Requirement::Kingdom(k, c) => state.kingdom_scheduled(*k, *c),
+            Requirement::MoonCount(c) => state.total_moons() >= *c,
+            Requirement::Flag(f) => state.flag(*f),
+            Requirement::PhaseAtLeast(k, p) => state.kingdom_phase(*k) >= *p,
+            Requirement::PhaseOnly(k, p) => state.kingdom_phase(*k) == *p,
+            Requirement::Capture(c) => state.capture_learned(*c),
+            Requirement::Outfit(o) => state.can_wear(*o),
+            Requirement::HintArt(k) => state.kingdom_visited_post_game(*k),
+            Requirement::Tagged(t, c) => state.tag_count(*t) >= *c,
+            Requirement::All(r) => r.iter().all(|r| r.satisfied(state)),
+            Requirement::Any(r) => r.iter().any(|r| r.satisfied(state)),
+        }
+    }
+
+    pub fn load(r: &RequirementData, data: &Data,
+                line: usize) -> Result<Self, RandomizerError> {
+        let kingdom = |k: &str| {
+            KingdomName::from_name(k).map_err(|e| data.error(line, e.to_string()))
+        };
+        let r = match r {
+            RequirementData::Moon(m) => Requirement::Moon(*m),
+            RequirementData::Kingdom(k, c) => Requirement::Kingdom(kingdom(k)?, *c),
+            RequirementData::MoonCount(c) => Requirement::MoonCount(*c),
+            RequirementData::CompletedMainGame => {
+                Requirement::Flag(Flag::CompletedMainGame)
+            }
+            RequirementData::MoonRockBroken(k) => {
+                Requirement::Flag(Flag::MoonRockBroken(kingdom(k)?))
+            }
+            RequirementData::PhaseAtLeast(k, p) => Requirement::PhaseAtLeast(kingdom(k)?, *p),
+            RequirementData::PhaseOnly(k, p) => Requirement::PhaseOnly(kingdom(k)?, *p),
+            RequirementData::Capture(c) => Requirement::Capture(
+                Capture::from_name(c)
+                    .ok_or_else(|| data.error(line, format!("unknown capture {}", c)))?),
+            RequirementData::Outfit(o) => Requirement::Outfit(
+                Outfit::from_name(o)
+                    .ok_or_else(|| data.error(line, format!("unknown outfit {}", o)))?),
+            RequirementData::HintArt(k) => Requirement::HintArt(kingdom(k)?),
+            RequirementData::Tagged(t, c) => Requirement::Tagged(
+                Tag::from_name(t)
+                    .ok_or_else(|| data.error(line, format!("unknown tag {}", t)))?, *c),
+            RequirementData::All(r) => {
+                let r: Result<Vec<_>, _> = r.iter()
+                    .map(|r| Requirement::load(r, data, line))
+                    .collect();
+                Requirement::All(Cow::Owned(r?))
+            }
+            RequirementData::Any(r) => {
+                let r: Result<Vec<_>, _> = r.iter()
+                    .map(|r| Requirement::load(r, data, line))
+                    .collect();
+                Requirement::Any(Cow::Owned(r?))
+            }
+        };
+        Ok(r)
+    }
+
+    pub fn event(&self) -> Option<(Event, u16)> {
+        // the event to recheck this requirement on and the level the event
+        // has to reach, for requirements that stay satisfied once they are,
+        // moons are tracked separately and the rest are checked every time
+        match self {
+            Requirement::Kingdom(k, c) => Some((Event::Visit(*k), u16::from(*c))),
+            Requirement::HintArt(k) => Some((Event::PostGameVisit(*k), 0)),
+            Requirement::MoonCount(c) => Some((Event::MoonCount, *c)),
+            Requirement::Flag(Flag::CompletedMainGame) => {
+                Some((Event::Visit(KingdomName::Mushroom), 1))
+            }
+            Requirement::Flag(Flag::MoonRockBroken(k)) => {
+                Some((Event::MoonRock(*k), 0))
+            }
+            Requirement::PhaseAtLeast(k, p) => Some((Event::Phase(*k), u16::from(*p))),
+            Requirement::Capture(c) => Some((Event::Capture(*c), 0)),
+            Requirement::Tagged(t, c) => Some((Event::Tag(*t), *c)),
+            _ => None,
+        }
+    }
+
+    pub fn moons(&self) -> impl Iterator<Item = MoonID> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::Moon(m) => Some(*m),
+            _ => None,
+        })
+    }
+
+    pub fn moon_counts(&self) -> impl Iterator<Item = u16> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::MoonCount(c) => Some(*c),
+            _ => None,
+        })
+    }
+
+    pub fn kingdoms(&self) -> impl Iterator<Item = (KingdomName, u8)> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::Kingdom(k, c) => Some((*k, *c)),
+            Requirement::HintArt(k) => Some((*k, 1)),
+            _ => None,
+        })
+    }
+
+    pub fn phases(&self) -> impl Iterator<Item = (KingdomName, u8)> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::PhaseAtLeast(k, p) | Requirement::PhaseOnly(k, p) => Some((*k, *p)),
+            _ => None,
+        })
+    }
+
+    pub fn captures(&self) -> impl Iterator<Item = Capture> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::Capture(c) => Some(*c),
+            _ => None,
+        })
+    }
+
+    pub fn outfits<'a>(&'a self, state: &'a State) -> impl Iterator<Item = Outfit> + 'a {
+        // the outfits worn to meet this, going through the first branch of
+        // an any that is satisfied rather than every branch
+        Hard::met(slice::from_ref(self), state).filter_map(|r| match r {
+            Requirement::Outfit(o) => Some(*o),
+            _ => None,
+        })
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = (Tag, u16)> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::Tagged(t, c) => Some((*t, *c)),
+            _ => None,
+        })
+    }
+
+    pub fn flags(&self) -> impl Iterator<Item = Flag> + '_ {
+        Hard::new(slice::from_ref(self)).filter_map(|r| match r {
+            Requirement::Flag(f) => Some(*f),
+            _ => None,
+        })
+    }
+}
+
+// the requirements in a list that always have to be met, going into every
+// all but not the branches of an any, where meeting one of them is enough
+pub struct Hard<'a> {
+    current: slice::Iter<'a, Requirement>,
+    // the rest of each list an all was found in, which only allocates for
+    // data that puts an all in its requirements
+    outer: Vec<slice::Iter<'a, Requirement>>,
+    // with a state, the first satisfied branch of an any is gone into too
+    state: Option<&'a State>,
+}
+
+impl<'a> Hard<'a> {
+    pub fn new(requirements: &'a [Requirement]) -> Self {
+        Hard {
+            current: requirements.iter(),
+            outer: Vec::new(),
+            state: None,
+        }
+    }
+
+    // the requirements a state meets the list with
+    pub fn met(requirements: &'a [Requirement], state: &'a State) -> Self {
+        Hard {
+            state: Some(state),
+            ..Hard::new(requirements)
+        }
+    }
+}
+
+impl<'a> Iterator for Hard<'a> {
+    type Item = &'a Requirement;
+
+    fn next(&mut self) -> Option<&'a Requirement> {
+        loop {
+            let r = match self.current.next() {
+                Some(r) => r,
+                None => {
+                    self.current = self.outer.pop()?;
+                    continue;
+                }
+            };
+            let inner = match (r, self.state) {
+                (Requirement::All(rr), _) => Some(rr.iter()),
+                (Requirement::Any(rr), Some(state)) => rr.iter()
+                    .find(|r| r.satisfied(state))
+                    .map(|r| slice::from_ref(r).iter()),
+                _ => None,
+            };
+            if let Some(inner) = inner {
+                self.outer.push(std::mem::replace(&mut self.current, inner));
+            }
+            return Some(r);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use crate::error::RandomizerError;
+use crate::estimate::{Estimates, Subtotal};
+use crate::kingdom::KingdomName;
+use crate::moon::{MoonID, Moons};
+use crate::state::State;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Step {
+    Visit,
+    Moon,
+}
+
+impl Step {
+    pub fn from_name(name: &str) -> Option<Step> {
+        match name.to_ascii_lowercase().as_str() {
+            "visit" => Some(Step::Visit),
+            "moon" => Some(Step::Moon),
+            _ => None,
+        }
+    }
+}
+
+// steps through a route without giving away what comes after, a visit or a
+// moon at a time, keeping a log of every look ahead
+pub struct Reveal {
+    step: Step,
+    // the moons of each visit in route order, as the route prints them
+    visits: Vec<(KingdomName, Vec<MoonID>)>,
+    // what each of those moons counts for
+    counts: Vec<Vec<u16>>,
+    // the time of each visit and of the route up to the end of it
+    subtotals: Vec<String>,
+    finish: u32,
+    visit: usize,
+    // how many moons of the current visit have been shown
+    moon: usize,
+    collected: u16,
+    peeks: Vec<String>,
+}
+
+impl Reveal {
+    pub fn new(moons: &Moons, state: &State, estimates: &Estimates,
+               step: Step) -> Result<Self, RandomizerError> {
+        let mut visits: Vec<(KingdomName, Vec<MoonID>)> = Vec::new();
+        let mut counts: Vec<Vec<u16>> = Vec::new();
+        let mut subtotals = Vec::new();
+        let mut subtotal = Subtotal::new(estimates);
+        for (kingdom, ids) in state.visits(moons)? {
+            subtotal.arrive();
+            let mut c = Vec::new();
+            for m in ids {
+                c.push(moons.moon(*m)?.count());
+                subtotal.collect(*m)?;
+            }
+            visits.push((kingdom, ids.to_vec()));
+            counts.push(c);
+            subtotals.push(subtotal.to_string());
+        }
+        Ok(Reveal {
+            step,
+            visits,
+            counts,
+            subtotals,
+            finish: subtotal.elapsed(),
+            visit: 0,
+            moon: 0,
+            collected: 0,
+            peeks: Vec::new(),
+        })
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    pub fn finished(&self) -> bool {
+        self.visit == self.visits.len()
+    }
+
+    // the number of the visit being shown, from 1
+    pub fn visit_number(&self) -> usize {
+        self.visit + 1
+    }
+
+    pub fn collected(&self) -> u16 {
+        self.collected
+    }
+
+    // the times to show under a visit, by its number from 1
+    pub fn subtotal(&self, visit_number: usize) -> Option<&str> {
+        self.subtotals.get(visit_number.checked_sub(1)?).map(|s| s.as_str())
+    }
+
+    pub fn finish(&self) -> u32 {
+        self.finish
+    }
+
+    // the rest of the current visit, moving on to the visit after it
+    pub fn next_visit(&mut self) -> Option<(KingdomName, &[MoonID])> {
+        let (kingdom, ids) = self.visits.get(self.visit)?;
+        let ids = &ids[self.moon..];
+        self.collected += self.counts[self.visit][self.moon..].iter().sum::<u16>();
+        self.visit += 1;
+        self.moon = 0;
+        Some((*kingdom, ids))
+    }
+
+    // the next moon, and whether it starts a new visit
+    pub fn next_moon(&mut self) -> Option<(MoonID, bool)> {
+        let (_, ids) = self.visits.get(self.visit)?;
+        let id = ids[self.moon];
+        let arrived = self.moon == 0;
+        self.collected += self.counts[self.visit][self.moon];
+        self.moon += 1;
+        if self.moon == ids.len() {
+            self.visit += 1;
+            self.moon = 0;
+        }
+        Some((id, arrived))
+    }
+
+    // one step further than the next one, without moving on, so a visit at
+    // a time peeks at the visit after next and a moon at a time peeks at the
+    // rest of the visit, which is logged for the spoiler log
+    pub fn peek(&mut self) -> Option<(KingdomName, &[MoonID])> {
+        let (next, start) = match self.step {
+            Step::Visit => (self.visit + 1, 0),
+            Step::Moon => (self.visit, self.moon),
+        };
+        let (kingdom, ids) = self.visits.get(next)?;
+        let ids = &ids[start..];
+        self.peeks.push(format!("peeked at {} moons of visit {} after collecting {}",
+                                ids.len(), next + 1, self.collected));
+        Some((*kingdom, ids))
+    }
+
+    pub fn peeks(&self) -> &[String] {
+        &self.peeks
+    }
+}
@@ -0,0 +1,92 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::error::RandomizerError;
+use crate::kingdom::{Kingdoms, KingdomName};
+use crate::moon::{MoonPool, Moons};
+use crate::state::State;
+
+pub fn generate<R: Rng + ?Sized>(kingdoms: &Kingdoms, moons: &Moons,
+                                 mut pool: MoonPool, mut state: State,
+                                 rng: &mut R) -> Result<State, RandomizerError> {
+    let mut leave_chance = 1;
+    let total = pool.remaining();
+
+    // start up the first kingdom
+    state.add_kingdom_to_schedule(KingdomName::Cap);
+    state.schedule_kingdom(kingdoms, rng)?;
+    pool.kingdom_visited(moons, &state);
+
+    loop {
+        // first, find all moons that can be scheduled
+        let available = pool.return_available(moons, &mut state)?;
+        for a in &available {
+            state.add_moon_to_schedule(*a);
+        }
+        // schedule a random count trying to be enough to leave
+        let exit_count = if state.completed_main_game() {
+            1
+        } else {
+            kingdoms.kingdom(state.current_kingdom())?.moons_to_leave()
+        };
+        let scheduleable = state.moons_affordable(moons)?;
+        let exit_count = std::cmp::min(exit_count as usize, scheduleable);
+        let scheduled = if exit_count == scheduleable {
+            exit_count
+        } else {
+            rng.gen_range(exit_count, scheduleable)
+        };
+        if scheduled == 0 {
+            state.next_kingdom(kingdoms)?;
+            if state.kingdoms_to_schedule() == 0 {
+                // no more moons and no more kingdoms, we are done
+                break;
+            }
+            // schedule the next kingdom
+            state.schedule_kingdom(kingdoms, rng)?;
+            pool.kingdom_visited(moons, &state);
+        } else {
+            // schedule the moons
+            for _ in 0..scheduled {
+                // the moons bought so far can leave too few coins for the rest
+                if state.moons_affordable(moons)? == 0 {
+                    break;
+                }
+                let id = state.schedule_moon(moons, rng)?;
+                pool.moon_scheduled(moons, &state, id);
+            }
+            // lets only leave with a 10% chance that increases 10% each time
+            let chance = rng.gen_range(0, 10);
+            if chance < leave_chance {
+                leave_chance = 1;
+                // leave for the next kingdom, if there is one to go to
+                if state.next_kingdom(kingdoms)? && state.kingdoms_to_schedule() > 0 {
+                    state.schedule_kingdom(kingdoms, rng)?;
+                    pool.kingdom_visited(moons, &state);
+                }
+            } else {
+                leave_chance += 1;
+            }
+        }
+    }
+
+    // running out of kingdoms early means the data left some moons stuck
+    let scheduled = state.moons_ordered().len();
+    if scheduled < total {
+        return Err(RandomizerError::IncompleteRoute { scheduled, total });
+    }
+    Ok(state)
+}
+
+pub fn generate_many<'a>(kingdoms: &'a Kingdoms, moons: &'a Moons,
+                         pool: &'a MoonPool, state: &'a State, seed: u64,
+                         runs: usize)
+    -> impl IndexedParallelIterator<Item = Result<State, RandomizerError>> + 'a {
+    // every run gets its own generator seeded from its number, so the routes
+    // only depend on the seed and not on which thread generated them
+    (0..runs).into_par_iter().map(move |run| {
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(run as u64));
+        generate(kingdoms, moons, pool.clone(), state.clone(), &mut rng)
+    })
+}
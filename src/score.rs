@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::error::RandomizerError;
+use crate::moon::Moons;
+use crate::state::State;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Metric {
+    Visits,
+    Revisits,
+    Variance,
+    Gap,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [
+        Metric::Visits,
+        Metric::Revisits,
+        Metric::Variance,
+        Metric::Gap,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Metric> {
+        match name.to_ascii_lowercase().as_str() {
+            "visits" => Some(Metric::Visits),
+            "revisits" => Some(Metric::Revisits),
+            "variance" => Some(Metric::Variance),
+            "gap" => Some(Metric::Gap),
+            _ => None,
+        }
+    }
+}
+
+// lower is better for every part of the score
+pub struct Score {
+    visits: usize,
+    revisits: usize,
+    variance: f64,
+    longest_gap: usize,
+}
+
+impl Score {
+    pub fn new(moons: &Moons, state: &State) -> Result<Self, RandomizerError> {
+        let visits = state.visits(moons)?;
+
+        // every visit to a kingdom after the first is a revisit
+        let mut seen = HashMap::new();
+        let mut revisits = 0;
+        // the most visits elsewhere before coming back to a kingdom
+        let mut longest_gap = 0;
+        for (x, (k, _)) in visits.iter().enumerate() {
+            if let Some(last) = seen.insert(*k, x) {
+                revisits += 1;
+                longest_gap = std::cmp::max(longest_gap, x - last - 1);
+            }
+        }
+
+        // how uneven the number of moons collected on each visit is
+        let mut counts = Vec::new();
+        for (_, ids) in &visits {
+            let mut count = 0;
+            for m in ids.iter() {
+                count += moons.moon(*m)?.count();
+            }
+            counts.push(count);
+        }
+        let variance = if counts.is_empty() {
+            0.0
+        } else {
+            let n = counts.len() as f64;
+            let mean = counts.iter().map(|c| f64::from(*c)).sum::<f64>() / n;
+            counts.iter()
+                .map(|c| (f64::from(*c) - mean).powi(2))
+                .sum::<f64>() / n
+        };
+
+        Ok(Score {
+            visits: visits.len(),
+            revisits,
+            variance,
+            longest_gap,
+        })
+    }
+
+    pub fn visits(&self) -> usize {
+        self.visits
+    }
+
+    pub fn revisits(&self) -> usize {
+        self.revisits
+    }
+
+    pub fn variance(&self) -> f64 {
+        self.variance
+    }
+
+    pub fn longest_gap(&self) -> usize {
+        self.longest_gap
+    }
+
+    pub fn value(&self, metric: Metric) -> f64 {
+        match metric {
+            Metric::Visits => self.visits as f64,
+            Metric::Revisits => self.revisits as f64,
+            Metric::Variance => self.variance,
+            Metric::Gap => self.longest_gap as f64,
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Route score: {} visits, {} revisits, {:.1} variance in moons \
+                  per visit, longest gap of {} visits",
+                 self.visits, self.revisits, self.variance, self.longest_gap);
+    }
+}
@@ -1,10 +1,17 @@
 use std::collections::{HashSet, HashMap};
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
+use crate::capture::Capture;
+use crate::error::RandomizerError;
+use crate::estimate::{format_duration, Estimates, Subtotal};
 use crate::kingdom::{Kingdoms, KingdomName};
 use crate::moon::{Moons, MoonID};
+use crate::outfit::Outfit;
+use crate::requirement::Flag;
+use crate::tag::Tag;
 
+#[derive(Clone)]
 pub struct State {
     current_kingdom: KingdomName,
     total_kingdom_moons: u16,
@@ -15,9 +22,26 @@ pub struct State {
     moons_scheduled: HashSet<MoonID>,
     kingdoms_to_schedule: Vec<KingdomName>,
     kingdoms_ordered: Vec<KingdomName>,
+    moons_per_visit: Vec<u16>,
     kingdoms_scheduled: HashMap<KingdomName, u8>,
     kingdoms_completed: HashSet<KingdomName>,
+    kingdom_phases: HashMap<KingdomName, u8>,
+    captures: HashMap<Capture, MoonID>,
+    coins: u32,
+    regional_coins_found: HashMap<KingdomName, u16>,
+    regional_coins: HashMap<KingdomName, u16>,
+    outfits: HashSet<Outfit>,
+    moon_rocks_broken: HashSet<KingdomName>,
+    kingdoms_post_game: HashSet<KingdomName>,
+    tags: HashMap<Tag, u16>,
     completed_main_game: bool,
+    difficulty_curve: f64,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl State {
@@ -32,29 +56,119 @@ impl State {
             moons_scheduled: HashSet::new(),
             kingdoms_to_schedule: Vec::new(),
             kingdoms_ordered: Vec::new(),
+            moons_per_visit: Vec::new(),
             kingdoms_scheduled: HashMap::new(),
             kingdoms_completed: HashSet::new(),
+            kingdom_phases: HashMap::new(),
+            captures: HashMap::new(),
+            coins: 0,
+            regional_coins_found: HashMap::new(),
+            regional_coins: HashMap::new(),
+            outfits: HashSet::new(),
+            moon_rocks_broken: HashSet::new(),
+            kingdoms_post_game: HashSet::new(),
+            tags: HashMap::new(),
             completed_main_game: false,
+            difficulty_curve: 0.0,
         }
     }
 
-    pub fn print_moons(&self, kingdoms: &Kingdoms, moons: &Moons) {
+    pub fn print_moons(&self, kingdoms: &Kingdoms, moons: &Moons,
+                       estimates: &Estimates) -> Result<(), RandomizerError> {
         let mut x = 1;
-        let mut current_kingdom = KingdomName::Cap;
+        let mut current_kingdom = None;
+        let mut subtotal = Subtotal::new(estimates);
         for m in &self.moons_ordered {
-            if moons.moon(*m).kingdom() != current_kingdom {
-                println!("==={}===",
-                     kingdoms.kingdom(moons.moon(*m).kingdom()).name());
-                current_kingdom = moons.moon(*m).kingdom();
+            let moon = moons.moon(*m)?;
+            if current_kingdom != Some(moon.kingdom()) {
+                if current_kingdom.is_some() {
+                    println!("\t{}", subtotal);
+                }
+                println!("==={}===", kingdoms.kingdom(moon.kingdom())?.name());
+                current_kingdom = Some(moon.kingdom());
+                subtotal.arrive();
             }
-            let count = moons.moon(*m).count();
-            if count > 1 {
-                println!("{}.\t{} ({})", x, moons.moon(*m).name(), count);
+            let count = moon.count();
+            let mut line = if count == 0 {
+                // route steps that aren't moons don't get a number
+                format!("\t{}", moon.name())
+            } else if count > 1 {
+                format!("{}.\t{} ({})", x, moon.name(), count)
             } else {
-                println!("{}.\t{}", x, moons.moon(*m).name());
+                format!("{}.\t{}", x, moon.name())
+            };
+            if let Some(art) = moon.hint_art() {
+                line.push_str(&format!(" (hint art seen in {}, {})",
+                                       art.location(),
+                                       kingdoms.kingdom(art.kingdom())?.name()));
             }
+            line.push_str(&self.capture_notes(moons, *m)?);
+            println!("{}", line);
             x += count;
+            subtotal.collect(*m)?;
         }
+        println!("\t{}", subtotal);
+        println!("Projected finish: {}", format_duration(subtotal.elapsed()));
+        Ok(())
+    }
+
+    // the route a visit at a time, as it is printed, so kingdoms the route
+    // passed through without collecting anything don't count as visits
+    pub fn visits(&self, moons: &Moons) -> Result<Vec<(KingdomName, &[MoonID])>, RandomizerError> {
+        let mut visits: Vec<(KingdomName, &[MoonID])> = Vec::new();
+        let mut start = 0;
+        for (x, m) in self.moons_ordered.iter().enumerate() {
+            let kingdom = moons.moon(*m)?.kingdom();
+            match visits.last_mut() {
+                Some((k, v)) if *k == kingdom => *v = &self.moons_ordered[start..=x],
+                _ => {
+                    start = x;
+                    visits.push((kingdom, &self.moons_ordered[x..=x]));
+                }
+            }
+        }
+        Ok(visits)
+    }
+
+    pub fn route_text(&self, kingdoms: &Kingdoms, moons: &Moons,
+                      estimates: &Estimates) -> Result<String, RandomizerError> {
+        // one moon per line with its kingdom and no numbering, so a moon
+        // moving in the route only changes the lines it moved between and
+        // the times under their visits
+        let mut text = String::new();
+        let mut current_kingdom = None;
+        let mut subtotal = Subtotal::new(estimates);
+        for m in &self.moons_ordered {
+            let moon = moons.moon(*m)?;
+            if current_kingdom != Some(moon.kingdom()) {
+                if current_kingdom.is_some() {
+                    text.push_str(&format!("\t{}\n", subtotal));
+                }
+                current_kingdom = Some(moon.kingdom());
+                subtotal.arrive();
+            }
+            text.push_str(&format!("{}: {}{}\n",
+                                   kingdoms.kingdom(moon.kingdom())?.name(),
+                                   moon.name(), self.capture_notes(moons, *m)?));
+            subtotal.collect(*m)?;
+        }
+        if current_kingdom.is_some() {
+            text.push_str(&format!("\t{}\n", subtotal));
+        }
+        text.push_str(&format!("Projected finish: {}\n", format_duration(subtotal.elapsed())));
+        Ok(text)
+    }
+
+    // a note on the moon each capture is first learned from, the same in
+    // every format the route is printed in
+    pub fn capture_notes(&self, moons: &Moons, id: MoonID) -> Result<String, RandomizerError> {
+        let mut notes = String::new();
+        for c in moons.moon(id)?.captures() {
+            if self.captures.get(c) == Some(&id) {
+                notes.push_str(&format!(" [learn capture: {}]", c.name()));
+            }
+        }
+        Ok(notes)
     }
 
     pub fn add_kingdom_to_schedule(&mut self, id: KingdomName) {
@@ -76,17 +190,23 @@ impl State {
         self.kingdoms_to_schedule.push(id);
     }
 
-    pub fn schedule_kingdom(&mut self) -> bool {
-        // if there are no kingdoms to schedule, return false
+    pub fn kingdoms_to_schedule(&self) -> usize {
+        self.kingdoms_to_schedule.len()
+    }
+
+    pub fn schedule_kingdom<R: Rng + ?Sized>(&mut self, kingdoms: &Kingdoms,
+                                           rng: &mut R) -> Result<KingdomName, RandomizerError> {
         if self.kingdoms_to_schedule.is_empty() {
-            return false;
+            return Err(RandomizerError::NothingToSchedule(
+                String::from("no kingdom can be travelled to")));
         }
         // randomly pick an available kingdom and schedule it
-        let random = thread_rng().gen_range(0, self.kingdoms_to_schedule.len());
+        let random = rng.gen_range(0, self.kingdoms_to_schedule.len());
         // remove it from the scheduled
         let id = self.kingdoms_to_schedule.remove(random);
         // schedule it
         self.kingdoms_ordered.push(id);
+        self.moons_per_visit.push(0);
         // update how many times we scheduled this
         match self.kingdoms_scheduled.get_mut(&id) {
             Some(v) => *v += 1,
@@ -108,21 +228,30 @@ impl State {
         // set the current schedule
         self.current_kingdom = id;
         self.total_kingdom_moons = 0;
+        // collect the coins picked up on this visit
+        let kingdom = kingdoms.kingdom(id)?;
+        self.coins += u32::from(kingdom.coin_income());
+        // and the regional coins, until every one in the kingdom is found
+        let found = self.regional_coins_found.entry(id).or_insert(0);
+        let new_coins = std::cmp::min(kingdom.regional_coins_per_visit(),
+                                      kingdom.regional_coins() - *found);
+        *found += new_coins;
+        *self.regional_coins.entry(id).or_insert(0) += new_coins;
         // check if we beat the game
         if self.current_kingdom == KingdomName::Mushroom {
             self.completed_main_game = true;
         }
+        if self.completed_main_game {
+            self.kingdoms_post_game.insert(id);
+        }
         // if we have backed up moons, move them to the queue
-        match self.moons_stored_queue.get_mut(&self.current_kingdom) {
-            Some(v) => {
-                for vv in v {
-                    self.moons_to_schedule.push(*vv);
-                }
+        if let Some(v) = self.moons_stored_queue.get_mut(&self.current_kingdom) {
+            for vv in v {
+                self.moons_to_schedule.push(*vv);
             }
-            None => {}
         }
         self.moons_stored_queue.insert(self.current_kingdom, Vec::new());
-        true
+        Ok(id)
     }
 
     pub fn complete_kingdom(&mut self, id: KingdomName) {
@@ -130,13 +259,13 @@ impl State {
         self.kingdoms_completed.insert(id);
     }
 
-    pub fn next_kingdom(&mut self, kingdoms: &Kingdoms) -> bool {
+    pub fn next_kingdom(&mut self, kingdoms: &Kingdoms) -> Result<bool, RandomizerError> {
         // move to the next kingdom
+        if !kingdoms.kingdom(self.current_kingdom)?.can_leave(self) {
+            // can't leave yet
+            return Ok(false);
+        }
         if self.completed_main_game {
-            if !kingdoms.kingdom(self.current_kingdom).can_leave(&self) {
-                // can't leave yet
-                return false;
-            }
             // add every kingdom that isn't this one
             self.add_kingdom_to_schedule(KingdomName::Cap);
             self.add_kingdom_to_schedule(KingdomName::Cascade);
@@ -153,24 +282,20 @@ impl State {
             self.add_kingdom_to_schedule(KingdomName::Bowser);
             self.add_kingdom_to_schedule(KingdomName::Moon);
             self.add_kingdom_to_schedule(KingdomName::Mushroom);
-            if kingdoms.kingdom(KingdomName::Dark).available(&self) {
+            if kingdoms.kingdom(KingdomName::Dark)?.available(self) {
                 self.add_kingdom_to_schedule(KingdomName::Dark);
             }
-            if kingdoms.kingdom(KingdomName::Darker).available(&self) {
+            if kingdoms.kingdom(KingdomName::Darker)?.available(self) {
                 self.add_kingdom_to_schedule(KingdomName::Darker);
             }
         } else {
-            if !kingdoms.kingdom(self.current_kingdom).can_leave(&self) {
-                // can't leave yet
-                return false;
-            }
-            for k in kingdoms.kingdom(self.current_kingdom).next() {
-                if kingdoms.kingdom(*k).available(&self) {
+            for k in kingdoms.kingdom(self.current_kingdom)?.next() {
+                if kingdoms.kingdom(*k)?.available(self) {
                     self.add_kingdom_to_schedule(*k);
                 }
             }
         }
-        true
+        Ok(true)
     }
 
     pub fn add_moon_to_schedule(&mut self, id: MoonID) {
@@ -181,27 +306,111 @@ impl State {
         self.moons_to_schedule.len()
     }
 
-    pub fn schedule_moon(&mut self, moons: &Moons) -> bool {
-        // if there are no moons to schedule, return false
+    // the moons waiting to be scheduled that the coins left can pay for
+    pub fn moons_affordable(&self, moons: &Moons) -> Result<usize, RandomizerError> {
+        Ok(self.affordable(moons)?.len())
+    }
+
+    fn affordable(&self, moons: &Moons) -> Result<Vec<usize>, RandomizerError> {
+        let mut affordable = Vec::new();
+        for (x, m) in self.moons_to_schedule.iter().enumerate() {
+            if u32::from(moons.moon(*m)?.coin_cost()) <= self.coins {
+                affordable.push(x);
+            }
+        }
+        Ok(affordable)
+    }
+
+    pub fn schedule_moon<R: Rng + ?Sized>(&mut self, moons: &Moons,
+                                        rng: &mut R) -> Result<MoonID, RandomizerError> {
         if self.moons_to_schedule.is_empty() {
-            return false;
+            return Err(RandomizerError::NothingToSchedule(
+                String::from("no moon is available")));
         }
         // randomly pick a moon and schedule it
-        let random = thread_rng().gen_range(0, self.moons_to_schedule.len());
-        let id = self.moons_to_schedule.remove(random);
-        let count = moons.moon(id).count();
-        // schedule it
+        let random = self.pick_moon(moons, rng)?;
+        let id = self.moons_to_schedule[random];
+        let moon = moons.moon(id)?;
+        // schedule it, paying for it now it is collected
+        self.spend_coins(moon.coin_cost())?;
+        self.moons_to_schedule.remove(random);
+        let count = moon.count();
         self.moons_ordered.push(id);
         self.moons_scheduled.insert(id);
+        // move the kingdom on if this moon changes its story
+        if moon.advances_phase() {
+            *self.kingdom_phases.entry(moon.kingdom()).or_insert(0) += 1;
+        }
+        if moon.breaks_moon_rock() {
+            self.moon_rocks_broken.insert(moon.kingdom());
+        }
+        // remember where each capture was first learned
+        for c in moon.captures() {
+            self.captures.entry(*c).or_insert(id);
+        }
+        for t in moon.tags() {
+            *self.tags.entry(*t).or_insert(0) += 1;
+        }
+        if let Some(v) = self.moons_per_visit.last_mut() {
+            *v += count;
+        }
         self.total_kingdom_moons += count;
         self.total_moons += count;
-        true
+        Ok(id)
+    }
+
+    pub fn set_difficulty_curve(&mut self, curve: f64) {
+        // 0 picks uniformly, higher values stick closer to the curve
+        self.difficulty_curve = curve;
+    }
+
+    fn pick_moon<R: Rng + ?Sized>(&self, moons: &Moons,
+                                  rng: &mut R) -> Result<usize, RandomizerError> {
+        // only the moons the coins left can pay for
+        let affordable = self.affordable(moons)?;
+        if affordable.is_empty() {
+            return Err(RandomizerError::NothingToSchedule(
+                String::from("no moon can be paid for")));
+        }
+        if self.difficulty_curve <= 0.0 {
+            return Ok(affordable[rng.gen_range(0, affordable.len())]);
+        }
+        // the difficulty we want rises from 1 to 5 as the route goes on, and
+        // moons are weighted by how close they are to it
+        let progress = f64::from(self.total_moons) / f64::from(moons.total_count());
+        let target = 1.0 + 4.0 * progress;
+        let weights: Vec<f64> = affordable.iter()
+            .map(|x| {
+                let difficulty = f64::from(moons.moon(self.moons_to_schedule[*x])?.difficulty());
+                Ok((-self.difficulty_curve * (difficulty - target).abs()).exp())
+            })
+            .collect::<Result<_, RandomizerError>>()?;
+        let mut random = rng.gen_range(0.0, weights.iter().sum::<f64>());
+        for (x, w) in weights.iter().enumerate() {
+            if random < *w {
+                return Ok(affordable[x]);
+            }
+            random -= w;
+        }
+        Ok(affordable[affordable.len() - 1])
     }
 
     pub fn current_kingdom(&self) -> KingdomName {
         self.current_kingdom
     }
 
+    pub fn moons_ordered(&self) -> &Vec<MoonID> {
+        &self.moons_ordered
+    }
+
+    pub fn kingdoms_ordered(&self) -> &Vec<KingdomName> {
+        &self.kingdoms_ordered
+    }
+
+    pub fn moons_per_visit(&self) -> &Vec<u16> {
+        &self.moons_per_visit
+    }
+
     pub fn total_kingdom_moons(&self) -> u16 {
         self.total_kingdom_moons
     }
@@ -218,6 +427,67 @@ impl State {
         self.completed_main_game
     }
 
+    pub fn kingdom_phase(&self, kingdom: KingdomName) -> u8 {
+        match self.kingdom_phases.get(&kingdom) {
+            Some(p) => *p,
+            None => 0,
+        }
+    }
+
+    pub fn coins(&self) -> u32 {
+        self.coins
+    }
+
+    fn spend_coins(&mut self, cost: u16) -> Result<(), RandomizerError> {
+        // only affordable moons are picked, so this is never short
+        self.coins = self.coins.checked_sub(u32::from(cost))
+            .ok_or(RandomizerError::NotEnoughCoins { cost, coins: self.coins })?;
+        Ok(())
+    }
+
+    pub fn regional_coins(&self, kingdom: KingdomName) -> u16 {
+        match self.regional_coins.get(&kingdom) {
+            Some(c) => *c,
+            None => 0,
+        }
+    }
+
+    pub fn can_wear(&self, outfit: Outfit) -> bool {
+        self.outfits.contains(&outfit)
+            || self.regional_coins(outfit.kingdom()) >= outfit.cost()
+    }
+
+    pub fn buy_outfit(&mut self, outfit: Outfit) {
+        // an outfit the regional coins can't pay for stays in the shop
+        if self.outfits.contains(&outfit) {
+            return;
+        }
+        let coins = self.regional_coins(outfit.kingdom());
+        if let Some(left) = coins.checked_sub(outfit.cost()) {
+            self.regional_coins.insert(outfit.kingdom(), left);
+            self.outfits.insert(outfit);
+        }
+    }
+
+    pub fn capture_learned(&self, capture: Capture) -> bool {
+        self.captures.contains_key(&capture)
+    }
+
+    pub fn flag(&self, flag: Flag) -> bool {
+        match flag {
+            Flag::CompletedMainGame => self.completed_main_game,
+            Flag::MoonRockBroken(k) => self.moon_rocks_broken.contains(&k),
+        }
+    }
+
+    pub fn tag_count(&self, tag: Tag) -> u16 {
+        self.tags.get(&tag).copied().unwrap_or(0)
+    }
+
+    pub fn kingdom_visited_post_game(&self, kingdom: KingdomName) -> bool {
+        self.kingdoms_post_game.contains(&kingdom)
+    }
+
     pub fn kingdom_scheduled(&self, kingdom: KingdomName, visited: u8) -> bool {
         match self.kingdoms_scheduled.get(&kingdom) {
             Some(v) => *v >= visited,
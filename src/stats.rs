@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use crate::error::RandomizerError;
+use crate::kingdom::{Kingdoms, KingdomName};
+use crate::moon::Moons;
+use crate::state::State;
+
+// width of the longest histogram bar
+const BAR_WIDTH: usize = 40;
+const BUCKETS: u32 = 10;
+
+#[derive(Default)]
+pub struct Distribution {
+    samples: Vec<u32>,
+}
+
+impl Distribution {
+    pub fn new() -> Self {
+        Distribution {
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, sample: u32) {
+        self.samples.push(sample);
+    }
+
+    pub fn merge(&mut self, other: Distribution) {
+        self.samples.extend(other.samples);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn percentile(&self, percent: u32) -> u32 {
+        // nearest rank on the sorted samples
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let rank = (percent as usize * sorted.len()).div_ceil(100);
+        sorted[rank.max(1) - 1]
+    }
+
+    pub fn print_percentiles(&self, label: &str) {
+        if self.is_empty() {
+            println!("{:<24} never", label);
+            return;
+        }
+        println!("{:<24} min {:>4}  p10 {:>4}  p50 {:>4}  p90 {:>4}  p99 {:>4}  max {:>4}",
+                 label, self.percentile(0), self.percentile(10),
+                 self.percentile(50), self.percentile(90),
+                 self.percentile(99), self.percentile(100));
+    }
+
+    pub fn print_histogram(&self) {
+        if self.is_empty() {
+            return;
+        }
+        let min = self.percentile(0);
+        let max = self.percentile(100);
+        let size = std::cmp::max(1, (max - min + BUCKETS) / BUCKETS);
+        let mut counts = vec![0; ((max - min) / size + 1) as usize];
+        for s in &self.samples {
+            counts[((s - min) / size) as usize] += 1;
+        }
+        let most = *counts.iter().max().unwrap_or(&1);
+        for (x, c) in counts.iter().enumerate() {
+            let start = min + x as u32 * size;
+            println!("{:>5} - {:<5} {:>6} {}", start, start + size - 1, c,
+                     "#".repeat(c * BAR_WIDTH / most));
+        }
+    }
+}
+
+pub struct Stats {
+    runs: usize,
+    failures: usize,
+    visits: Distribution,
+    kingdom_visits: HashMap<KingdomName, Distribution>,
+    exit_positions: HashMap<KingdomName, Distribution>,
+    unlock_visits: HashMap<KingdomName, Distribution>,
+    first_visits: HashMap<KingdomName, Distribution>,
+    first_moons: HashMap<KingdomName, Distribution>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats {
+            runs: 0,
+            failures: 0,
+            visits: Distribution::new(),
+            kingdom_visits: HashMap::new(),
+            exit_positions: HashMap::new(),
+            unlock_visits: HashMap::new(),
+            first_visits: HashMap::new(),
+            first_moons: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, kingdoms: &Kingdoms, moons: &Moons,
+               state: &State) -> Result<(), RandomizerError> {
+        self.runs += 1;
+
+        // visits as the route prints them, so hops that collect nothing
+        // aren't counted
+        let visits = state.visits(moons)?;
+        self.visits.add(visits.len() as u32);
+        for k in KingdomName::ALL.iter() {
+            let count = visits.iter().filter(|(v, _)| v == k).count();
+            self.kingdom_visits.entry(*k).or_default().add(count as u32);
+        }
+
+        // where each exit moon lands, numbered as the route prints them
+        let mut position = 1;
+        for m in state.moons_ordered() {
+            let moon = moons.moon(*m)?;
+            if kingdoms.exit_for(*m).is_some() {
+                self.exit_positions.entry(moon.kingdom())
+                    .or_default()
+                    .add(u32::from(position));
+            }
+            position += moon.count();
+        }
+
+        // the visit whose moons bring the total up to what the kingdoms
+        // unlocked by moon totals need, and the first visit to them after
+        let mut totals = Vec::new();
+        let mut collected = 0;
+        for (_, v) in &visits {
+            totals.push(collected);
+            for m in v.iter() {
+                collected += moons.moon(*m)?.count();
+            }
+        }
+        totals.push(collected);
+        for k in [KingdomName::Dark, KingdomName::Darker].iter() {
+            let needed = kingdoms.kingdom(*k)?.moons_to_unlock();
+            if let Some(x) = totals.iter().skip(1).position(|t| *t >= needed) {
+                self.unlock_visits.entry(*k).or_default().add(x as u32 + 1);
+            }
+            if let Some(x) = visits.iter().position(|(v, _)| v == k) {
+                self.first_visits.entry(*k).or_default().add(x as u32 + 1);
+                self.first_moons.entry(*k).or_default()
+                    .add(u32::from(totals[x]));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_failure(&mut self) {
+        // a route that could not schedule every moon
+        self.runs += 1;
+        self.failures += 1;
+    }
+
+    pub fn merge(mut self, other: Stats) -> Stats {
+        // combine the runs of two batches, such as from different threads
+        self.runs += other.runs;
+        self.failures += other.failures;
+        self.visits.merge(other.visits);
+        let maps = vec![
+            (&mut self.kingdom_visits, other.kingdom_visits),
+            (&mut self.exit_positions, other.exit_positions),
+            (&mut self.unlock_visits, other.unlock_visits),
+            (&mut self.first_visits, other.first_visits),
+            (&mut self.first_moons, other.first_moons),
+        ];
+        for (mine, theirs) in maps {
+            for (k, d) in theirs {
+                mine.entry(k).or_default().merge(d);
+            }
+        }
+        self
+    }
+
+    pub fn print(&self, kingdoms: &Kingdoms) -> Result<(), RandomizerError> {
+        println!("Routes generated: {}", self.runs);
+        println!("Routes missing moons: {} ({:.2}%)", self.failures,
+                 percent(self.failures, self.runs));
+
+        println!();
+        println!("===Kingdom visits per route===");
+        self.visits.print_percentiles("Visits");
+        self.visits.print_histogram();
+
+        println!();
+        println!("===Visits per kingdom===");
+        for (k, kingdom) in kingdoms.iter() {
+            if let Some(d) = self.kingdom_visits.get(&k) {
+                d.print_percentiles(kingdom.name());
+            }
+        }
+
+        println!();
+        println!("===Exit moon position===");
+        for (k, kingdom) in kingdoms.iter() {
+            if let Some(d) = self.exit_positions.get(&k) {
+                d.print_percentiles(kingdom.name());
+            }
+        }
+
+        println!();
+        println!("===Unlocks===");
+        for k in [KingdomName::Dark, KingdomName::Darker].iter() {
+            let name = kingdoms.kingdom(*k)?.name();
+            let empty = Distribution::new();
+            let unlocked = self.unlock_visits.get(k).unwrap_or(&empty);
+            let visits = self.first_visits.get(k).unwrap_or(&empty);
+            let moons = self.first_moons.get(k).unwrap_or(&empty);
+            println!("{} unlocked in {} routes ({:.2}%)", name, unlocked.len(),
+                     percent(unlocked.len(), self.runs));
+            unlocked.print_percentiles("Unlocked by visit");
+            println!("{} reached in {} routes ({:.2}%)", name, visits.len(),
+                     percent(visits.len(), self.runs));
+            visits.print_percentiles("First visit");
+            moons.print_percentiles("With moons");
+            moons.print_histogram();
+        }
+        Ok(())
+    }
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
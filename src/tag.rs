@@ -0,0 +1,61 @@
+// the kinds of moon counted towards Toadette's achievements
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Tag {
+    FlatMoon,
+    TreasureChest,
+    MusicNotes,
+    TimerChallenge,
+    CaptainToad,
+    PeachTour,
+    Sheep,
+    Goombette,
+    Lakitu,
+    FlowerGrowing,
+    Rabbit,
+    GroundPound,
+    HatHook,
+    TravelingBird,
+    Costume,
+    HatAndSeek,
+    Jammin,
+    Art,
+    Slots,
+    KoopaFreerunning,
+    Walking,
+    Quiz,
+    SecretPath,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 23] = [
+        Tag::FlatMoon,
+        Tag::TreasureChest,
+        Tag::MusicNotes,
+        Tag::TimerChallenge,
+        Tag::CaptainToad,
+        Tag::PeachTour,
+        Tag::Sheep,
+        Tag::Goombette,
+        Tag::Lakitu,
+        Tag::FlowerGrowing,
+        Tag::Rabbit,
+        Tag::GroundPound,
+        Tag::HatHook,
+        Tag::TravelingBird,
+        Tag::Costume,
+        Tag::HatAndSeek,
+        Tag::Jammin,
+        Tag::Art,
+        Tag::Slots,
+        Tag::KoopaFreerunning,
+        Tag::Walking,
+        Tag::Quiz,
+        Tag::SecretPath,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Tag> {
+        Tag::ALL.iter()
+            .find(|t| format!("{:?}", t).eq_ignore_ascii_case(name))
+            .copied()
+    }
+}
@@ -0,0 +1,486 @@
+use std::collections::HashMap;
+
+use odyssey_randomizer::kingdom::{Kingdoms, KingdomName};
+use odyssey_randomizer::moon::Moons;
+use odyssey_randomizer::requirement::Requirement;
+use odyssey_randomizer::tag::Tag;
+
+// an achievement, the tag it counts and the moons it used to list by hand
+type Achievement = (&'static str, Tag, &'static [(KingdomName, &'static str)]);
+
+// in achievement order
+const HARDCODED: &[Achievement] = &[
+    ("Flat Moon Finder", Tag::FlatMoon, &[
+        (KingdomName::Cascade, "Behind the Waterfall"),
+        (KingdomName::Cascade, "Hidden Chasm Passage"),
+        (KingdomName::Cascade, "Taking Notes: Hurry Upward"),
+        (KingdomName::Sand, "Secret of the Mural"),
+        (KingdomName::Sand, "Secret of the Inverted Mural"),
+        (KingdomName::Cloud, "King of the Cube!"),
+        (KingdomName::Cloud, "The Sixth Face"),
+        (KingdomName::Lost, "Avoiding Fuzzies Inside the Wall"),
+        (KingdomName::Metro, "Celebrating in the Streets!"),
+        (KingdomName::Metro, "Up on the Big Screen"),
+    ]),
+    ("Flat Moon Fanatic", Tag::FlatMoon, &[
+        (KingdomName::Metro, "Down Inside the Big Screen"),
+        (KingdomName::Snow, "Slip Behind the Ice"),
+        (KingdomName::Seaside, "Ocean-Bottom Maze: Treasure"),
+        (KingdomName::Seaside, "Ocean-Bottom Maze: Hidden Room"),
+        (KingdomName::Seaside, "Seaside Kingdom Timer Challenge 1"),
+        (KingdomName::Seaside, "Taking Notes: Ocean-Bottom Maze"),
+        (KingdomName::Luncheon, "Beneath the Rolling Vegetables"),
+        (KingdomName::Ruined, "Roulette Tower: Stopped"),
+        (KingdomName::Bowser, "Scene of Crossing the Poison Swamp"),
+        (KingdomName::Bowser, "Taking Notes: In the Folding Screen"),
+    ]),
+    ("Treasure Chest Hunter", Tag::TreasureChest, &[
+        (KingdomName::Cascade, "Treasure of the Waterfall Basin"),
+        (KingdomName::Sand, "The Treasure of Jaxi Ruins"),
+        (KingdomName::Sand, "Hidden Room in the Inverted Pyramid"),
+        (KingdomName::Sand, "Underground Treasure Chest"),
+        (KingdomName::Sand, "Sphynx's Treasure Vault"),
+        (KingdomName::Lake, "Treasure in the Spiky Waterway"),
+        (KingdomName::Lake, "Jump, Grab, and Climb Some More"),
+        (KingdomName::Wooded, "Spinning-Platforms Treasure"),
+        (KingdomName::Wooded, "Deep Woods Treasure Trap"),
+        (KingdomName::Wooded, "Exploring for Treasure"),
+        (KingdomName::Metro, "City Hall Lost & Found"),
+        (KingdomName::Metro, "One Man's Trash..."),
+        (KingdomName::Snow, "The Shiverian Treasure Chest"),
+        (KingdomName::Snow, "Treasure in the Ice Wall"),
+        (KingdomName::Snow, "Secret Path to Shiveria"),
+    ]),
+    ("Super Treasure Chest Hunter", Tag::TreasureChest, &[
+        (KingdomName::Seaside, "Glass Palace Treasure Chest"),
+        (KingdomName::Seaside, "Treasure Trap Hidden in the Inlet"),
+        (KingdomName::Seaside, "Treasure Chest in the Narrow Valley"),
+        (KingdomName::Luncheon, "The Treasure Chest in the Veggies"),
+        (KingdomName::Ruined, "In the Ancient Treasure Chest"),
+        (KingdomName::Bowser, "Bowser's Castle Treasure Vault"),
+        (KingdomName::Moon, "Cliffside Treasure Chest"),
+        (KingdomName::Moon, "Fly to the Treasure Chest and Back"),
+        (KingdomName::Moon, "Sphynx's Hidden Vault"),
+        (KingdomName::Mushroom, "Courtyard Chest Trap"),
+    ]),
+    ("Note-Collecting World Tour", Tag::MusicNotes, &[
+        (KingdomName::Cap, "Taking Notes: In the Fog"),
+        (KingdomName::Cascade, "Taking Notes: Hurry Upward"),
+        (KingdomName::Sand, "Taking Notes: Jump on the Palm"),
+        (KingdomName::Sand, "Taking Notes: Running Down"),
+        (KingdomName::Sand, "Taking Notes: In the Wall Painting"),
+    ]),
+    ("Note-Collecting Space Tour", Tag::MusicNotes, &[
+        (KingdomName::Lake, "Taking Notes: Dive and Swim"),
+        (KingdomName::Lake, "Taking Notes: In the Cliffside"),
+        (KingdomName::Wooded, "Taking Notes: On Top of the Wall"),
+        (KingdomName::Wooded, "Taking Notes: Stretching"),
+        (KingdomName::Cloud, "Taking Notes: Up and Down"),
+        (KingdomName::Lost, "Taking Notes: Stretch and Shrink"),
+        (KingdomName::Snow, "Taking Notes: Snow Path Dash"),
+        (KingdomName::Seaside, "Taking Notes: Ocean Surface Dash"),
+        (KingdomName::Seaside, "Taking Notes: Ocean-Bottom Maze"),
+        (KingdomName::Seaside, "Taking Notes in the Sea"),
+        (KingdomName::Luncheon, "Taking Notes: Swimming in Magma"),
+        (KingdomName::Luncheon, "Taking Notes: Big Pot Swim"),
+        (KingdomName::Luncheon, "Taking Notes: Spinning Athletics"),
+        (KingdomName::Bowser, "Taking Notes: Between Spinies"),
+        (KingdomName::Bowser, "Taking Notes: On the Wall"),
+        (KingdomName::Bowser, "Taking Notes with a Spinning Throw"),
+        (KingdomName::Moon, "Taking Notes: On the Moon's Surface"),
+        (KingdomName::Moon, "Taking Notes: In Low Gravity"),
+        (KingdomName::Mushroom, "Taking Notes: Around the Well"),
+    ]),
+    ("Timer Challenge Amateur", Tag::TimerChallenge, &[
+        (KingdomName::Cap, "Cap Kingdom Timer Challenge 1"),
+        (KingdomName::Cap, "Cap Kingdom Timer Challenge 2"),
+        (KingdomName::Cascade, "Cascade Kingdom Timer Challenge 1"),
+        (KingdomName::Cascade, "Cascade Kingdom Timer Challenge 2"),
+        (KingdomName::Sand, "Sand Kingdom Timer Challenge 1"),
+        (KingdomName::Sand, "Sand Kingdom Timer Challenge 2"),
+        (KingdomName::Sand, "Sand Kingdom Timer Challenge 3"),
+        (KingdomName::Lake, "Lake Kingdom Timer Challenge 1"),
+        (KingdomName::Lake, "Lake Kingdom Timer Challenge 2"),
+        (KingdomName::Wooded, "Wooded Kingdom Timer Challenge 1"),
+        (KingdomName::Wooded, "Wooded Kingdom Timer Challenge 2"),
+        (KingdomName::Wooded, "Wooded Kingdom Timer Challenge 3"),
+        (KingdomName::Lost, "Lost Kingdom Timer Challenge"),
+        (KingdomName::Metro, "Metro Kingdom Timer Challenge 1"),
+        (KingdomName::Metro, "Metro Kingdom Timer Challenge 2"),
+    ]),
+    ("Timer Challenge Professional", Tag::TimerChallenge, &[
+        (KingdomName::Metro, "Metro Kingdom Timer Challenge 3"),
+        (KingdomName::Snow, "Snow Kingdom Timer Challenge 1"),
+        (KingdomName::Snow, "Snow Kingdom Timer Challenge 2"),
+        (KingdomName::Snow, "Snow Kingdom Timer Challenge 3"),
+        (KingdomName::Seaside, "Seaside Kingdom Timer Challenge 1"),
+        (KingdomName::Seaside, "Seaside Kingdom Timer Challenge 2"),
+        (KingdomName::Seaside, "Seaside Kingdom Timer Challenge 3"),
+        (KingdomName::Luncheon, "Luncheon Kingdom Timer Challenge 1"),
+        (KingdomName::Luncheon, "Luncheon Kingdom Timer Challenge 2"),
+        (KingdomName::Luncheon, "Luncheon Kingdom Timer Challenge 3"),
+        (KingdomName::Bowser, "Bowser's Kingdom Timer Challenge 1"),
+        (KingdomName::Bowser, "Bowser's Kingdom Timer Challenge 2"),
+        (KingdomName::Moon, "Moon Kingdom Timer Challenge 1"),
+        (KingdomName::Moon, "Moon Kingdom Timer Challenge 2"),
+        (KingdomName::Mushroom, "Mushroom Kingdom Timer Challenge"),
+    ]),
+    ("Captain Toad Meeter", Tag::CaptainToad, &[
+        (KingdomName::Cap, "Good Evening, Captain Toad!"),
+        (KingdomName::Cascade, "Good Morning, Captain Toad!"),
+        (KingdomName::Sand, "You're Quite a Catch, Captain Toad!"),
+        (KingdomName::Lake, "Let's Go Swimming, Captain Toad!"),
+        (KingdomName::Wooded, "Hey Out There, Captain Toad!"),
+    ]),
+    ("Captain Toad Greeter", Tag::CaptainToad, &[
+        (KingdomName::Lost, "Get Some Rest, Captain Toad"),
+        (KingdomName::Metro, "How You Doin' Captain Toad?"),
+        (KingdomName::Snow, "Captain Toad is Chilly!"),
+        (KingdomName::Seaside, "Good Job, Captain Toad!"),
+        (KingdomName::Luncheon, "Bon Appetit, Captain Toad!"),
+    ]),
+    ("Touring with Princess Peach", Tag::PeachTour, &[
+        (KingdomName::Cap, "Peach in the Cap Kingdom"),
+        (KingdomName::Cascade, "Peach in the Cascade Kingdom"),
+        (KingdomName::Sand, "Peach in the Sand Kingdom"),
+        (KingdomName::Lake, "Peach in the Lake Kingdom"),
+        (KingdomName::Cloud, "Peach in the Cloud Kingdom"),
+    ]),
+    ("Touring with Princess Peach", Tag::PeachTour, &[
+        (KingdomName::Lost, "Peach in the Lost Kingdom"),
+        (KingdomName::Metro, "Peach in the Metro Kingdom"),
+        (KingdomName::Snow, "Peach in the Snow Kingdom"),
+        (KingdomName::Seaside, "Peach in the Seaside Kingdom"),
+        (KingdomName::Luncheon, "Peach in the Luncheon Kingdom"),
+    ]),
+    ("Master Sheep Herder", Tag::Sheep, &[
+        (KingdomName::Sand, "Herding Sheep in the Dunes"),
+        (KingdomName::Wooded, "Herding Sheep Above the Forest Fog"),
+        (KingdomName::Wooded, "Herding Sheep on the Iron Bridge"),
+        (KingdomName::Mushroom, "Herding Sheep at Peach's Castle"),
+    ]),
+    ("Gaga for Goombette", Tag::Goombette, &[
+        (KingdomName::Sand, "Love in the Heart of the Desert"),
+        (KingdomName::Sand, "Love at the Edge of the Desert"),
+        (KingdomName::Lake, "Love by the Lake"),
+        (KingdomName::Wooded, "Love in the Forest Ruins"),
+        (KingdomName::Seaside, "Love by the Seaside"),
+        (KingdomName::Luncheon, "Love Above the Lava"),
+        (KingdomName::Mushroom, "Love at Peach's Castle"),
+    ]),
+    ("Lakitu Fishing Trip", Tag::Lakitu, &[
+        (KingdomName::Sand, "Fishing in the Oasis"),
+        (KingdomName::Sand, "You're Quite a Catch, Captain Toad!"),
+        (KingdomName::Lake, "Lake Fishing"),
+        (KingdomName::Snow, "Fishing in the Glacier!"),
+        (KingdomName::Bowser, "Fishing(?) in Bowser's Castle"),
+    ]),
+    ("Flower-Growing Guru", Tag::FlowerGrowing, &[
+        (KingdomName::Sand, "Desert Gardening: Plaza Seed"),
+        (KingdomName::Sand, "Desert Gardening: Ruins Seed"),
+        (KingdomName::Sand, "Desert Gardening: Seed on the Cliff"),
+        (KingdomName::Lake, "Lake Gardening: Spiky Passage Seed"),
+        (KingdomName::Metro, "City Gardening: Building Planter"),
+    ]),
+    ("Flower-Growing Sage", Tag::FlowerGrowing, &[
+        (KingdomName::Metro, "City Gardening: Plaza Planter"),
+        (KingdomName::Metro, "City Gardening: Rooftop Planter"),
+        (KingdomName::Seaside, "Sea Gardening: Inlet Seed"),
+        (KingdomName::Seaside, "Sea Gardening: Canyon Seed"),
+        (KingdomName::Seaside, "Sea Gardening: Hot-Spring Seed"),
+    ]),
+    ("Running with Rabbits", Tag::Rabbit, &[
+        (KingdomName::Cap, "Caught Hopping Near the Ship!"),
+        (KingdomName::Cascade, "Caught Hopping at the Waterfall"),
+        (KingdomName::Sand, "Caught Hopping in the Desert!"),
+        (KingdomName::Wooded, "Caught Hopping in the Forest!"),
+        (KingdomName::Lost, "Caught Hopping in the Jungle!"),
+    ]),
+    ("Racing with Rabbits", Tag::Rabbit, &[
+        (KingdomName::Snow, "Caught Hopping in the Snow!"),
+        (KingdomName::Luncheon, "Caught Hopping in the Volcano!"),
+        (KingdomName::Bowser, "Caught Hopping at Bowser's Castle"),
+        (KingdomName::Moon, "Caught Hopping on the Moon!"),
+        (KingdomName::Mushroom, "Caught Hopping at Peach's Castle!"),
+    ]),
+    ("Ground Pound Instructor", Tag::GroundPound, &[
+        (KingdomName::Moon, "Found with Cap Kingdom Art"),
+        (KingdomName::Cap, "Fog-Shrouded Platform"),
+        (KingdomName::Cascade, "Bottom of the Waterfall Basin"),
+        (KingdomName::Cascade, "Under the Old Electrical Pole"),
+        (KingdomName::Cascade, "Under the Ground"),
+        (KingdomName::Sand, "Where the Birds Gather"),
+        (KingdomName::Sand, "Top of a Dune"),
+        (KingdomName::Sand, "Lost in the Luggage"),
+        (KingdomName::Sand, "The Lurker Under the Stone"),
+        (KingdomName::Sand, "Found in the Sand! Good Dog!"),
+        (KingdomName::Sand, "Wandering Cactus"),
+        (KingdomName::Sand, "A Rumble from the Sandy Floor"),
+        (KingdomName::Sand, "Binding Band Returned"),
+        (KingdomName::Sand, "An Invisible Gleam"),
+        (KingdomName::Lake, "On the Lakeshore"),
+        (KingdomName::Lake, "From the Broken Pillar"),
+        (KingdomName::Cascade, "Found with Lake Kingdom Art"),
+    ]),
+    ("Ground Pound Professor", Tag::GroundPound, &[
+        (KingdomName::Lake, "Rooftop of the Water Plaza"),
+        (KingdomName::Wooded, "Thanks for the Charge!"),
+        (KingdomName::Wooded, "By the Babbling Brook in Deep Woods"),
+        (KingdomName::Wooded, "The Hard Rock in Deep Woods"),
+        (KingdomName::Wooded, "Beneath the Roots of the Moving Tree"),
+        (KingdomName::Sand, "Found with Wooded Kingdom Art"),
+        (KingdomName::Cloud, "Digging in the...Cloud?"),
+        (KingdomName::Metro, "Who Piled Garbage on This?"),
+        (KingdomName::Metro, "Hidden in the Scrap"),
+        (KingdomName::Metro, "Left at the Cafe?"),
+        (KingdomName::Lake, "Found With Metro Kingdom Art"),
+        (KingdomName::Metro, "Found in the Park! Good Dog!"),
+        (KingdomName::Metro, "Reaching Pitchblack Island"),
+        (KingdomName::Snow, "Shining in the Snow in Town"),
+        (KingdomName::Snow, "Atop a Blustery Arch"),
+        (KingdomName::Lost, "Found with Snow Kingdom Art"),
+        (KingdomName::Snow, "It Popped Out of the Ice"),
+        (KingdomName::Snow, "Deep in the Cold, Cold Water"),
+        (KingdomName::Snow, "Water Pooling in the Crevasse"),
+        (KingdomName::Snow, "Squirming Under Ice"),
+        (KingdomName::Seaside, "Under a Dangerous Ceiling"),
+        (KingdomName::Seaside, "What the Waves Left Behind"),
+        (KingdomName::Seaside, "The Back Canyon: Excavate!"),
+        (KingdomName::Seaside, "Bubblaine Northern Reaches"),
+        (KingdomName::Seaside, "Wriggling on the Sandy Bottom"),
+        (KingdomName::Seaside, "Found on the Beach! Good Dog!"),
+        (KingdomName::Seaside, "A Rumble on the Seaside Floor"),
+        (KingdomName::Metro, "Found with Seaside Kingdom Art"),
+        (KingdomName::Seaside, "At the Base of the Lighthouse"),
+        (KingdomName::Luncheon, "Atop a Column in a Row"),
+        (KingdomName::Luncheon, "Surrounded by Tall Mountains"),
+        (KingdomName::Luncheon, "Island of Salt Floating in the Lava"),
+        (KingdomName::Luncheon, "Overlooking a Bunch of Ingredients"),
+        (KingdomName::Luncheon, "All the Cracks are Fixed"),
+        (KingdomName::Luncheon, "Treasure Beneath the Cheese Rocks"),
+    ]),
+    ("Rad Hatter", Tag::HatHook, &[
+        (KingdomName::Cascade, "Guarded by a Colossal Fossil"),
+        (KingdomName::Sand, "Hang Your Hat on the Fountain"),
+        (KingdomName::Sand, "Skull Sign in the Transparent Maze"),
+    ]),
+    ("Super Rad Hatter", Tag::HatHook, &[
+        (KingdomName::Seaside, "A Light Next to the Lighthouse"),
+        (KingdomName::Seaside, "The Tall Rock Shelf in the Deep Ocean"),
+        (KingdomName::Ruined, "Caught on a Big Horn"),
+        (KingdomName::Bowser, "Caught on the Iron Fence"),
+        (KingdomName::Bowser, "Caught on the Giant Horn"),
+        (KingdomName::Moon, "The Tip of a White Spire"),
+        (KingdomName::Mushroom, "Pops Out of the Tail"),
+    ]),
+    ("Traveling-Bird Herder", Tag::TravelingBird, &[
+        (KingdomName::Cascade, "Sphynx Traveling to the Waterfall"),
+        (KingdomName::Sand, "Bird Traveling the Desert"),
+        (KingdomName::Sand, "Bird Traveling the Wastes"),
+        (KingdomName::Lake, "Bird Traveling Over the Lake"),
+        (KingdomName::Wooded, "Bird Traveling the Forest"),
+        (KingdomName::Metro, "Bird Traveling the City"),
+        (KingdomName::Metro, "Bird Traveling in the Park"),
+        (KingdomName::Seaside, "Bird Traveling Over the Ocean"),
+    ]),
+    ("Wearing it Well!", Tag::Costume, &[
+        (KingdomName::Cascade, "Caveman Cave-Fan"),
+        (KingdomName::Sand, "Dancing with New Friends"),
+        (KingdomName::Lake, "I Feel Underdressed"),
+    ]),
+    ("Wearing it Great!", Tag::Costume, &[
+        (KingdomName::Lake, "That Trendy \"Pirate\" Look"),
+        (KingdomName::Lake, "Space Is \"In\" Right Now"),
+        (KingdomName::Lake, "That \"Old West\" Style"),
+        (KingdomName::Wooded, "Exploring for Treasure"),
+        (KingdomName::Snow, "I'm Not Cold!"),
+    ]),
+    ("Wearing it Perfect!", Tag::Costume, &[
+        (KingdomName::Seaside, "A Relaxing Dance"),
+        (KingdomName::Luncheon, "Mechanic: Repairs Complete!"),
+        (KingdomName::Bowser, "Scene of Crossing the Poison Swamp"),
+        (KingdomName::Bowser, "Taking Notes: In the Folding Screen"),
+        (KingdomName::Moon, "Doctor in the House"),
+        (KingdomName::Mushroom, "Totally Classic"),
+        (KingdomName::Mushroom, "Courtyard Chest Trap"),
+    ]),
+    ("Hat-Seeking Missile", Tag::HatAndSeek, &[
+        (KingdomName::Sand, "Hat-and-Seek: In the Sand"),
+        (KingdomName::Metro, "Hat-and-Seek: In the City"),
+        (KingdomName::Metro, "Hat-and-Seek: In the Crowd"),
+        (KingdomName::Snow, "Hat-and-Seek in the Snow"),
+        (KingdomName::Luncheon, "Hat-and-Seek: Among the Food"),
+        (KingdomName::Mushroom, "Hat-and-Seek: Mushroom Kingdom"),
+    ]),
+    ("Music Maestro", Tag::Jammin, &[
+        (KingdomName::Sand, "Jammin' in the Sand Kingdom"),
+        (KingdomName::Wooded, "Jammin' in the Wooded Kingdom"),
+        (KingdomName::Metro, "Jammin' in the Metro Kingdom"),
+        (KingdomName::Luncheon, "Jammin' in the Luncheon Kingdom"),
+        (KingdomName::Mushroom, "Jammin' in the Mushroom Kingdom"),
+    ]),
+    ("Art Enthusiast", Tag::Art, &[
+        (KingdomName::Moon, "Found with Cap Kingdom Art"),
+        (KingdomName::Bowser, "Found with Sand Kingdom Art"),
+        (KingdomName::Cascade, "Found with Lake Kingdom Art"),
+        (KingdomName::Lake, "Found With Metro Kingdom Art"),
+        (KingdomName::Lost, "Found with Snow Kingdom Art"),
+    ]),
+    ("Art Investigator", Tag::Art, &[
+        (KingdomName::Metro, "Found with Seaside Kingdom Art"),
+        (KingdomName::Seaside, "Found with Luncheon Kingdom Art"),
+        (KingdomName::Sand, "Found with Bowser's Kingdom Art"),
+        (KingdomName::Wooded, "Found with Moon Kingdom Art"),
+        (KingdomName::Cap, "Found with Mushroom kingdom Art"),
+        (KingdomName::Cascade, "Found with Dark Side Art 1"),
+        (KingdomName::Metro, "Found with Dark Side Art 2"),
+        (KingdomName::Mushroom, "Found with Dark Side Art 3"),
+        (KingdomName::Cloud, "Found with Dark Side Art 4"),
+        (KingdomName::Snow, "Found with Dark Side Art 5"),
+        (KingdomName::Seaside, "Found with Dark Side Art 6"),
+        (KingdomName::Lost, "Found with Dark Side Art 7"),
+        (KingdomName::Luncheon, "Found with Dark Side Art 8"),
+        (KingdomName::Lake, "Found with Dark Side Art 9"),
+        (KingdomName::Ruined, "Found with Dark Side Art 10"),
+    ]),
+    ("Slots Machine", Tag::Slots, &[
+        (KingdomName::Sand, "Sand Kingdom Slots"),
+        (KingdomName::Metro, "Metro Kingdom Slots"),
+        (KingdomName::Luncheon, "Luncheon Kingdom Slots"),
+    ]),
+    ("Koopa Freerunning MVP", Tag::KoopaFreerunning, &[
+        (KingdomName::Cap, "Cap Kingdom Regular Cup"),
+        (KingdomName::Cascade, "Cascade Kingdom Regular Cup"),
+        (KingdomName::Sand, "Sand Kingdom Regular Cup"),
+        (KingdomName::Lake, "Lake Kingdom Regular Cup"),
+        (KingdomName::Wooded, "Wooded Kingdom Regular Cup"),
+        (KingdomName::Lost, "Lost Kingdom Regular Cup"),
+        (KingdomName::Metro, "Moon Kingdom Regular Cup"),
+        (KingdomName::Snow, "Snow Kingdom Regular Cup"),
+        (KingdomName::Seaside, "Seaside Kingdom Regular Cup"),
+        (KingdomName::Bowser, "Bowser's Kingdom Regular Cup"),
+        (KingdomName::Moon, "Moon Kingdom Regular Cup!"),
+    ]),
+    ("Koopa Freerunning Hall of Famer", Tag::KoopaFreerunning, &[
+        (KingdomName::Cap, "Cap Kingdom Master Cup"),
+        (KingdomName::Cascade, "Cascade Kingdom Master Cup"),
+        (KingdomName::Sand, "Sand Kingdom Master Cup"),
+        (KingdomName::Lake, "Lake Kingdom Master Cup"),
+        (KingdomName::Wooded, "Wooded Kingdom Master Cup"),
+        (KingdomName::Lost, "Lost Kingdom Master Cup"),
+        (KingdomName::Metro, "Metro Kingdom Master Cup"),
+        (KingdomName::Snow, "Snow Kingdom Master Cup"),
+        (KingdomName::Seaside, "Seaside Kingdom Master Cup"),
+        (KingdomName::Bowser, "Bowser's Kingdom Master Cup"),
+        (KingdomName::Moon, "Moon Kingdom Master Cup"),
+        (KingdomName::Mushroom, "Mushroom Kingdom Regular Cup"),
+        (KingdomName::Mushroom, "Mushroom Kingdom Master Cup"),
+    ]),
+    ("Supernaturally Sure-Footed", Tag::Walking, &[
+        (KingdomName::Sand, "Walking the Desert"),
+        (KingdomName::Sand, "More Walking in the Desert!"),
+        (KingdomName::Snow, "Walking on Ice!"),
+        (KingdomName::Snow, "Even More Walking on Ice"),
+        (KingdomName::Moon, "Walking on the Moon!"),
+        (KingdomName::Moon, "Walking on the Moon: Again!"),
+    ]),
+    ("Quizmaster", Tag::Quiz, &[
+        (KingdomName::Sand, "Sand Quiz: Wonderful!"),
+        (KingdomName::Seaside, "Ocean Quiz: Good!"),
+        (KingdomName::Moon, "Moon Quiz: Amazing!"),
+    ]),
+    ("World Warper", Tag::SecretPath, &[
+        (KingdomName::Cascade, "Secret Path to Fossil Falls"),
+        (KingdomName::Sand, "Secret Path to Tostarena!"),
+        (KingdomName::Lake, "Secret Path to Lake Lamode!"),
+        (KingdomName::Wooded, "Secret Path to Steam Gardens!"),
+        (KingdomName::Metro, "Secret Path to New Donk City!"),
+        (KingdomName::Snow, "Secret Path to Shiveria"),
+        (KingdomName::Seaside, "Secret Path to Bubblaine!"),
+        (KingdomName::Luncheon, "Secret Path to Mount Volbono!"),
+        (KingdomName::Bowser, "Secret Path to Bowser's Castle"),
+        (KingdomName::Mushroom, "Secret Path to Peach's Castle!"),
+    ]),
+];
+
+fn achievement_rules(moons: &Moons) -> Vec<(String, Tag, u16)> {
+    moons.iter()
+        .filter(|(_, m)| m.achievement())
+        .flat_map(|(_, m)| {
+            m.prerequisites().iter()
+                .flat_map(|r| r.tags())
+                .map(move |(t, c)| (String::from(m.name()), t, c))
+        })
+        .collect()
+}
+
+#[test]
+fn achievement_counts_match_hardcoded_lists() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let rules = achievement_rules(&moons);
+    assert_eq!(rules.len(), HARDCODED.len());
+
+    // each tier needs every moon listed for it and the tiers before it
+    let mut totals: HashMap<Tag, u16> = HashMap::new();
+    for ((name, tag, count), (old_name, old_tag, old_moons)) in
+        rules.iter().zip(HARDCODED.iter()) {
+        let total = totals.entry(*old_tag).or_insert(0);
+        *total += old_moons.len() as u16;
+        assert_eq!(name, old_name);
+        assert_eq!(tag, old_tag);
+        assert_eq!(count, total, "{}", name);
+    }
+}
+
+#[test]
+fn tagged_moons_match_hardcoded_lists() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let mut tagged: HashMap<Tag, Vec<(KingdomName, String)>> = HashMap::new();
+    for (_, m) in moons.iter() {
+        for t in m.tags() {
+            tagged.entry(*t).or_default()
+                .push((m.kingdom(), String::from(m.name())));
+        }
+    }
+    let mut listed: HashMap<Tag, Vec<(KingdomName, String)>> = HashMap::new();
+    for (_, tag, old_moons) in HARDCODED {
+        for (k, name) in old_moons.iter() {
+            listed.entry(*tag).or_default().push((*k, String::from(*name)));
+        }
+    }
+    for (tag, mut old_moons) in listed {
+        let mut new_moons = tagged.remove(&tag).unwrap_or_default();
+        old_moons.sort_by(|a, b| a.1.cmp(&b.1));
+        new_moons.sort_by(|a, b| a.1.cmp(&b.1));
+        assert_eq!(new_moons, old_moons, "{:?}", tag);
+    }
+    assert!(tagged.is_empty(), "tags without an achievement: {:?}",
+            tagged.keys().collect::<Vec<_>>());
+}
+
+#[test]
+fn achievements_only_need_tags_and_earlier_achievements() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    for (_, m) in moons.iter().filter(|(_, m)| m.achievement()) {
+        for r in m.prerequisites() {
+            if let Requirement::Moon(p) = r {
+                assert!(moons.moon(*p).unwrap().achievement()
+                        || moons.moon(*p).unwrap().name() == "Achieve World Peace",
+                        "{} lists {}", m.name(), moons.moon(*p).unwrap().name());
+            }
+        }
+    }
+}
+
+#[test]
+fn tagged_moons_are_counted_rather_than_all_needed() {
+    // any ten flat moons will do, so none of them is a prerequisite
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let finder = moons.find("Flat Moon Finder")[0];
+    let shopper = moons.find("Regional Coin Shopper")[0];
+    assert_eq!(moons.prerequisites(finder), &vec![shopper]);
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::fs;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use odyssey_randomizer::bingo::{Board, Timings};
+use odyssey_randomizer::data;
+use odyssey_randomizer::estimate::Estimates;
+use odyssey_randomizer::kingdom::Kingdoms;
+use odyssey_randomizer::moon::Moons;
+
+#[test]
+fn every_line_is_within_target() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let estimates = Estimates::new(&moons);
+    let timings = Timings::new(&kingdoms, &moons, &estimates).unwrap();
+    for seed in 0..20 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let board = Board::generate(&kingdoms, &moons, &estimates, 5, 60 * 60, &mut rng)
+            .unwrap();
+        assert_eq!(board.moons().len(), 25);
+        let mut unique = board.moons().to_vec();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), 25, "seed {}", seed);
+        assert_eq!(board.lines().len(), 12);
+        for line in board.lines() {
+            assert!(timings.line(&line) <= 60 * 60, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn impossible_target_is_an_error() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let estimates = Estimates::new(&moons);
+    let mut rng = StdRng::seed_from_u64(0);
+    assert!(Board::generate(&kingdoms, &moons, &estimates, 5, 60, &mut rng).is_err());
+}
+
+#[test]
+fn any_takes_one_of_its_branches() {
+    // the first moon needs one of two others, so it can't be quicker than
+    // the quicker of them with its own time on top
+    let mut text = fs::read_to_string("data/odyssey.txt").unwrap();
+    text = text.replacen("\"Frog-Jumping Above the Fog\"\n    needs kingdom Cascade",
+                         "\"Frog-Jumping Above the Fog\"\n    needs all (kingdom Cascade) \
+                          (any (moon cap2) (moon cap3))", 1);
+    let path = env::temp_dir().join(format!("odyssey_bingo_any_{}.txt", std::process::id()));
+    fs::write(&path, text).unwrap();
+    let read = data::read(&path.to_string_lossy());
+    fs::remove_file(&path).unwrap();
+    let data = read.unwrap();
+    let kingdoms = Kingdoms::load(&data).unwrap();
+    let moons = Moons::load(&kingdoms, &data).unwrap();
+    let estimates = Estimates::new(&moons);
+    let timings = Timings::new(&kingdoms, &moons, &estimates).unwrap();
+    let first = moons.find("Frog-Jumping Above the Fog")[0];
+    let deck = moons.find("Frog-Jumping from the Top Deck")[0];
+    let timer = moons.find("Cap Kingdom Timer Challenge 1")[0];
+    let quicker = std::cmp::min(timings.line(&[deck]), timings.line(&[timer]));
+    assert!(timings.line(&[first]) >= quicker + estimates.moon(first).unwrap());
+}
@@ -0,0 +1,103 @@
+use std::env;
+use std::fs;
+
+use odyssey_randomizer::data;
+use odyssey_randomizer::error::RandomizerError;
+use odyssey_randomizer::estimate::Estimates;
+use odyssey_randomizer::kingdom::Kingdoms;
+use odyssey_randomizer::moon::Moons;
+
+// the built in data with some lines swapped out, written where the loader can
+// read it
+fn custom_data(name: &str, changes: &[(&str, &str)]) -> String {
+    let mut text = fs::read_to_string("data/odyssey.txt").unwrap();
+    for (from, to) in changes {
+        assert!(text.contains(from), "no {:?} in the data", from);
+        text = text.replacen(from, to, 1);
+    }
+    let path = env::temp_dir().join(format!("odyssey_{}_{}.txt", name, std::process::id()));
+    fs::write(&path, text).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+fn load(path: &str) -> Result<(Kingdoms, Moons), RandomizerError> {
+    let data = data::read(path)?;
+    let kingdoms = Kingdoms::load(&data)?;
+    let moons = Moons::load(&kingdoms, &data)?;
+    Ok((kingdoms, moons))
+}
+
+#[test]
+fn any_only_needs_one_branch() {
+    // the first moon can be had from Cascade without the second, so the
+    // second needing the first isn't a cycle
+    let path = custom_data("any", &[
+        ("\"Frog-Jumping Above the Fog\"\n    needs kingdom Cascade",
+         "\"Frog-Jumping Above the Fog\"\n    needs any (moon cap2) (kingdom Cascade)"),
+        ("\"Frog-Jumping from the Top Deck\"\n    needs kingdom Cascade",
+         "\"Frog-Jumping from the Top Deck\"\n    needs moon cap1"),
+    ]);
+    let loaded = load(&path);
+    fs::remove_file(&path).unwrap();
+    let (_, moons) = loaded.unwrap();
+    let first = moons.find("Frog-Jumping Above the Fog")[0];
+    let second = moons.find("Frog-Jumping from the Top Deck")[0];
+    assert!(moons.prerequisites(first).is_empty());
+    assert_eq!(moons.prerequisites(second), &vec![first]);
+}
+
+#[test]
+fn cycles_are_refused() {
+    let path = custom_data("cycle", &[
+        ("\"Frog-Jumping Above the Fog\"\n    needs kingdom Cascade",
+         "\"Frog-Jumping Above the Fog\"\n    needs moon cap2"),
+        ("\"Frog-Jumping from the Top Deck\"\n    needs kingdom Cascade",
+         "\"Frog-Jumping from the Top Deck\"\n    needs moon cap1"),
+    ]);
+    let loaded = load(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(loaded.err(), Some(RandomizerError::UnsatisfiableData(
+        String::from("Frog-Jumping Above the Fog depends on itself"))));
+}
+
+#[test]
+fn nested_phase_only_is_taken_before_the_phase_ends() {
+    let path = custom_data("phase_only", &[
+        ("\"Inside an Iron Girder\"\n",
+         "\"Inside an Iron Girder\"\n    needs all (phase-only Metro 0) (kingdom Metro)\n"),
+        ("\"Swaying in the Breeze\"\n",
+         "\"Swaying in the Breeze\"\n    needs any (phase-only Metro 0) (moon metro8)\n"),
+    ]);
+    let read = data::read(&path);
+    fs::remove_file(&path).unwrap();
+    let data = read.unwrap();
+    let id = |key: &str| data.moons.iter().position(|m| m.key == key).unwrap();
+    let festival = &data.moons[id("metro7")];
+    for key in ["metro8", "metro9"].iter() {
+        assert!(festival.prerequisites.iter()
+                .any(|r| matches!(r, data::RequirementData::Moon(m) if *m == id(key))),
+                "{} is not needed before the festival", key);
+    }
+}
+
+#[test]
+fn zero_kingdom_visits_are_refused() {
+    let path = custom_data("zero_visits", &[
+        ("\"Frog-Jumping Above the Fog\"\n    needs kingdom Cascade",
+         "\"Frog-Jumping Above the Fog\"\n    needs kingdom Cascade 0"),
+    ]);
+    let read = data::read(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(read.err(), Some(RandomizerError::Parse { ref message, .. })
+                     if message == "kingdom visits start at 1"));
+}
+
+#[test]
+fn times_for_other_data_are_an_error() {
+    // an id past the moons the times were made for
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let estimates = Estimates::new(&moons);
+    let past = moons.iter().count();
+    assert_eq!(estimates.moon(past), Err(RandomizerError::BadMoonId(past)));
+}
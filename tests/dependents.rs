@@ -0,0 +1,22 @@
+use odyssey_randomizer::kingdom::Kingdoms;
+use odyssey_randomizer::moon::Moons;
+
+#[test]
+fn rescuing_peach_opens_the_post_game() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let peach = moons.find("Rescue Princess Peach")[0];
+    let treasure = moons.find("The Forgotten Treasure")[0];
+    assert!(moons.transitive_dependents(peach).contains(&treasure));
+}
+
+#[test]
+fn exit_moons_open_the_next_kingdoms() {
+    // a sand moon that needs nothing but being there, which the cascade
+    // exit moon leads to
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let exit = moons.find("Multi Moon Atop the Falls")[0];
+    let sand = moons.find("Atop the Highest Tower")[0];
+    assert!(moons.dependents(exit).contains(&sand));
+}
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use odyssey_randomizer::kingdom::{Kingdoms, KingdomName};
+use odyssey_randomizer::moon::{MoonPool, Moons};
+use odyssey_randomizer::outfit::Outfit;
+use odyssey_randomizer::route;
+use odyssey_randomizer::state::State;
+
+fn generate(kingdoms: &Kingdoms, moons: &Moons, seed: u64, curve: f64) -> State {
+    let mut state = State::new();
+    state.set_difficulty_curve(curve);
+    let mut rng = StdRng::seed_from_u64(seed);
+    route::generate(kingdoms, moons, MoonPool::new(moons), state, &mut rng)
+        .unwrap()
+}
+
+// play the route back into a fresh state one visit and one moon at a time,
+// checking each moon and each departure against the state at that point
+fn replay(kingdoms: &Kingdoms, moons: &Moons, route: &State) -> Result<(), TestCaseError> {
+    let mut state = State::new();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut ordered = route.moons_ordered().iter().peekable();
+    for (x, k) in route.kingdoms_ordered().iter().enumerate() {
+        if x > 0 && !state.completed_main_game() {
+            prop_assert!(kingdoms.kingdom(state.current_kingdom()).unwrap().can_leave(&state),
+                         "left {:?} on visit {} before it could be left",
+                         state.current_kingdom(), x);
+        }
+        // a single choice leaves nothing to chance, and a route can pick
+        // the kingdom it is already in, which is staying put
+        if *k != state.current_kingdom() {
+            state.add_kingdom_to_schedule(*k);
+            state.schedule_kingdom(kingdoms, &mut rng).unwrap();
+        }
+        // visits count multi moons as more than one, and route steps that
+        // aren't moons as none, those go with the visit they follow
+        let mut collected = 0;
+        while let Some(id) = ordered.next_if(|m| {
+            let moon = moons.moon(**m).unwrap();
+            collected < route.moons_per_visit()[x]
+                || (moon.count() == 0 && moon.kingdom() == *k)
+        }) {
+            let moon = moons.moon(*id).unwrap();
+            collected += moon.count();
+            prop_assert!(moon.available(&state), "{} was not available on visit {}",
+                         moon.name(), x + 1);
+            let outfits: Vec<Outfit> = moon.outfits(&state).collect();
+            for o in outfits {
+                state.buy_outfit(o);
+            }
+            state.add_moon_to_schedule(*id);
+            state.schedule_moon(moons, &mut rng).unwrap();
+        }
+    }
+    Ok(())
+}
+
+// how many moons had been collected before the first visit to a kingdom
+fn moons_before(route: &State, kingdom: KingdomName) -> Option<u16> {
+    let x = route.kingdoms_ordered().iter().position(|k| *k == kingdom)?;
+    Some(route.moons_per_visit()[..x].iter().sum())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn every_moon_is_scheduled_once(seed in any::<u64>(), curve in 0.0..3.0) {
+        let kingdoms = Kingdoms::new();
+        let moons = Moons::new(&kingdoms).unwrap();
+        let route = generate(&kingdoms, &moons, seed, curve);
+        let unique: HashSet<_> = route.moons_ordered().iter().collect();
+        prop_assert_eq!(unique.len(), route.moons_ordered().len());
+        prop_assert_eq!(unique.len(), moons.iter().count());
+    }
+
+    #[test]
+    fn moons_and_departures_follow_the_rules(seed in any::<u64>(), curve in 0.0..3.0) {
+        let kingdoms = Kingdoms::new();
+        let moons = Moons::new(&kingdoms).unwrap();
+        let route = generate(&kingdoms, &moons, seed, curve);
+        replay(&kingdoms, &moons, &route)?;
+    }
+
+    #[test]
+    fn captures_are_learned_before_they_are_needed(seed in any::<u64>(), curve in 0.0..3.0) {
+        let kingdoms = Kingdoms::new();
+        let moons = Moons::new(&kingdoms).unwrap();
+        let route = generate(&kingdoms, &moons, seed, curve);
+        let mut learned = HashSet::new();
+        for id in route.moons_ordered() {
+            let moon = moons.moon(*id).unwrap();
+            for c in moon.prerequisites().iter().flat_map(|r| r.captures()) {
+                prop_assert!(learned.contains(&c), "{} came before learning {}",
+                             moon.name(), c.name());
+            }
+            learned.extend(moon.captures().iter().copied());
+        }
+    }
+
+    #[test]
+    fn late_kingdoms_come_after_their_unlocks(seed in any::<u64>()) {
+        let kingdoms = Kingdoms::new();
+        let moons = Moons::new(&kingdoms).unwrap();
+        let route = generate(&kingdoms, &moons, seed, 0.0);
+        let visits = route.kingdoms_ordered();
+        let first = |k| visits.iter().position(|v| *v == k);
+        prop_assert!(first(KingdomName::Mushroom) > first(KingdomName::Moon));
+        prop_assert!(moons_before(&route, KingdomName::Dark).unwrap() >= 250);
+        prop_assert!(moons_before(&route, KingdomName::Darker).unwrap() >= 500);
+    }
+}
+
+#[test]
+fn outfits_wait_for_regional_coins() {
+    let kingdoms = Kingdoms::new();
+    let mut state = State::new();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut visit = |state: &mut State, k| {
+        state.add_kingdom_to_schedule(k);
+        state.schedule_kingdom(&kingdoms, &mut rng).unwrap();
+    };
+    // one visit to Cascade doesn't find enough for the outfit
+    visit(&mut state, KingdomName::Cascade);
+    let found = state.regional_coins(KingdomName::Cascade);
+    assert!(found < Outfit::Caveman.cost());
+    state.buy_outfit(Outfit::Caveman);
+    assert!(!state.can_wear(Outfit::Caveman));
+    assert_eq!(state.regional_coins(KingdomName::Cascade), found);
+
+    visit(&mut state, KingdomName::Cap);
+    visit(&mut state, KingdomName::Cascade);
+    let found = state.regional_coins(KingdomName::Cascade);
+    state.buy_outfit(Outfit::Caveman);
+    assert!(state.can_wear(Outfit::Caveman));
+    assert_eq!(state.regional_coins(KingdomName::Cascade), found - Outfit::Caveman.cost());
+}
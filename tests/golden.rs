@@ -0,0 +1,46 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use odyssey_randomizer::estimate::Estimates;
+use odyssey_randomizer::kingdom::Kingdoms;
+use odyssey_randomizer::moon::{MoonPool, Moons};
+use odyssey_randomizer::route;
+use odyssey_randomizer::score::Score;
+use odyssey_randomizer::state::State;
+
+// routes for these seeds are pinned in tests/snapshots, so any change to the
+// data or the generator that moves a moon shows up as a diff to review, and
+// intended changes are accepted with cargo insta review
+const SEEDS: &[u64] = &[0, 1, 2019, 836];
+
+#[test]
+fn routes_match_golden_output() {
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let estimates = Estimates::new(&moons);
+    for seed in SEEDS {
+        let mut rng = StdRng::seed_from_u64(*seed);
+        let state = route::generate(&kingdoms, &moons, MoonPool::new(&moons),
+                                    State::new(), &mut rng)
+            .unwrap();
+        insta::assert_snapshot!(format!("seed_{}", seed),
+                                state.route_text(&kingdoms, &moons, &estimates).unwrap());
+    }
+}
+
+#[test]
+fn score_counts_the_printed_visits() {
+    // passing through a kingdom without collecting anything isn't a visit
+    let kingdoms = Kingdoms::new();
+    let moons = Moons::new(&kingdoms).unwrap();
+    let estimates = Estimates::new(&moons);
+    let mut rng = StdRng::seed_from_u64(0);
+    let state = route::generate(&kingdoms, &moons, MoonPool::new(&moons), State::new(),
+                                &mut rng)
+        .unwrap();
+    let text = state.route_text(&kingdoms, &moons, &estimates).unwrap();
+    let sections = text.lines().filter(|l| l.starts_with("\t(visit ")).count();
+    let score = Score::new(&moons, &state).unwrap();
+    assert_eq!(score.visits(), sections);
+    assert_eq!(sections, 60);
+}